@@ -0,0 +1,130 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    time::SystemTime
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Europe::Moscow;
+use serde::{Deserialize, Serialize};
+
+use crate::bot::{add_info, read_json};
+use crate::error::{Error, Result};
+
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Trader,
+    Admin,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct User {
+    pub id: u64,
+    pub role: Role,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Allow-list of telegram users, stored in `add_info/access.json`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Access {
+    pub users: Vec<User>,
+    /// Private or group chats that get the bot notifications.
+    pub notify_chats: Vec<i64>,
+}
+
+impl Access {
+    const FILE: &'static str = "access.json";
+
+    pub fn load() -> Result<Access> {
+        read_json(Self::FILE)
+    }
+
+    /// The single-user setup: the owner of the private chat is an admin.
+    /// A group chat has a negative id and no owner, its users go to `access.json` by hand.
+    pub fn owner(chat_id: Option<i64>) -> Result<Access> {
+        match chat_id {
+            Some(id) if id < 0 => Err(Error::Config(format!(
+                "TG_CHAT_ID {} is a group chat, set a user id or fill add_info/access.json", id))),
+            Some(id) => Ok(Access {
+                users: vec![User { id: id as u64, role: Role::Admin, name: String::from("owner") }],
+                notify_chats: vec![id],
+            }),
+            None => Ok(Access::default()),
+        }
+    }
+
+    pub fn role(&self, user_id: u64) -> Option<Role> {
+        self.users.iter()
+            .find(|user| user.id == user_id)
+            .map(|user| user.role)
+    }
+
+    pub fn is_allowed(&self, user_id: u64, required: Role) -> bool {
+        matches!(self.role(user_id), Some(role) if role >= required)
+    }
+}
+
+/// Appends the command to `add_info/audit.log`.
+pub fn audit(user_id: u64, username: &str, chat_id: i64, command: &str, allowed: bool) {
+    let line = audit_line(SystemTime::now(), user_id, username, chat_id, command, allowed);
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(add_info("audit.log"))
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(err) = result {
+        tracing::error!(%err, "can't write audit log");
+    }
+}
+
+fn audit_line(time: SystemTime, user_id: u64, username: &str, chat_id: i64,
+              command: &str, allowed: bool) -> String {
+    let time: DateTime<Utc> = time.into();
+    // quoted and escaped, a tab or a newline in the text makes no fields or lines
+    format!("{}\tuser={}\tname={:?}\tchat={}\tcommand={:?}\t{}\n",
+            time.with_timezone(&Moscow).format("%Y-%m-%d %H:%M:%S"),
+            user_id, username, chat_id, command,
+            if allowed { "allowed" } else { "denied" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles() {
+        let access: Access = serde_json::from_str(r#"{
+            "users": [
+                {"id": 1, "role": "viewer"},
+                {"id": 2, "role": "trader", "name": "trader"},
+                {"id": 3, "role": "admin"}
+            ],
+            "notify_chats": [-100]
+        }"#).unwrap();
+        assert!(access.is_allowed(1, Role::Viewer));
+        assert!(!access.is_allowed(1, Role::Trader));
+        assert!(access.is_allowed(2, Role::Trader));
+        assert!(!access.is_allowed(2, Role::Admin));
+        assert!(access.is_allowed(3, Role::Viewer));
+        assert!(access.is_allowed(3, Role::Admin));
+        assert!(!access.is_allowed(4, Role::Viewer));
+    }
+    #[test]
+    fn audit_format() {
+        let line = audit_line(SystemTime::UNIX_EPOCH, 2, "trader", -100, "/stop", false);
+        assert_eq!(line, "1970-01-01 03:00:00\tuser=2\tname=\"trader\"\tchat=-100\tcommand=\"/stop\"\tdenied\n");
+        let forged = audit_line(SystemTime::UNIX_EPOCH, 2, "a\tb", -100, "/stop\n1970-01-01\tuser=3", true);
+        assert_eq!(forged.matches('\t').count(), 5);
+        assert_eq!(forged.lines().count(), 1);
+        assert!(forged.contains("command=\"/stop\\n1970-01-01\\tuser=3\""));
+    }
+    #[test]
+    fn owner() {
+        assert_eq!(Access::owner(Some(42)).unwrap().role(42), Some(Role::Admin));
+        assert!(matches!(Access::owner(Some(-100)), Err(Error::Config(_))));
+        assert!(Access::owner(None).unwrap().users.is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::bot::{account_file, add_info, read_json};
+use crate::error::{Error, Result};
+use crate::grpc::Broker;
+use crate::tcs::{
+    AccessLevel, AccountStatus, AccountType, GetMarginAttributesResponse, MoneyValue,
+    Account as BrokerAccount
+};
+
+
+/// One entry of `add_info/accounts.json`: which strategy trades on which account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AccountConfig {
+    pub id: String,
+    /// See `strategies::by_name`.
+    pub strategy: String,
+    /// Directory in `add_info` for the statistics and order ids of the account,
+    /// empty means `add_info` itself.
+    #[serde(default)]
+    pub name: String,
+}
+
+impl AccountConfig {
+    const FILE: &'static str = "accounts.json";
+
+    /// Without `accounts.json` the single account from the credentials trades `scalp`.
+    pub fn load(default_id: Option<&str>) -> Result<Vec<AccountConfig>> {
+        let configs: Vec<AccountConfig> = if add_info(Self::FILE).exists() {
+            read_json(Self::FILE)?
+        } else {
+            let id = default_id.ok_or_else(|| Error::Config(
+                "no accounts: set T_ACCOUNT_ID or fill add_info/accounts.json".to_string()))?;
+            vec![AccountConfig { id: id.to_string(), strategy: "scalp".to_string(), name: String::new() }]
+        };
+        check_unique(&configs)?;
+        Ok(configs)
+    }
+}
+
+fn check_unique(configs: &[AccountConfig]) -> Result<()> {
+    if configs.is_empty() {
+        return Err(Error::Config("accounts.json is empty".to_string()));
+    }
+    for (i, config) in configs.iter().enumerate() {
+        for other in &configs[..i] {
+            if other.id == config.id || other.name == config.name {
+                return Err(Error::Config(format!(
+                    "accounts.json: {} and {} share the account or the directory", other.id, config.id)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A configured account checked against the broker.
+#[derive(Clone, Debug)]
+pub struct Account {
+    pub id: String,
+    /// `AccountConfig::name`, empty for the single-account setup.
+    pub name: String,
+    /// Name given in the broker app.
+    pub title: String,
+    pub kind: AccountType,
+    pub strategy: String,
+    /// Only margin accounts have them.
+    pub margin: Option<GetMarginAttributesResponse>,
+}
+
+impl Account {
+    /// Path of the account file relative to `add_info`.
+    pub fn file(&self, file: &str) -> String {
+        account_file(&self.name, file)
+    }
+}
+
+impl Display for Account {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            AccountType::Tinkoff => "брокерский",
+            AccountType::TinkoffIis => "ИИС",
+            AccountType::InvestBox => "инвесткопилка",
+            AccountType::Unspecified => "неизвестный",
+        };
+        write!(f, "Счёт: {} «{}», {}", self.id, self.title, kind)?;
+        if let Some(margin) = &self.margin {
+            let money = |m: &Option<MoneyValue>| m.as_ref()
+                .map(|m| format!("{}.{:02} {}", m.units, m.nano / 1_0000000, m.currency))
+                .unwrap_or_default();
+            write!(f, "\nЛиквидный портфель: {}, начальная маржа: {}",
+                   money(&margin.liquid_portfolio), money(&margin.starting_margin))?;
+        }
+        Ok(())
+    }
+}
+
+/// Refuses accounts the bot can't trade on.
+fn validate(config: &AccountConfig, account: &BrokerAccount) -> Result<()> {
+    if account.status != AccountStatus::Open as i32 {
+        return Err(Error::Config(format!("account {} is not open", config.id)));
+    }
+    match AccessLevel::from_i32(account.access_level) {
+        Some(AccessLevel::AccountAccessLevelFullAccess) => Ok(()),
+        Some(AccessLevel::AccountAccessLevelReadOnly) => Err(Error::Config(format!(
+            "the token has read-only access to account {}, orders need full access", config.id))),
+        _ => Err(Error::Config(format!("the token has no access to account {}", config.id))),
+    }
+}
+
+/// Finds the configured accounts among the accounts of the token.
+pub async fn discover(broker: &Broker, configs: Vec<AccountConfig>) -> Result<Vec<Account>> {
+    let info = broker.get_info().await?.into_inner();
+    info!(tariff = %info.tariff, qualified = info.qual_status, "user");
+    let accounts = broker.get_accounts().await?.into_inner().accounts;
+
+    let mut found = Vec::new();
+    for config in configs {
+        let account = accounts.iter()
+            .find(|account| account.id == config.id)
+            .ok_or_else(|| Error::Config(format!(
+                "account {} not found, the token has: {}", config.id,
+                accounts.iter().map(|a| a.id.as_str()).collect::<Vec<_>>().join(", "))))?;
+        validate(&config, account)?;
+        let margin = match broker.get_margin_attributes(config.id.clone()).await {
+            Ok(response) => Some(response.into_inner()),
+            Err(status) => {
+                debug!(account = %config.id, message = status.message(), "no margin attributes");
+                None
+            },
+        };
+        let account = Account {
+            id: config.id,
+            name: config.name,
+            title: account.name.clone(),
+            kind: AccountType::from_i32(account.r#type).unwrap_or(AccountType::Unspecified),
+            strategy: config.strategy,
+            margin,
+        };
+        info!(account = %account.id, title = %account.title, kind = ?account.kind,
+              strategy = %account.strategy, "account");
+        found.push(account);
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{Request, Response, Status, transport::Server};
+    use crate::connection::{Connection, ConnectionSettings, Endpoint};
+    use crate::credentials::{Credentials, Secret};
+    use crate::tcs::{
+        GetAccountsRequest, GetAccountsResponse, GetInfoRequest, GetInfoResponse,
+        GetMarginAttributesRequest, GetUserTariffRequest, GetUserTariffResponse,
+        users_service_server::{UsersService, UsersServiceServer}
+    };
+
+    /// Stands in for the broker: the access level follows the token, as `GetAccounts` does.
+    struct StandIn;
+
+    #[tonic::async_trait]
+    impl UsersService for StandIn {
+        async fn get_accounts(&self, request: Request<GetAccountsRequest>)
+            -> Result<Response<GetAccountsResponse>, Status> {
+            let access_level = match request.metadata().get("authorization") {
+                Some(token) if token == "bearer full" => AccessLevel::AccountAccessLevelFullAccess,
+                _ => AccessLevel::AccountAccessLevelReadOnly,
+            };
+            Ok(Response::new(GetAccountsResponse {
+                accounts: vec![BrokerAccount {
+                    id: "2000000001".to_string(),
+                    r#type: AccountType::Tinkoff as i32,
+                    status: AccountStatus::Open as i32,
+                    access_level: access_level as i32,
+                    ..BrokerAccount::default()
+                }],
+            }))
+        }
+        async fn get_margin_attributes(&self, _: Request<GetMarginAttributesRequest>)
+            -> Result<Response<GetMarginAttributesResponse>, Status> {
+            Err(Status::invalid_argument("30051"))
+        }
+        async fn get_user_tariff(&self, _: Request<GetUserTariffRequest>)
+            -> Result<Response<GetUserTariffResponse>, Status> {
+            Err(Status::unimplemented(""))
+        }
+        async fn get_info(&self, _: Request<GetInfoRequest>)
+            -> Result<Response<GetInfoResponse>, Status> {
+            Ok(Response::new(GetInfoResponse::default()))
+        }
+    }
+
+    fn config(id: &str, name: &str) -> AccountConfig {
+        AccountConfig { id: id.to_string(), strategy: "scalp".to_string(), name: name.to_string() }
+    }
+
+    #[test]
+    fn validation() {
+        let mut account = BrokerAccount {
+            id: "1".to_string(),
+            r#type: AccountType::TinkoffIis as i32,
+            status: AccountStatus::Open as i32,
+            access_level: AccessLevel::AccountAccessLevelFullAccess as i32,
+            ..BrokerAccount::default()
+        };
+        assert!(validate(&config("1", ""), &account).is_ok());
+        account.access_level = AccessLevel::AccountAccessLevelReadOnly as i32;
+        assert!(matches!(validate(&config("1", ""), &account), Err(Error::Config(_))));
+        account.access_level = AccessLevel::AccountAccessLevelFullAccess as i32;
+        account.status = AccountStatus::Closed as i32;
+        assert!(validate(&config("1", ""), &account).is_err());
+    }
+    #[tokio::test]
+    async fn readonly_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Server::builder()
+            .add_service(UsersServiceServer::new(StandIn))
+            .serve_with_incoming(TcpListenerStream::new(listener)));
+
+        let settings = ConnectionSettings {
+            endpoint: Endpoint::Custom(format!("http://{}", addr)),
+            request_timeout: Duration::from_secs(2),
+            ..ConnectionSettings::default()
+        };
+        let credentials = Credentials {
+            token: Secret::new("full".to_string()),
+            readonly_token: Some(Secret::new("readonly".to_string())),
+            telegram_token: None,
+            account_id: None,
+            chat_id: None,
+            dashboard_token: None,
+        };
+        let broker = Broker::new(settings.channel().unwrap(), &credentials, Connection::new());
+        let accounts = discover(&broker, vec![config("2000000001", "")]).await.unwrap();
+        assert_eq!(accounts[0].id, "2000000001");
+        assert!(accounts[0].margin.is_none());
+    }
+    #[test]
+    fn unique() {
+        assert!(check_unique(&[config("1", "iis"), config("2", "broker")]).is_ok());
+        assert!(check_unique(&[config("1", "iis"), config("1", "broker")]).is_err());
+        assert!(check_unique(&[config("1", ""), config("2", "")]).is_err());
+        assert!(check_unique(&[]).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+//! Performance of the closed trades from `Statistics`. The results are `ProfitStat::net`,
+//! like on the equity chart.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use serde::Serialize;
+
+use crate::bot::{Statistics, TradeStat};
+use crate::tcs::Quotation;
+
+
+/// Upper bounds of the holding times, seconds.
+const HOLDING: [(u64, &str); 6] = [
+    (60, "< 1 мин"),
+    (5 * 60, "1-5 мин"),
+    (15 * 60, "5-15 мин"),
+    (60 * 60, "15-60 мин"),
+    (4 * 60 * 60, "1-4 ч"),
+    (u64::MAX, "> 4 ч"),
+];
+const TRADING_DAYS: f64 = 252.0;
+
+/// An empty float sum is -0.
+fn sum(values: impl Iterator<Item = f64>) -> f64 {
+    values.fold(0.0, |sum, value| sum + value)
+}
+
+/// Won over lost, infinite without losses.
+pub fn profit_factor(results: &[f64]) -> f64 {
+    let profit = sum(results.iter().copied().filter(|&r| r > 0.0));
+    let loss = -sum(results.iter().copied().filter(|&r| r < 0.0));
+    match (profit > 0.0, loss > 0.0) {
+        (_, true) => profit / loss,
+        (true, false) => f64::INFINITY,
+        (false, false) => 0.0,
+    }
+}
+
+/// Share of the positive results.
+pub fn win_rate(results: &[f64]) -> f64 {
+    if results.is_empty() {
+        return 0.0;
+    }
+    results.iter().filter(|&&r| r > 0.0).count() as f64 / results.len() as f64
+}
+
+fn price(&(units, nano): &(i64, i32)) -> f64 {
+    Quotation { units, nano }.to_f64()
+}
+
+/// Paid over the order prices, a better fill gives a negative one.
+fn slippage(trade: &TradeStat) -> f64 {
+    let side = if trade.direction { 1.0 } else { -1.0 };
+    let leg = |intended: &(i64, i32), fill: f64| if *intended == (0, 0) { 0.0 } else { fill - price(intended) };
+    side * (leg(&trade.intended_in, price(&trade.price_in)) - leg(&trade.intended_out, price(&trade.price_out)))
+        * trade.lots as f64
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Performance {
+    pub trades: usize,
+    pub net: f64,
+    /// The result after every trade.
+    pub equity: Vec<f64>,
+    /// The deepest fall of the equity from its peak.
+    pub max_drawdown: f64,
+    /// Over the daily results, annualized; zero with less than two days.
+    pub sharpe: f64,
+    /// Same with the losing days only in the deviation, zero without them.
+    pub sortino: f64,
+    pub win_rate: f64,
+    pub average_win: f64,
+    /// Negative.
+    pub average_loss: f64,
+    /// The average result of a trade.
+    pub expectancy: f64,
+    pub profit_factor: f64,
+    /// Share of the trading time in a position, none without the trading time.
+    pub exposure: Option<f64>,
+    /// Trades by the holding time, see `HOLDING`.
+    pub holding: Vec<(String, usize)>,
+    pub median_holding_secs: u64,
+    /// In total, see `slippage`.
+    pub slippage: f64,
+}
+
+impl Performance {
+    /// Of the trades in order, `trade_secs` is `Statistics::trade_secs` of them.
+    pub fn new(trades: &[&TradeStat], trade_secs: u32) -> Performance {
+        let results: Vec<f64> = trades.iter().map(|trade| trade.profit.net.to_f64()).collect();
+        let mut equity = Vec::with_capacity(results.len());
+        let (mut last, mut peak, mut max_drawdown) = (0.0, 0.0, 0.0_f64);
+        for result in &results {
+            last += result;
+            peak = f64::max(peak, last);
+            max_drawdown = max_drawdown.max(peak - last);
+            equity.push(last);
+        }
+
+        let mut days: Vec<(&str, f64)> = Vec::new();
+        for (trade, result) in trades.iter().zip(&results) {
+            match days.last_mut() {
+                Some((date, total)) if *date == trade.date => *total += result,
+                _ => days.push((&trade.date, *result)),
+            }
+        }
+        let daily: Vec<f64> = days.into_iter().map(|(_, result)| result).collect();
+        let n = daily.len() as f64;
+        let mean = sum(daily.iter().copied()) / n.max(1.0);
+        let deviation = if daily.len() > 1 {
+            (sum(daily.iter().map(|d| (d - mean).powi(2))) / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        let downside = (sum(daily.iter().map(|d| d.min(0.0).powi(2))) / n.max(1.0)).sqrt();
+        let annual = |deviation: f64| if daily.len() > 1 && deviation > 0.0 {
+            mean / deviation * TRADING_DAYS.sqrt()
+        } else {
+            0.0
+        };
+
+        let wins: Vec<f64> = results.iter().copied().filter(|&r| r > 0.0).collect();
+        let losses: Vec<f64> = results.iter().copied().filter(|&r| r < 0.0).collect();
+        let average = |values: &[f64]| sum(values.iter().copied()) / (values.len().max(1) as f64);
+
+        let held: u64 = trades.iter().map(|trade| trade.held_secs).sum();
+        let mut holds: Vec<u64> = trades.iter().map(|trade| trade.held_secs).collect();
+        holds.sort_unstable();
+        let holding = HOLDING.iter().enumerate()
+            .map(|(i, &(to, name))| {
+                let from = if i == 0 { 0 } else { HOLDING[i - 1].0 };
+                (name.to_string(), holds.iter().filter(|&&secs| from <= secs && secs < to).count())
+            })
+            .collect();
+
+        Performance {
+            trades: results.len(),
+            net: last,
+            equity,
+            max_drawdown,
+            sharpe: annual(deviation),
+            sortino: annual(downside),
+            win_rate: win_rate(&results),
+            average_win: average(&wins),
+            average_loss: average(&losses),
+            expectancy: average(&results),
+            profit_factor: profit_factor(&results),
+            exposure: (trade_secs > 0).then(|| (held as f64 / trade_secs as f64).min(1.0)),
+            holding,
+            median_holding_secs: holds.get(holds.len() / 2).copied().unwrap_or_default(),
+            slippage: sum(trades.iter().map(|trade| slippage(trade))),
+        }
+    }
+}
+
+/// Minutes or seconds.
+fn duration(secs: u64) -> String {
+    if secs < 60 { format!("{} с", secs) } else { format!("{} мин", secs / 60) }
+}
+
+impl Display for Performance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Сделок: {}, результат: {:.2}, просадка: {:.2}", self.trades, self.net, self.max_drawdown)?;
+        writeln!(f, "Шарп: {:.2}, Сортино: {:.2}", self.sharpe, self.sortino)?;
+        writeln!(f, "Прибыльных: {:.0}%, в среднем {:.2} / {:.2}",
+                 self.win_rate * 100.0, self.average_win, self.average_loss)?;
+        writeln!(f, "Ожидание: {:.2}, профит-фактор: {:.2}", self.expectancy, self.profit_factor)?;
+        if let Some(exposure) = self.exposure {
+            writeln!(f, "В позиции: {:.1}% времени", exposure * 100.0)?;
+        }
+        let holding: Vec<String> = self.holding.iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        writeln!(f, "Удержание: медиана {}; {}", duration(self.median_holding_secs), holding.join(", "))?;
+        write!(f, "Проскальзывание: {:.2}", self.slippage)
+    }
+}
+
+/// The performance of all the trades and of their strategies and instruments.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub total: Performance,
+    pub by_strategy: BTreeMap<String, Performance>,
+    pub by_instrument: BTreeMap<String, Performance>,
+}
+
+impl Summary {
+    pub fn new(stat: &Statistics) -> Summary {
+        Summary::of(&stat.trades.iter().collect::<Vec<_>>(), stat.trade_secs)
+    }
+
+    /// Of the trades of several accounts, the exposure is over their total trading time.
+    pub fn of(trades: &[&TradeStat], trade_secs: u32) -> Summary {
+        // the older statistics have no names
+        let name = |name: &str| if name.is_empty() { "?".to_string() } else { name.to_string() };
+        let by = |key: &dyn Fn(&TradeStat) -> String| {
+            let mut groups: BTreeMap<String, Vec<&TradeStat>> = BTreeMap::new();
+            for &trade in trades {
+                groups.entry(key(trade)).or_default().push(trade);
+            }
+            groups.into_iter()
+                .map(|(key, trades)| (key, Performance::new(&trades, 0)))
+                .collect()
+        };
+        Summary {
+            total: Performance::new(trades, trade_secs),
+            by_strategy: by(&|trade| name(&trade.strategy)),
+            by_instrument: by(&|trade| name(&trade.ticker)),
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.total)?;
+        for (title, groups) in [("По стратегиям", &self.by_strategy), ("По инструментам", &self.by_instrument)] {
+            // one group is the total again
+            if groups.len() < 2 {
+                continue;
+            }
+            write!(f, "\n{}:", title)?;
+            for (name, performance) in groups {
+                write!(f, "\n  {}: сделок {}, результат {:.2}, прибыльных {:.0}%, профит-фактор {:.2}",
+                       name, performance.trades, performance.net, performance.win_rate * 100.0,
+                       performance.profit_factor)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::ProfitStat;
+
+    fn trade(date: &str, net: f64, held_secs: u64, strategy: &str) -> TradeStat {
+        TradeStat {
+            date: date.to_string(),
+            profit: ProfitStat { net: Quotation::from_nanos((net * 1e9) as i128), ..ProfitStat::default() },
+            held_secs,
+            lots: 10,
+            direction: true,
+            price_in: (5, 91_0000000),
+            intended_in: (5, 90_0000000),
+            price_out: (5, 93_0000000),
+            intended_out: (5, 93_0000000),
+            ticker: "TRUR".to_string(),
+            strategy: strategy.to_string(),
+            ..TradeStat::default()
+        }
+    }
+
+    #[test]
+    fn performance() {
+        let trades = [
+            trade("2023-02-09", 10.0, 30, "scalp"),
+            trade("2023-02-09", -20.0, 120, "scalp"),
+            trade("2023-02-10", 30.0, 600, "trend"),
+            trade("2023-02-13", -5.0, 7200, "trend"),
+        ];
+        let trades: Vec<_> = trades.iter().collect();
+        let p = Performance::new(&trades, 10_000);
+        assert_eq!(p.trades, 4);
+        assert_eq!(p.equity, [10.0, -10.0, 20.0, 15.0]);
+        assert_eq!(p.net, 15.0);
+        assert_eq!(p.max_drawdown, 20.0);
+        assert_eq!(p.win_rate, 0.5);
+        assert_eq!((p.average_win, p.average_loss), (20.0, -12.5));
+        assert_eq!(p.expectancy, 3.75);
+        assert_eq!(p.profit_factor, 40.0 / 25.0);
+        assert_eq!(p.exposure, Some(0.795));
+        // the days: -10, 30, -5
+        let mean = 5.0;
+        let deviation = ((15.0f64.powi(2) + 25.0f64.powi(2) + 10.0f64.powi(2)) / 2.0).sqrt();
+        assert!((p.sharpe - mean / deviation * 252f64.sqrt()).abs() < 1e-9);
+        let downside = ((100.0 + 25.0) / 3.0f64).sqrt();
+        assert!((p.sortino - mean / downside * 252f64.sqrt()).abs() < 1e-9);
+        let counts: Vec<usize> = p.holding.iter().map(|(_, count)| *count).collect();
+        assert_eq!(counts, [1, 1, 1, 0, 1, 0]);
+        assert_eq!(p.median_holding_secs, 600);
+        // a tick worse on every entry, the exits are as intended
+        assert!((p.slippage - 4.0 * 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn breakdowns() {
+        let trades = [trade("2023-02-09", 10.0, 30, "scalp"), trade("2023-02-09", -4.0, 30, "")];
+        let summary = Summary::of(&trades.iter().collect::<Vec<_>>(), 0);
+        assert_eq!(summary.total.exposure, None);
+        assert_eq!(summary.by_strategy.keys().collect::<Vec<_>>(), ["?", "scalp"]);
+        assert_eq!(summary.by_strategy["scalp"].net, 10.0);
+        assert_eq!(summary.by_instrument.len(), 1);
+        let text = summary.to_string();
+        assert!(text.contains("По стратегиям:\n  ?: сделок 1, результат -4.00"), "{}", text);
+        assert!(!text.contains("По инструментам"));
+
+        let empty = Performance::new(&[], 0);
+        assert_eq!((empty.net, empty.sharpe, empty.profit_factor), (0.0, 0.0, 0.0));
+        assert_eq!(empty.to_string().lines().next(), Some("Сделок: 0, результат: 0.00, просадка: 0.00"));
+    }
+}
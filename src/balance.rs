@@ -0,0 +1,267 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    sync::Arc
+};
+use serde::Serialize;
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::grpc::Broker;
+use crate::tcs::{
+    GetLastPricesRequest, InstrumentStatus, InstrumentsRequest, MoneyValue, PositionsRequest,
+    PositionsResponse, Quotation, WithdrawLimitsRequest, WithdrawLimitsResponse
+};
+
+
+/// An amount with its currency, `rub`, `usd`, ... as the broker names them.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Money {
+    pub value: Quotation,
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(value: Quotation, currency: &str) -> Money {
+        Money { value, currency: currency.to_string() }
+    }
+}
+
+impl From<&MoneyValue> for Money {
+    fn from(money: &MoneyValue) -> Money {
+        Money::new(Quotation { units: money.units, nano: money.nano }, &money.currency.to_lowercase())
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.value, self.currency.to_uppercase())
+    }
+}
+
+/// Money of the account in one currency.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct CurrencyBalance {
+    /// Free to trade.
+    pub available: Quotation,
+    /// Reserved by active orders.
+    pub blocked: Quotation,
+    /// May be withdrawn, see `WithdrawLimits`.
+    pub withdrawable: Quotation,
+}
+
+/// All the money of the account by currency.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Balance {
+    pub currencies: BTreeMap<String, CurrencyBalance>,
+}
+
+impl Balance {
+    pub fn new(positions: &PositionsResponse, limits: &WithdrawLimitsResponse) -> Balance {
+        let mut currencies: BTreeMap<String, CurrencyBalance> = BTreeMap::new();
+        let mut add = |values: &[MoneyValue], field: fn(&mut CurrencyBalance) -> &mut Quotation| {
+            for value in values {
+                let money = Money::from(value);
+                let slot = field(currencies.entry(money.currency).or_default());
+                *slot = slot.clone() + money.value;
+            }
+        };
+        add(&positions.money, |c| &mut c.available);
+        add(&positions.blocked, |c| &mut c.blocked);
+        add(&limits.money, |c| &mut c.withdrawable);
+        Balance { currencies }
+    }
+
+    /// Available money in `currency` alone: the orders in it can't spend the other currencies
+    /// without an exchange first.
+    pub fn available(&self, currency: &str) -> Money {
+        let value = self.currencies.get(currency).map(|balance| balance.available.clone()).unwrap_or_default();
+        Money::new(value, currency)
+    }
+
+    /// Available money of every currency converted to `currency`, to show.
+    /// Currencies without a rate are left out.
+    pub fn available_in(&self, currency: &str, rates: &Rates) -> Money {
+        let value = self.currencies.iter()
+            .filter_map(|(from, balance)| rates.convert(&balance.available, from, currency))
+            .fold(Quotation::default(), |sum, value| sum + value);
+        Money::new(value, currency)
+    }
+}
+
+impl Display for Balance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.currencies.is_empty() {
+            return write!(f, "денег нет");
+        }
+        let text = self.currencies.iter()
+            .map(|(currency, balance)| {
+                let mut text = Money::new(balance.available.clone(), currency).to_string();
+                if balance.blocked != Quotation::default() {
+                    text.push_str(&format!(" (заблокировано {})", balance.blocked));
+                }
+                text
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{}", text)
+    }
+}
+
+/// Prices of the currencies in RUB.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rates {
+    rub: BTreeMap<String, Quotation>,
+}
+
+impl Rates {
+    pub fn new(prices: impl IntoIterator<Item = (String, Quotation)>) -> Rates {
+        let mut rub: BTreeMap<_, _> = prices.into_iter().collect();
+        rub.insert("rub".to_string(), Quotation { units: 1, nano: 0 });
+        Rates { rub }
+    }
+
+    pub fn convert(&self, amount: &Quotation, from: &str, to: &str) -> Option<Quotation> {
+        if from == to {
+            return Some(amount.clone());
+        }
+        let (from, to) = (self.rub.get(from)?.to_nanos(), self.rub.get(to)?.to_nanos());
+        if to == 0 {
+            return None;
+        }
+        Some(Quotation::from_nanos(amount.to_nanos() * from / to))
+    }
+}
+
+/// A currency instrument, `USD000UTSTOM` for `usd`.
+#[derive(Clone, Debug)]
+struct CurrencyInstrument {
+    uid: String,
+    /// The price is for this many units, e.g. 100 for some currencies.
+    nominal: i64,
+}
+
+/// Loads the balances and converts them through the last prices of the currency instruments.
+#[derive(Clone)]
+pub struct Wallet {
+    broker: Broker,
+    /// By currency, looked up once for all the clones.
+    instruments: Arc<OnceCell<BTreeMap<String, CurrencyInstrument>>>,
+}
+
+impl Wallet {
+    pub fn new(broker: Broker) -> Wallet {
+        Wallet { broker, instruments: Arc::new(OnceCell::new()) }
+    }
+
+    pub async fn balance(&self, account_id: &str) -> Result<Balance> {
+        let positions = self.broker.get_positions(PositionsRequest {
+            account_id: account_id.to_string(),
+        }).await?;
+        let limits = self.broker.get_withdraw_limits(WithdrawLimitsRequest {
+            account_id: account_id.to_string(),
+        }).await?;
+        Ok(Balance::new(positions.get_ref(), limits.get_ref()))
+    }
+
+    pub async fn rates(&self, currencies: &[&str]) -> Result<Rates> {
+        let instruments = self.instruments.get_or_try_init(|| async {
+            let response = self.broker.get_currencies(InstrumentsRequest {
+                instrument_status: InstrumentStatus::Base.into(),
+            }).await?;
+            Ok::<_, Error>(response.into_inner().instruments.into_iter()
+                .map(|currency| (currency.iso_currency_name.to_lowercase(), CurrencyInstrument {
+                    uid: currency.uid,
+                    nominal: currency.nominal.map(|n| n.units).filter(|&n| n > 0).unwrap_or(1),
+                }))
+                .collect())
+        }).await?;
+        let wanted: Vec<_> = currencies.iter()
+            .filter_map(|&currency| instruments.get(currency).map(|i| (currency, i)))
+            .collect();
+        if wanted.is_empty() {
+            return Ok(Rates::new([]));
+        }
+        let response = self.broker.get_last_prices(GetLastPricesRequest {
+            figi: Vec::new(),
+            instrument_id: wanted.iter().map(|(_, i)| i.uid.clone()).collect(),
+        }).await?;
+        let prices = response.into_inner().last_prices;
+        let rates = wanted.into_iter()
+            .filter_map(|(currency, instrument)| {
+                let price = prices.iter()
+                    .find(|price| price.instrument_uid == instrument.uid)
+                    .and_then(|price| price.price.clone())?;
+                let per_unit = Quotation::from_nanos(price.to_nanos() / instrument.nominal as i128);
+                Some((currency.to_string(), per_unit))
+            });
+        Ok(Rates::new(rates))
+    }
+
+    /// All the money in `currency`, the other currencies converted, see `Balance::available_in`.
+    pub async fn convert(&self, balance: &Balance, currency: &str) -> Result<Money> {
+        if balance.currencies.keys().all(|from| from == currency) {
+            // nothing to convert, the rates are not asked for
+            return Ok(balance.available_in(currency, &Rates::new([])));
+        }
+        let mut currencies: Vec<&str> = balance.currencies.keys().map(String::as_str).collect();
+        currencies.push(currency);
+        let rates = self.rates(&currencies).await?;
+        for from in balance.currencies.keys() {
+            if rates.convert(&Quotation::default(), from, currency).is_none() {
+                warn!(from = %from, to = currency, "no exchange rate, the money is not counted");
+            }
+        }
+        Ok(balance.available_in(currency, &rates))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(units: i64, nano: i32, currency: &str) -> MoneyValue {
+        MoneyValue { currency: currency.to_string(), units, nano }
+    }
+
+    #[test]
+    fn balances() {
+        let positions = PositionsResponse {
+            money: vec![money(1000, 0, "rub"), money(10, 50_0000000, "usd")],
+            blocked: vec![money(200, 0, "rub")],
+            ..PositionsResponse::default()
+        };
+        let limits = WithdrawLimitsResponse {
+            money: vec![money(800, 0, "RUB")],
+            ..WithdrawLimitsResponse::default()
+        };
+        let balance = Balance::new(&positions, &limits);
+        assert_eq!(balance.currencies["rub"].available, Quotation { units: 1000, nano: 0 });
+        assert_eq!(balance.currencies["rub"].blocked, Quotation { units: 200, nano: 0 });
+        assert_eq!(balance.currencies["rub"].withdrawable, Quotation { units: 800, nano: 0 });
+        assert!(!balance.currencies.contains_key("cny"));
+        assert_eq!(balance.to_string(), "1000 RUB (заблокировано 200), 10.5 USD");
+
+        let rates = Rates::new([("usd".to_string(), Quotation { units: 80, nano: 0 })]);
+        assert_eq!(balance.available_in("rub", &rates), Money::new(Quotation { units: 1840, nano: 0 }, "rub"));
+        assert_eq!(balance.available_in("usd", &rates), Money::new(Quotation { units: 23, nano: 0 }, "usd"));
+        // no rate for hkd: only the hkd itself
+        assert_eq!(balance.available_in("hkd", &rates).value, Quotation::default());
+        // only the rubles buy a ruble instrument
+        assert_eq!(balance.available("rub"), Money::new(Quotation { units: 1000, nano: 0 }, "rub"));
+        assert_eq!(balance.available("cny").value, Quotation::default());
+    }
+    #[test]
+    fn conversion() {
+        let rates = Rates::new([
+            ("usd".to_string(), Quotation { units: 75, nano: 50_0000000 }),
+            ("cny".to_string(), Quotation { units: 10, nano: 0 }),
+        ]);
+        let one = Quotation { units: 1, nano: 0 };
+        assert_eq!(rates.convert(&one, "usd", "rub"), Some(Quotation { units: 75, nano: 50_0000000 }));
+        assert_eq!(rates.convert(&Quotation { units: 151, nano: 0 }, "rub", "usd"), Some(Quotation { units: 2, nano: 0 }));
+        assert_eq!(rates.convert(&one, "usd", "cny"), Some(Quotation { units: 7, nano: 55_0000000 }));
+        assert_eq!(rates.convert(&one, "hkd", "rub"), None);
+    }
+}
@@ -1,34 +1,39 @@
 use std:: {
-    sync::Arc,
-    fmt::Write,
+    path::{Path, PathBuf},
     time::{Duration, Instant, SystemTime}
 };
-use chrono::{DateTime, Utc, Timelike, TimeZone, Datelike};
+use chrono::{DateTime, Utc, Timelike, TimeZone};
 use chrono_tz::Europe::Moscow;
 use prost_types::Timestamp;
 
 use std::fmt::{Debug, Display, Formatter};
-use tonic:: {
-    Status,
-    transport::Channel,
-    codegen::InterceptedService,
-};
-use crate::DefaultInterceptor;
-use crate::tcs::{CancelOrderRequest, GetOrderBookRequest, GetOrderBookResponse, GetOrdersRequest,
+use crate::tcs::{CancelOrderRequest, GetOrderBookRequest, GetOrdersRequest,
                  GetOrderStateRequest, OrderDirection, PositionsRequest, PostOrderRequest,
-                 Quotation, CandleInterval, GetCandlesRequest, GetCandlesResponse,
-                 market_data_service_client::MarketDataServiceClient,
-                 operations_service_client::OperationsServiceClient,
-                 orders_service_client::OrdersServiceClient, OrderType};
-use tokio::sync::mpsc::Receiver;
+                 Quotation, CandleInterval, GetCandlesRequest, GetLastPricesRequest,
+                 GetLastTradesRequest, HistoricCandle, OrderState, OrderType, Trade};
+use tokio::sync::{mpsc::Receiver, watch};
+use tracing::{field::Empty, info, info_span, warn, Instrument, Span};
 use serde::{
     {Deserialize, Deserializer, Serialize, Serializer},
-    de::Error,
     ser::SerializeStruct
 };
 use serde_json::{Value};
 
-use crate::tg::{send_message, RequestType};
+use crate::balance::{Money, Wallet};
+use crate::candles::{self, Candles};
+use crate::history;
+use crate::control::{Request, RequestType};
+use crate::error::{Error, Recovery, Result};
+use crate::notify::{Event, Notifier};
+use crate::portfolio::Portfolio;
+use crate::strategies::{Action, Context, Fill, Rejection, Source, Strategy};
+use crate::chart::{self, ChartError, ChartKind};
+use crate::metrics::metrics;
+use crate::grpc::Broker;
+use crate::accounts::Account;
+use crate::analytics::Summary;
+use crate::dashboard::{self, ConfigView, Dashboard, Feed, OrderView, PositionView, Snapshot, StateView};
+use crate::shutdown::{PositionOutcome, ShutdownPolicy, ShutdownSummary, StopReason};
 
 
 impl Serialize for Quotation {
@@ -51,7 +56,7 @@ impl<'de> Deserialize<'de> for Quotation {
                 nano: nano.as_i64().unwrap() as i32,
             })
         } else {
-            Err(Error::custom("invalid Quotation"))
+            Err(serde::de::Error::custom("invalid Quotation"))
         }
     }
 }
@@ -73,7 +78,7 @@ impl std::ops::Sub for Quotation {
         let mut nano_sub = self.nano - rhs.nano;
         let mut units_sub = self.units - rhs.units;
         if nano_sub < 0 {
-            nano_sub = 100_0000000 + nano_sub;
+            nano_sub += 100_0000000;
             units_sub -= 1;
         }
         Quotation { units: units_sub, nano: nano_sub }
@@ -144,13 +149,47 @@ impl std::ops::Mul for Quotation {
         first_mul + second_mul
     }
 }
+impl PartialOrd for Quotation {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some((self.units, self.nano).cmp(&(other.units, other.nano)))
+    }
+}
 impl Display for Quotation {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut nano = self.nano;
-        while nano % 10 == 0 {
-            nano /= 10;
+        // negative ones come with the nano of either sign
+        let nanos = self.to_nanos();
+        let sign = if nanos < 0 { "-" } else { "" };
+        let (units, nano) = (nanos.abs() / 1_000_000_000, nanos.abs() % 1_000_000_000);
+        if nano == 0 {
+            return write!(f, "{}{}", sign, units);
         }
-        write!(f, "{}.{}", self.units, nano)
+        let nano = format!("{:09}", nano);
+        write!(f, "{}{}.{}", sign, units, nano.trim_end_matches('0'))
+    }
+}
+impl Quotation {
+    pub fn to_f64(&self) -> f64 {
+        self.units as f64 + self.nano as f64 / 1e9
+    }
+
+    /// Volume weighted price of several executions: (price, lots).
+    pub fn average(parts: &[(Quotation, i64)]) -> Quotation {
+        let lots: i64 = parts.iter().map(|(_, l)| l).sum();
+        if lots == 0 {
+            return Quotation::default();
+        }
+        let value: i128 = parts.iter()
+            .map(|(p, l)| p.to_nanos() * *l as i128)
+            .sum();
+        Quotation::from_nanos(value / lots as i128)
+    }
+
+    pub fn to_nanos(&self) -> i128 {
+        self.units as i128 * 1_000_000_000 + self.nano as i128
+    }
+
+    pub fn from_nanos(nanos: i128) -> Quotation {
+        Quotation { units: (nanos / 1_000_000_000) as i64, nano: (nanos % 1_000_000_000) as i32 }
     }
 }
 
@@ -162,9 +201,14 @@ pub struct Statistics {
     pub turnover: u32,
     pub profit: ProfitStat,
     pub trades: Vec<TradeStat>,
-    pub trades_count: u16
+    pub trades_count: u16,
+    #[serde(default)]
+    pub days: Vec<DayResult>,
+    /// Of the profit formula, see `Statistics::migrate`.
+    #[serde(default)]
+    pub version: u32,
 }
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct DayStat {
     pub date: String,
     pub turnover: u32,
@@ -172,13 +216,20 @@ pub struct DayStat {
     pub trades: Vec<TradeStat>,
     pub trades_count: u16
 }
-#[derive(Serialize, Deserialize, Clone)]
+/// Counted by `ProfitStat::new` since `Statistics::VERSION` 1.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct ProfitStat {
     pub net: Quotation,
     pub after_fees: Quotation,
     pub after_tax: Quotation
 }
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DayResult {
+    pub date: String,
+    pub profit: ProfitStat,
+    pub trades_count: u16,
+}
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct TradeStat {
     pub time_in: String,
     pub time_out: String,
@@ -187,6 +238,141 @@ pub struct TradeStat {
     pub direction: bool,
     pub turnover: u32,
     pub profit: ProfitStat,
+    // the older statistics have none of the fields below
+    /// Moscow date of the close.
+    #[serde(default)]
+    pub date: String,
+    #[serde(default)]
+    pub lots: i64,
+    /// From the open fill to the close one, as the bot saw them.
+    #[serde(default)]
+    pub held_secs: u64,
+    /// `Position::intended`.
+    #[serde(default)]
+    pub intended_in: (i64, i32),
+    #[serde(default)]
+    pub intended_out: (i64, i32),
+    #[serde(default)]
+    pub ticker: String,
+    /// See `strategies::by_name`.
+    #[serde(default)]
+    pub strategy: String,
+}
+impl Statistics {
+    pub const VERSION: u32 = 1;
+
+    pub fn new(bot_start_date: String) -> Statistics {
+        Statistics {
+            bot_start_date,
+            trade_secs: 0,
+            turnover: 0,
+            profit: ProfitStat::default(),
+            trades: Vec::new(),
+            trades_count: 0,
+            days: Vec::new(),
+            version: Statistics::VERSION,
+        }
+    }
+
+    /// `dir` is `Account::name`.
+    pub fn load(dir: &str) -> Result<Statistics> {
+        read_json(&account_file(dir, "stat.json"))
+    }
+
+    /// Before `VERSION` 1 `after_fees` and `after_tax` were the profit multiplied by the rates,
+    /// recounts them as `ProfitStat::new` does for the trades of then, their days and the total.
+    /// Returns false for the statistics that are up to date.
+    pub fn migrate(&mut self, fee_rate: &Quotation, tax_rate: &Quotation) -> bool {
+        if self.version >= Statistics::VERSION {
+            return false;
+        }
+        let deltas: Vec<_> = self.trades.iter_mut()
+            .map(|trade| trade.migrate(fee_rate, tax_rate))
+            .collect();
+        for delta in &deltas {
+            self.profit.migrate(delta);
+        }
+        // the days were counted for the latest trades, in their order
+        let counted: usize = self.days.iter().map(|day| day.trades_count as usize).sum();
+        if let Some(first) = deltas.len().checked_sub(counted) {
+            let mut deltas = deltas[first..].iter();
+            for day in &mut self.days {
+                for delta in deltas.by_ref().take(day.trades_count as usize) {
+                    day.profit.migrate(delta);
+                }
+            }
+        }
+        self.version = Statistics::VERSION;
+        true
+    }
+}
+impl DayStat {
+    pub fn new(date: String) -> DayStat {
+        DayStat {
+            date,
+            turnover: 0,
+            profit: ProfitStat::default(),
+            trades: Vec::new(),
+            trades_count: 0
+        }
+    }
+
+    /// Today's statistics from `today.json`, a new day starts with empty ones.
+    pub fn load(dir: &str) -> Result<DayStat> {
+        let stat: DayStat = read_json(&account_file(dir, "today.json"))?;
+        let today = Bot::today();
+        Ok(if stat.date == today { stat } else { DayStat::new(today) })
+    }
+
+    /// See `Statistics::migrate`.
+    pub fn migrate(&mut self, fee_rate: &Quotation, tax_rate: &Quotation) {
+        for trade in &mut self.trades {
+            let delta = trade.migrate(fee_rate, tax_rate);
+            self.profit.migrate(&delta);
+        }
+    }
+}
+impl ProfitStat {
+    /// The fees are the share of the turnover, the tax is the share of the profit left after them.
+    pub fn new(net: Quotation, turnover: &Quotation, fee_rate: &Quotation, tax_rate: &Quotation) -> ProfitStat {
+        let share = |value: &Quotation, rate: &Quotation|
+            Quotation::from_nanos(value.to_nanos() * rate.to_nanos() / 1_000_000_000);
+        let after_fees = net.clone() - share(turnover, fee_rate);
+        let after_tax = if after_fees.to_nanos() > 0 {
+            after_fees.clone() - share(&after_fees, tax_rate)
+        } else {
+            after_fees.clone()
+        };
+        ProfitStat { net, after_fees, after_tax }
+    }
+
+    /// Applies the change of `after_fees` and `after_tax` of a trade, see `TradeStat::migrate`.
+    fn migrate(&mut self, (old, new): &(ProfitStat, ProfitStat)) {
+        self.after_fees = self.after_fees.clone() - old.after_fees.clone() + new.after_fees.clone();
+        self.after_tax = self.after_tax.clone() - old.after_tax.clone() + new.after_tax.clone();
+    }
+}
+impl TradeStat {
+    /// Recounts the profit of a trade written before `Statistics::VERSION` 1, returns the old and the new one.
+    /// Those have no `date`, the newer ones stay as they are.
+    fn migrate(&mut self, fee_rate: &Quotation, tax_rate: &Quotation) -> (ProfitStat, ProfitStat) {
+        let old = self.profit.clone();
+        if self.date.is_empty() {
+            let turnover = Quotation { units: self.turnover as i64, nano: 0 };
+            self.profit = ProfitStat::new(old.net.clone(), &turnover, fee_rate, tax_rate);
+        }
+        (old, self.profit.clone())
+    }
+}
+impl std::ops::Add for ProfitStat {
+    type Output = ProfitStat;
+    fn add(self, rhs: Self) -> Self::Output {
+        ProfitStat {
+            net: self.net + rhs.net,
+            after_fees: self.after_fees + rhs.after_fees,
+            after_tax: self.after_tax + rhs.after_tax,
+        }
+    }
 }
 impl Display for ProfitStat {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -203,47 +389,62 @@ impl Display for DayStat {
     }
 }
 
+#[allow(dead_code)]
+#[derive(Clone)]
 pub enum AnalysisType {
     OrderBook(i32),
-    Candle(CandleInterval)
+    /// The smallest interval is polled, the others are built from it.
+    Candle(Vec<CandleInterval>)
 }
 
+#[derive(Clone)]
 pub struct Settings {
-    pub account_id: String,
-
     pub ticker: String,
     pub uid: String,
     pub figi: String,
 
     pub class_code: String,
+    /// Currency of the instrument prices, `rub`, `usd`, ...
+    pub currency: String,
     pub trading_time: Vec<(u32, u32, u32, u32)>,
     pub data_type: AnalysisType,
+    /// Polled besides `data_type`.
+    pub sources: Vec<Source>,
 
     pub fee_rate: Quotation,
     pub tax_rate: Quotation,
-}
 
-pub trait Strategy {
-    fn analyze_ob(&mut self, _: &GetOrderBookResponse, _: &State) -> Action {
-        panic!("Analyze order book not implemented")
-    }
-    fn analyze_c(&mut self, _: &GetCandlesResponse, _: &State) -> Action {
-        panic!("Analyze candles not implemented")
-    }
-    fn get_take_profit(&mut self, p: Quotation, l: i64) -> (Quotation, i64, OrderDirection);
-    fn get_settings(&self) -> Settings;
+    pub shutdown_policy: ShutdownPolicy,
+    pub stop_on_session_end: bool,
 }
 
-
-#[derive(PartialEq, Debug)]
-pub enum Action {
-    Close(Quotation, i64, OrderDirection),
-    Open(Quotation, i64, OrderDirection),
-    Hold,
+impl Display for Settings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let data = match &self.data_type {
+            AnalysisType::OrderBook(depth) => format!("стакан глубиной {}", depth),
+            AnalysisType::Candle(intervals) => format!("свечи {}", intervals.iter()
+                .map(CandleInterval::as_str_name)
+                .collect::<Vec<_>>()
+                .join(", ")),
+        };
+        let data = self.sources.iter().fold(data, |data, source| match source {
+            Source::LastPrice => data + ", последняя цена",
+            Source::Trades => data + ", сделки",
+        });
+        let time = self.trading_time.iter()
+            .map(|(fh, fm, th, tm)| format!("{:02}:{:02}-{:02}:{:02}", fh, fm, th, tm))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "Инструмент: {} ({}), uid {}, валюта {}\nВремя торговли: {}\nДанные: {}\n\
+                   Комиссия: {}, налог: {}\nПри остановке: {:?}, в конце сессии: {}",
+               self.ticker, self.class_code, self.uid, self.currency.to_uppercase(), time, data,
+               self.fee_rate, self.tax_rate, self.shutdown_policy,
+               if self.stop_on_session_end { "остановка" } else { "сон" })
+    }
 }
 
-#[derive(PartialEq, Debug)]
-enum PosState {
+#[derive(PartialEq, Debug, Clone)]
+pub enum PosState {
     WaitOpen,
     WaitClose,
     PartialOpen,
@@ -251,19 +452,40 @@ enum PosState {
     Hold,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Position {
     state: PosState,
     pub price_in: Quotation,
     pub lots: i64,
     pub direction: OrderDirection,
-    price_out: Quotation,
-    id: (String, String) // open_id, close_id
+    /// Of the close order, zero until it is placed.
+    pub price_out: Quotation,
+    /// Of the open and close orders, the fills are compared with them for the slippage.
+    pub intended: (Quotation, Quotation),
+    /// The open order is filled, or placed while it is not.
+    opened: Instant,
+    pub id: (String, String), // open_id, close_id
+    /// Lives from the open order to the close one.
+    span: Span,
+}
+
+impl Position {
+    /// Which order of the position is working and how far it got.
+    pub fn state(&self) -> &PosState {
+        &self.state
+    }
 }
 
 
+/// What the trading loop does after a step.
+enum Step {
+    Stop,
+    /// Right away, the step was cut short.
+    Retry,
+    Done,
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum State {
     InPosition(Position),
     Seeking(Quotation),
@@ -274,36 +496,83 @@ pub struct Bot {
     state: Option<State>,
 
     order_id: (u32, String), // for generating `order_id`
+    /// What the bot may spend, in `Settings::currency`.
     money: Quotation,
+    /// All the money of the account converted to `Settings::currency`, to show.
+    total: Quotation,
+    portfolio: watch::Receiver<Portfolio>,
 
-    order_client: OrdersServiceClient<InterceptedService<Channel, DefaultInterceptor>>,
-    market_client: MarketDataServiceClient<InterceptedService<Channel, DefaultInterceptor>>,
-    operation_client: OperationsServiceClient<InterceptedService<Channel, DefaultInterceptor>>,
+    account: Account,
+    broker: Broker,
+    wallet: Wallet,
 
     settings: Settings,
     strategy: Box<dyn Strategy>,
 
-    tg_bot: Arc<teloxide::prelude::Bot>,
+    notifier: Notifier,
+    dashboard: Dashboard,
+
+    stop: Option<StopReason>,
+    paused: bool,
+    started: Instant,
+
+    /// Callbacks that came up while the state was taken, see `dispatch`.
+    pending: Vec<Pending>,
+    last_timer: Instant,
+    /// Time of the newest trade given to `on_trade`, `(seconds, nanos)`.
+    last_trade: Option<(i64, i32)>,
+    /// The series of `AnalysisType::Candle`.
+    candles: Candles,
+    /// Between the steps of the trading loop.
+    pause: Duration,
+    /// Failed steps in a row and the last error told about, see `recover`.
+    failures: u32,
+    last_error: Option<String>,
+}
+
+enum Pending {
+    Fill(Fill),
+    Rejected(Rejection),
 }
 
-struct Client {
-    order: OrdersServiceClient<InterceptedService<Channel, DefaultInterceptor>>,
-    market: MarketDataServiceClient<InterceptedService<Channel, DefaultInterceptor>>,
-    operation: OperationsServiceClient<InterceptedService<Channel, DefaultInterceptor>>,
+pub const ADD_INFO_PATH: &str = "add_info";
+/// Days of the downloaded candles a candle strategy starts with, see `history::recent`.
+const HISTORY_DAYS: i64 = 7;
+
+pub fn add_info(file: &str) -> PathBuf {
+    Path::new(ADD_INFO_PATH).join(file)
+}
+
+/// `file` in the `dir` of an account, relative to `add_info`;
+/// an absolute `dir` keeps the files apart, the replays do so.
+pub fn account_file(dir: &str, file: &str) -> String {
+    if dir.is_empty() { file.to_string() } else { format!("{}/{}", dir, file) }
+}
+
+/// Reads `add_info/<file>`.
+pub fn read_json<T: serde::de::DeserializeOwned>(file: &str) -> Result<T> {
+    let text = std::fs::read_to_string(add_info(file))?;
+    serde_json::from_str(text.as_str()).map_err(|err| Error::persistence(file, err))
 }
 
-impl<'a> Bot {
-    const ADD_INFO_PATH: &'a str = ".\\add_info\\";
+pub fn write_json<T: Serialize>(file: &str, value: &T) -> Result<()> {
+    let text = serde_json::to_string(value).map_err(|err| Error::persistence(file, err))?;
+    Ok(std::fs::write(add_info(file), text)?)
+}
+
+impl Bot {
 
-    pub fn new(channel: Channel,
-               inter: DefaultInterceptor,
-               money: Quotation,
+    pub fn new(account: Account,
+               broker: Broker,
+               wallet: Wallet,
+               portfolio: watch::Receiver<Portfolio>,
                strategy: Box<dyn Strategy>,
-               tg_bot: teloxide::prelude::Bot) -> Bot {
+               notifier: Notifier,
+               dashboard: Dashboard) -> Result<Bot> {
         let time_crutch = || {
             let mut set = strategy.get_settings();
             for (fh, fm, th, tm)
-            in (&mut set.trading_time).into_iter() {
+            in set.trading_time.iter_mut() {
                 if *th < *fh {
                     *th += 24;
                 }
@@ -314,48 +583,106 @@ impl<'a> Bot {
             }
             set
         };
-        Bot {
+        let settings = time_crutch();
+        Bot::migrate_stat(&account, &settings)?;
+        let mut candles = Candles::new(match &settings.data_type {
+            AnalysisType::Candle(intervals) => intervals,
+            AnalysisType::OrderBook(_) => &[],
+        });
+        if let AnalysisType::Candle(_) = settings.data_type {
+            // what `download` saved, the broker gives only a day of minutes
+            match history::recent(Path::new(history::HISTORY_PATH), &settings.uid, candles.base(), HISTORY_DAYS) {
+                Ok(stored) => {
+                    info!(candles = stored.len(), "history loaded");
+                    candles.update(stored);
+                },
+                Err(err) => warn!(%err, "history is not loaded"),
+            }
+        }
+        Ok(Bot {
             state: None,
-            order_id: Bot::get_order_id(), // 1_0000 for Debug, 1_000000 for Release
-            money,
-            order_client: OrdersServiceClient::with_interceptor(
-                channel.clone(), inter.clone()),
-            market_client: MarketDataServiceClient::with_interceptor(
-                channel.clone(), inter.clone()),
-            operation_client: OperationsServiceClient::with_interceptor(
-                channel.clone(), inter.clone()),
-            settings: time_crutch(),
+            order_id: Bot::get_order_id(&account)?, // 1_0000 for Debug, 1_000000 for Release
+            money: Quotation::default(),
+            total: Quotation::default(),
+            portfolio,
+            account,
+            broker,
+            wallet,
+            settings,
             strategy,
-            tg_bot: Arc::new(tg_bot),
+            notifier,
+            dashboard,
+            stop: None,
+            paused: false,
+            started: Instant::now(),
+            pending: Vec::new(),
+            last_timer: Instant::now(),
+            last_trade: None,
+            candles,
+            pause: Duration::new(0, 500_000_000),
+            failures: 0,
+            last_error: None,
+        })
+    }
+
+    /// Changes the pause between the steps, a replay goes without one.
+    pub fn with_pause(mut self, pause: Duration) -> Bot {
+        self.pause = pause;
+        self
+    }
+
+    /// Overrides `Settings::shutdown_policy` of the strategy.
+    pub fn with_shutdown_policy(mut self, policy: ShutdownPolicy) -> Bot {
+        self.settings.shutdown_policy = policy;
+        self
+    }
+
+    /// See `Statistics::migrate`, today's file has the same trades.
+    fn migrate_stat(account: &Account, settings: &Settings) -> Result<()> {
+        let mut total = Statistics::load(&account.name)?;
+        if !total.migrate(&settings.fee_rate, &settings.tax_rate) {
+            return Ok(());
         }
+        let mut day: DayStat = read_json(&account.file("today.json"))?;
+        day.migrate(&settings.fee_rate, &settings.tax_rate);
+        write_json(&account.file("today.json"), &day)?;
+        write_json(&account.file("stat.json"), &total)?;
+        info!(account = %account.name, "statistics migrated to version {}", Statistics::VERSION);
+        Ok(())
     }
 
-    fn get_order_id() -> (u32, String) {
+    fn get_order_id(account: &Account) -> Result<(u32, String)> {
         use std::str::FromStr;
-        let contents = std::fs::read_to_string(Self::ADD_INFO_PATH.to_owned() + "oid.txt")
-            .expect("Didn't find file oid.txt");
-        (u32::from_str(contents.as_str()).unwrap(), contents)
-    }
-    fn update_order_id(order_id: &String) {
-        std::fs::write(Self::ADD_INFO_PATH.to_owned() + "oid.txt", &order_id)
-            .unwrap_or_else(|err| {
-                panic!("Can't write `orders_id` to `oid.txt`. Current id: {}\n Error: {}",
-                       &order_id, err);
-            });
+        let contents = std::fs::read_to_string(add_info(&account.file("oid.txt")))?;
+        let id = u32::from_str(contents.trim()).map_err(|err| Error::persistence("oid.txt", err))?;
+        Ok((id, contents))
+    }
+    fn update_order_id(account: &Account, order_id: &String) -> Result<()> {
+        Ok(std::fs::write(add_info(&account.file("oid.txt")), order_id)?)
     }
 
-    async fn get_money(&mut self) -> Result<Quotation, Status> {
-        let req = PositionsRequest {
-            account_id: self.settings.account_id.clone(),
+    /// Money in the currency of the instrument from the portfolio stream, polled while it is loading;
+    /// the total counts the other currencies at the last prices.
+    async fn get_money(&mut self) -> Result<Quotation> {
+        let streamed = {
+            let portfolio = self.portfolio.borrow();
+            (!portfolio.loading).then(|| portfolio.money.clone())
         };
-        let response = self.operation_client.get_positions(req).await?;
-        let money = &response.get_ref().money;
-        for val in money {
-            if val.currency.eq("rub") {
-                return Ok(Quotation { units: val.units, nano: val.nano });
-            }
+        let balance = match streamed {
+            Some(balance) => balance,
+            None => self.wallet.balance(&self.account.id).await?,
+        };
+        self.money = balance.available(&self.settings.currency).value;
+        match self.wallet.convert(&balance, &self.settings.currency).await {
+            Ok(total) => self.total = total.value,
+            Err(err) => warn!(%err, "the total is not converted"),
         }
-        panic!("Can't take RUB money!")
+        Ok(self.money.clone())
+    }
+
+    /// `value` in the currency of the instrument.
+    fn money(&self, value: &Quotation) -> Money {
+        Money::new(value.clone(), &self.settings.currency)
     }
 
     fn get_sleep_time(&self) -> Duration {
@@ -375,12 +702,14 @@ impl<'a> Bot {
         Duration::new(w, 1)
     }
 
-    async fn get_from_tg(&self, rx: &mut Receiver<RequestType>) {
-        while let Ok(val) = rx.try_recv() {
-            match val {
+    async fn get_from_tg(&mut self, rx: &mut Receiver<Request>) -> Result<()> {
+        while let Ok(Request { kind, reply }) = rx.try_recv() {
+            let notifier = self.notifier.clone();
+            let answer = |text: String| notifier.reply(&reply, Event::Message(text));
+            match kind {
                 RequestType::StateRequest => {
-                    let mut ans = if let None = &self.state {
-                        send_message(self.tg_bot.clone(), "Состояние неизвестно".to_string()).await;
+                    let mut ans = if self.state.is_none() {
+                        answer("Состояние неизвестно".to_string());
                         continue;
                     } else {
                         String::new()
@@ -391,44 +720,44 @@ impl<'a> Bot {
                                 PosState::WaitClose => {
                                     format!(
                                         "Открытые позиции:\
-                                        \nвход {} лотов по цене {} RUB\
-                                        \nожидаем выход по цене {} RUB",
-                                        pos.lots, pos.price_in, pos.price_out)
+                                        \nвход {} лотов по цене {}\
+                                        \nожидаем выход по цене {}",
+                                        pos.lots, self.money(&pos.price_in), self.money(&pos.price_out))
                                 },
                                 PosState::PartialClose => {
                                     format!(
                                         "Открытые позиции:\
-                                        \nвход {} лотов по цене {} RUB\
-                                        \nожидаем выход по цене {} RUB, частично закрыто\
+                                        \nвход {} лотов по цене {}\
+                                        \nожидаем выход по цене {}, частично закрыто\
                                         (пока не могу узнать сколько точно :))",
-                                        pos.lots, pos.price_in, pos.price_out)
+                                        pos.lots, self.money(&pos.price_in), self.money(&pos.price_out))
                                 },
                                 PosState::WaitOpen => {
                                     format!(
                                         "Ожидаем открытия позиции:\
-                                        \nвход {} лотов по цене {} RUB",
-                                        pos.lots, pos.price_in)
+                                        \nвход {} лотов по цене {}",
+                                        pos.lots, self.money(&pos.price_in))
                                 },
                                 PosState::PartialOpen => {
                                     format!(
                                         "Открытые позиции:\
-                                        \nвход {} лотов (из x лотов, хз сколько :)) по цене {} RUB",
-                                        pos.lots, pos.price_in)
+                                        \nвход {} лотов (из x лотов, хз сколько :)) по цене {}",
+                                        pos.lots, self.money(&pos.price_in))
                                 },
                                 PosState::Hold => {
                                     format!(
                                         "Открытые позиции:\
-                                        \nвход {} лотов по цене {} RUB\
+                                        \nвход {} лотов по цене {}\
                                         \nзаявка на закрытие ещё не выставлена",
-                                        pos.lots, pos.price_in)
+                                        pos.lots, self.money(&pos.price_in))
                                 },
                             }.as_str());
                         },
                         State::Seeking(money) => {
                             ans.push_str(
                                 format!(
-                                    "Ищем точку входа, портфель: {} RUB",
-                                    money).as_str());
+                                    "Ищем точку входа, портфель: {}, со всеми валютами: {}",
+                                    self.money(money), self.money(&self.total)).as_str());
                         },
                         State::Sleeping(i, d) => {
                             ans.push_str(
@@ -438,230 +767,842 @@ impl<'a> Bot {
                                     Instant::now().duration_since(*i).as_secs() / 60).as_str());
                         }
                     }
-                    send_message(self.tg_bot.clone(), ans).await
+                    ans.push_str(format!("\n{}", *self.portfolio.borrow()).as_str());
+                    ans.push_str(format!("\nСвязь с брокером: {}", self.broker.connection().state()).as_str());
+                    answer(ans)
                 },
                 RequestType::StatRequest => {
-                    let file = std::fs::read_to_string(
-                        Self::ADD_INFO_PATH.to_owned() + "today.json").unwrap();
-                    let stat: DayStat = serde_json::from_str(file.as_str()).unwrap();
-                    send_message(self.tg_bot.clone(), stat.to_string()).await
+                    let stat = self.read_day_stat()?;
+                    let summary = Summary::new(&self.read_stat()?);
+                    answer(format!("{}\n\nЗа всё время:\n{}", stat, summary))
                 },
                 RequestType::StopRequest => {
-                    if let State::InPosition(pos) = &self.state.as_ref().unwrap() {
-                        send_message(self.tg_bot.clone(),
-                                     format!("Есть открытые позиции: {} лотов по {} RUB",
-                                             pos.lots, pos.price_in)).await;
+                    if let Some(State::InPosition(pos)) = &self.state {
+                        answer(format!("Есть открытые позиции: {} лотов по {}, политика: {:?}",
+                                             pos.lots, self.money(&pos.price_in),
+                                             self.settings.shutdown_policy));
                     }
-                    // по хорошему дать бы ещё диалог, если есть открытые позиции
-                    send_message(self.tg_bot.clone(), "Закрываемся".to_string()).await;
-                    std::process::exit(0);
+                    answer("Закрываемся".to_string());
+                    self.stop = Some(StopReason::Command);
+                },
+                RequestType::PauseRequest => {
+                    self.paused = true;
+                    answer("Пауза, новые позиции не открываем".to_string())
+                },
+                RequestType::ResumeRequest => {
+                    self.paused = false;
+                    answer("Продолжаем торговлю".to_string())
+                },
+                RequestType::FlattenRequest => {
+                    self.paused = true;
+                    let (cancelled, outcome) =
+                        self.close_position(ShutdownPolicy::Flatten).await?;
+                    answer(format!("Отменено заявок: {}\nПозиция: {}\nПауза до /resume",
+                                         cancelled, outcome))
+                },
+                RequestType::ChartRequest(kind) => {
+                    let failed = |err: ChartError| Event::Error(format!("Не получилось нарисовать график: {}", err));
+                    match self.chart(kind).await {
+                        Ok(draw) => {
+                            let caption = format!("{} {}", self.settings.ticker, Self::today());
+                            // the trading goes on while it renders
+                            tokio::spawn(async move {
+                                let event = match chart::render_blocking(draw).await {
+                                    Ok(png) => Event::Chart { caption, png },
+                                    Err(err) => failed(err),
+                                };
+                                notifier.reply(&reply, event);
+                            });
+                        },
+                        Err(err) => notifier.reply(&reply, failed(err)),
+                    }
+                },
+                RequestType::ConfigRequest => {
+                    let ans = format!("{}\n{}\nПауза: {}",
+                                      self.account,
+                                      self.strategy.get_settings(),
+                                      if self.paused { "да" } else { "нет" });
+                    answer(ans)
                 },
-                RequestType::Something => {
-                    send_message(self.tg_bot.clone(), "WTF?!".to_string()).await
-                }
             }
         }
+        Ok(())
     }
 
-    pub async fn handler(mut self, mut rx: Receiver<RequestType>) -> Result<(), Status>  {
-        const SLEEP_INTERVAL_TIME: Duration = Duration::new(60, 0);
-        const PAUSE_TIME: Duration = Duration::new(0, 500_000_000);
-        const LIMIT: Duration = Duration::new(60, 1);
-
+    pub async fn handler(mut self, mut rx: Receiver<Request>,
+                         mut stop: watch::Receiver<Option<StopReason>>) -> Result<()>  {
         let req_ob = GetOrderBookRequest {
             figi: self.settings.figi.clone(),
-            depth: 10,
+            depth: if let AnalysisType::OrderBook(depth) = self.settings.data_type { depth } else { 10 },
             instrument_id: self.settings.uid.clone(),
         };
-        let req_c = {
-            if let AnalysisType::Candle(interval) = self.settings.data_type {
-                let now = {
-                    let now: DateTime<Utc> = SystemTime::now().into();
-                    now.with_timezone(&Moscow)
-                };
+        self.started = Instant::now();
+        self.state = Some(State::Seeking(self.get_money().await?));
+        self.notifier.message("Мы начали!".to_string());
+        if let Err(err) = self.hook(|s, ctx| s.on_start(ctx)).await {
+            self.recover(err, &mut stop).await?;
+        }
+        self.dashboard.update(self.snapshot());
 
-                let from: SystemTime = Utc.with_ymd_and_hms(
-                    now.year(), now.month(), now.day(),
-                    self.settings.trading_time[0].0 + 1, 0, 0).unwrap().into();
-                let to: SystemTime = Utc.with_ymd_and_hms(
-                    now.year(), now.month(), now.day(),
-                    self.settings.trading_time.last().unwrap().0 + 1, 0, 0).unwrap().into();
-                Some(GetCandlesRequest {
-                    figi: self.settings.figi.clone(),
-                    interval: interval.into(),
-                    from: Some(Timestamp::from(from)),
-                    to: Some(Timestamp::from(to)),
-                    instrument_id: self.settings.uid.clone(),
-                })
-            } else {
-                None
+        let mut iteration: u64 = 0;
+        loop {
+            iteration += 1;
+            let span = info_span!("iteration", n = iteration);
+            // the step takes the state out, it is put back if the step fails
+            let state = self.state.clone();
+            let result = self.step(&req_ob, &mut rx, &mut stop).instrument(span).await;
+            if result.is_ok() {
+                self.failures = 0;
+                self.last_error = None;
+            }
+            match result {
+                Ok(Step::Stop) => return Ok(()),
+                Ok(Step::Retry) => {},
+                Ok(Step::Done) => tokio::time::sleep(self.pause).await,
+                Err(err) => {
+                    if self.state.is_none() {
+                        self.state = state;
+                    }
+                    self.recover(err, &mut stop).await?;
+                },
             }
+        }
+    }
+
+    fn context(&self) -> Context {
+        let state = self.state.clone().unwrap_or_else(|| State::Seeking(self.money.clone()));
+        Context::new(state, self.portfolio.borrow().clone(), self.money(&self.money),
+                     self.strategy.get_settings(), self.paused)
+    }
+
+    /// Runs a strategy callback and carries out the actions it submitted,
+    /// returns how many there were.
+    async fn hook(&mut self, callback: impl FnOnce(&mut dyn Strategy, &mut Context)) -> Result<usize> {
+        let mut ctx = self.context();
+        callback(self.strategy.as_mut(), &mut ctx);
+        let actions = ctx.into_actions();
+        let count = actions.len();
+        for action in actions {
+            self.execute(action).await?;
+        }
+        Ok(count)
+    }
+
+    /// Runs the callbacks that came up while the state was taken.
+    async fn dispatch(&mut self) -> Result<()> {
+        for pending in std::mem::take(&mut self.pending) {
+            match pending {
+                Pending::Fill(fill) => self.hook(|s, ctx| s.on_fill(ctx, &fill)).await?,
+                Pending::Rejected(rejection) =>
+                    self.hook(|s, ctx| s.on_order_rejected(ctx, &rejection)).await?,
+            };
+        }
+        Ok(())
+    }
+
+    /// Carries out an action of the strategy, the ones that don't fit the state are skipped,
+    /// the ones the money doesn't cover are refused with `Error::Risk`.
+    /// The state is left as it was if the action fails.
+    async fn execute(&mut self, action: Action) -> Result<()> {
+        if let Action::Open(p, l, d) | Action::Close(p, l, d) = &action {
+            let name = if matches!(action, Action::Open(..)) { "open" } else { "close" };
+            metrics().signals.with_label_values(&[name]).inc();
+            self.dashboard.publish(Feed::Signal {
+                action: name.to_string(),
+                lots: *l,
+                price: p.clone(),
+                direction: dashboard::direction(*d),
+            });
+        }
+        let state = match (self.state.clone().unwrap(), action) {
+            (state, Action::Hold) => state,
+            (state @ State::Seeking(..), _) if self.paused => state,
+            (State::Seeking(..), Action::Open(p, l, _) | Action::Close(p, l, _))
+            if p.clone() * Quotation { units: l, nano: 0 } > self.money => {
+                return Err(Error::Risk(format!("на {} лотов по {} не хватает денег, портфель: {}",
+                                               l, self.money(&p), self.money(&self.money))));
+            },
+            (State::Seeking(..), Action::Open(p, l, d) | Action::Close(p, l, d)) => {
+                let order_id = self.place_order(l, p.clone(), d).await?;
+                let span = info_span!(parent: None, "position", open_id = %order_id,
+                                      close_id = Empty, direction = ?d, lots = l);
+                info!(parent: &span, price = %p, "open order placed");
+                State::InPosition(Position {
+                    state: PosState::WaitOpen,
+                    price_in: p.clone(),
+                    lots: l,
+                    direction: d,
+                    price_out: Quotation::default(),
+                    intended: (p, Quotation::default()),
+                    opened: Instant::now(),
+                    id: (order_id, String::new()),
+                    span,
+                })
+            },
+            (State::InPosition(pos), Action::Close(p, l, d))
+            if pos.state == PosState::WaitClose || pos.state == PosState::Hold => {
+                let span = pos.span.clone();
+                self.replace_close(pos, p, l, d).instrument(span).await?
+            },
+            (state, action) => {
+                warn!(?action, "the action doesn't fit the state, skipped");
+                state
+            },
         };
+        self.state = Some(state);
+        Ok(())
+    }
 
-        let mut time = Instant::now();
-        self.state = Some(State::Seeking(self.get_money().await?));
-        send_message(self.tg_bot.clone(), "Мы начали!".to_string()).await;
+    /// Moves the close order of the position to `price`.
+    async fn replace_close(&mut self, mut pos: Position, price: Quotation, lots: i64,
+                           direction: OrderDirection) -> Result<State> {
+        if pos.state == PosState::WaitClose {
+            self.cancel_order(pos.id.1.clone()).await?;
+            pos.state = PosState::Hold;
+            // without the close order if the new one fails
+            self.state = Some(State::InPosition(pos.clone()));
+        }
+        pos.id.1 = self.place_order(lots, price.clone(), direction).await?;
+        pos.span.record("close_id", pos.id.1.as_str());
+        info!(%price, "close order moved");
+        pos.state = PosState::WaitClose;
+        pos.intended.1 = price.clone();
+        pos.price_out = price;
+        Ok(State::InPosition(pos))
+    }
 
-        loop {
-            self.get_from_tg(&mut rx).await;
-            let result = match self.settings.data_type {
-                AnalysisType::OrderBook(_) => {
-                    let response = {
-                        let response = self.market_client.get_order_book(req_ob.clone()).await;
-                        if let Err(_) = &response {
-                            println!("So fast or network error!");
-                            std::thread::sleep(LIMIT - time.elapsed());
-                            time = Instant::now();
-                            continue;
-                        }
-                        response.unwrap()
-                    };
-                    if response.get_ref().bids.is_empty() && response.get_ref().asks.is_empty() {
-                        if let Some(State::Seeking(..) | State::InPosition(..)) = self.state.as_ref() {
-                            let d = self.get_sleep_time();
-                            self.state = Some(State::Sleeping(Instant::now(), d.clone()));
-                            send_message(self.tg_bot.clone(),
-                                         format!("Идём спать на {} минут!", d.as_secs() / 60)).await;
-                        }
-                        None
-                    } else {
-                        Some(self.strategy.analyze_ob(response.get_ref(),
-                                                      self.state.as_ref().unwrap()))
+    /// New candles of the base interval: everything the broker gives at once on the first call,
+    /// then from the last known candle on.
+    async fn load_candles(&self) -> Result<Vec<HistoricCandle>> {
+        let base = self.candles.base();
+        let now = Utc::now().timestamp();
+        let earliest = now - candles::max_period(base) + 60;
+        let from = self.candles.resume_from().map_or(earliest, |last| last.max(earliest));
+        let response = self.broker.get_candles(GetCandlesRequest {
+            figi: self.settings.figi.clone(),
+            interval: base.into(),
+            from: Some(Timestamp { seconds: from, nanos: 0 }),
+            to: Some(Timestamp { seconds: now, nanos: 0 }),
+            instrument_id: self.settings.uid.clone(),
+        }).await?;
+        Ok(response.into_inner().candles)
+    }
+
+    /// Polls `Settings::sources` and passes the news to the strategy.
+    async fn poll_sources(&mut self) -> Result<()> {
+        for source in self.settings.sources.clone() {
+            match source {
+                Source::LastPrice => {
+                    let prices = self.broker.get_last_prices(GetLastPricesRequest {
+                        figi: Vec::new(),
+                        instrument_id: vec![self.settings.uid.clone()],
+                    }).await?.into_inner().last_prices;
+                    for price in &prices {
+                        self.hook(|s, ctx| s.on_last_price(ctx, price)).await?;
                     }
                 },
-                AnalysisType::Candle(_) => {
-                    let response = {
-                        let response = self.market_client.get_candles(
-                            req_c.as_ref().unwrap().clone()).await;
-                        if let Err(_) = &response {
-                            println!("So fast or network error!");
-                            std::thread::sleep(LIMIT - time.elapsed());
-                            time = Instant::now();
-                            continue;
-                        }
-                        response.unwrap()
-                    };
-                    if response.get_ref().candles.is_empty() {
-                        if let Some(State::Seeking(..) | State::InPosition(..)) = self.state.as_ref() {
-                            let d = self.get_sleep_time();
-                            self.state = Some(State::Sleeping(Instant::now(), d.clone()));
-                            send_message(self.tg_bot.clone(),
-                                         format!("Идём спать на {} минут!", d.as_secs() / 60)).await;
-                        }
-                        None
-                    } else {
-                        Some(self.strategy.analyze_c(response.get_ref(),
-                                                self.state.as_ref().unwrap()))
+                Source::Trades => {
+                    let key = |t: &Trade| t.time.as_ref().map(|time| (time.seconds, time.nanos));
+                    let mut trades = self.broker.get_last_trades(GetLastTradesRequest {
+                        figi: String::new(),
+                        from: self.last_trade.map(|(seconds, nanos)| Timestamp { seconds, nanos }),
+                        to: Some(Timestamp::from(SystemTime::now())),
+                        instrument_id: self.settings.uid.clone(),
+                    }).await?.into_inner().trades;
+                    trades.sort_by_key(key);
+                    let seen = self.last_trade;
+                    for trade in trades.iter().filter(|t| key(t) > seen) {
+                        self.last_trade = key(trade);
+                        self.hook(|s, ctx| s.on_trade(ctx, trade)).await?;
                     }
                 },
-            };
+            }
+        }
+        Ok(())
+    }
 
-            self.state = Some(match self.state.take().unwrap() {
-                state @ State::Seeking(..) => {
-                    match result.unwrap() {
-                        Action::Open(p, l, d)
-                        | Action::Close(p, l, d) => {
-                            let order_id = self.place_order(l, p.clone(), d).await?;
-                            State::InPosition(Position {
-                                state: PosState::WaitOpen,
-                                price_in: p,
-                                lots: l,
-                                direction: d,
-                                price_out: Quotation::default(),
-                                id: (order_id, String::new())
-                            })
-                        },
-                        Action::Hold => state
+    /// Handles the error according to `Error::recovery`, gives it back only if it is fatal.
+    async fn recover(&mut self, err: Error,
+                     stop: &mut watch::Receiver<Option<StopReason>>) -> Result<()> {
+        let recovery = err.recovery();
+        self.failures += 1;
+        warn!(%err, ?recovery, failures = self.failures, "step failed");
+        // the same error on every failed step is told once
+        let text = err.to_string();
+        let new = self.last_error.as_deref() != Some(text.as_str());
+        match recovery {
+            Recovery::Fatal => {
+                self.notifier.send(Event::Error(text));
+                self.notifier.flush().await;
+                return Err(err);
+            },
+            Recovery::Retry(_) => {},
+            Recovery::Skip => if new {
+                self.notifier.send(match &err {
+                    Error::Risk(message) => Event::RiskLimit(message.clone()),
+                    _ => Event::Error(text.clone()),
+                });
+            },
+            Recovery::Pause => if !self.paused {
+                self.paused = true;
+                self.notifier.send(Event::Error(format!("{}\nПауза до /resume", err)));
+            },
+        }
+        self.last_error = Some(text);
+        // a persistent error doesn't spin the loop, a stop cuts the wait short;
+        // the branch is off once the sender is gone
+        tokio::select! {
+            _ = tokio::time::sleep(recovery.pause(self.failures)) => {},
+            Ok(()) = stop.changed() => {},
+        }
+        Ok(())
+    }
+
+    /// One pass of the trading loop: requests, market data, strategy, orders.
+    async fn step(&mut self, req_ob: &GetOrderBookRequest, rx: &mut Receiver<Request>,
+                  stop: &mut watch::Receiver<Option<StopReason>>) -> Result<Step> {
+        const SLEEP_INTERVAL_TIME: Duration = Duration::new(60, 0);
+
+        let started = Instant::now();
+        self.get_from_tg(rx).await?;
+        if let Some(reason) = self.stop.or(*stop.borrow()) {
+            let mut ctx = self.context();
+            self.strategy.on_stop(&mut ctx);
+            if !ctx.into_actions().is_empty() {
+                warn!("actions on stop are skipped");
+            }
+            let summary = self.shutdown(reason).await?;
+            self.notifier.message(summary.to_string());
+            self.notifier.flush().await;
+            return Ok(Step::Stop);
+        }
+        self.dispatch().await?;
+        if self.portfolio.has_changed().unwrap_or(false) {
+            if !self.portfolio.borrow_and_update().loading {
+                self.get_money().await?;
+            }
+            self.hook(|s, ctx| s.on_portfolio(ctx)).await?;
+        }
+        if let Some(period) = self.strategy.timer() {
+            if self.last_timer.elapsed() >= period {
+                self.last_timer = Instant::now();
+                self.hook(|s, ctx| s.on_timer(ctx)).await?;
+            }
+        }
+        let actions = match self.settings.data_type {
+            AnalysisType::OrderBook(_) => {
+                let response = match self.broker.get_order_book(req_ob.clone()).await {
+                    Ok(response) => response,
+                    Err(err) => {
+                        warn!("So fast or network error!");
+                        metrics().rate_limit_hits.inc();
+                        return Err(err.into());
+                    },
+                };
+                self.dashboard.publish(Feed::order_book(response.get_ref()));
+                let book = response.into_inner();
+                self.update_position_metrics(
+                    book.bids.first().and_then(|order| order.price.clone()),
+                    book.asks.first().and_then(|order| order.price.clone()));
+                if book.bids.is_empty() && book.asks.is_empty() {
+                    if self.session_ended().await? {
+                        return Ok(Step::Retry);
                     }
-                },
-                State::InPosition(pos) => {
-                    let state = self.update_position_state(pos).await?;
-                    //let result = self.strategy.analyze_ob(response.get_ref(), &state);
-                    // Вопрос пока
-                    // match result {
-                    //     Action::Open(p, l, d)
-                    //     | Action::Close(p, l, d) => {
-                    //         let order_id = self.place_order(l, p.clone(), d).await?;
-                    //         State::InPosition(Position {
-                    //             state: PosState::WaitOpen,
-                    //             price_in: p,
-                    //             lots: l,
-                    //             direction: d,
-                    //             price_out: Quotation::default(),
-                    //             id: order_id
-                    //         })
-                    //     },
-                    //     Action::Hold => state
-                    // }
-                    state
-                },
-                State::Sleeping(i, d) => {
-                    if i.elapsed() < d {
-                        std::thread::sleep(SLEEP_INTERVAL_TIME);
-                        State::Sleeping(i, d)
-                    } else {
-                        send_message(self.tg_bot.clone(), "Проснись и пой!".to_string()).await;
-                        State::Seeking(self.money.clone())
+                    None
+                } else {
+                    Some(self.hook(|s, ctx| s.on_order_book(ctx, &book)).await?)
+                }
+            },
+            AnalysisType::Candle(_) => {
+                let candles = match self.load_candles().await {
+                    Ok(candles) => candles,
+                    Err(err) => {
+                        warn!("So fast or network error!");
+                        metrics().rate_limit_hits.inc();
+                        return Err(err);
+                    },
+                };
+                let close = candles.last().and_then(|c| c.close.clone());
+                self.update_position_metrics(close.clone(), close);
+                let today = candles::start(Utc::now().timestamp(), CandleInterval::Day);
+                let traded_today = candles.iter()
+                    .any(|c| c.time.as_ref().is_some_and(|t| t.seconds >= today));
+                if !traded_today && self.session_ended().await? {
+                    return Ok(Step::Retry);
+                }
+                if self.candles.update(candles) {
+                    // the callback borrows the bot, the series are put back after it
+                    let series = std::mem::replace(&mut self.candles, Candles::new(&[]));
+                    let actions = self.hook(|s, ctx| s.on_candle(ctx, &series)).await;
+                    self.candles = series;
+                    Some(actions?)
+                } else {
+                    None
+                }
+            },
+        };
+        if actions == Some(0) {
+            metrics().signals.with_label_values(&["hold"]).inc();
+        }
+        self.poll_sources().await?;
+
+        let mut woke = false;
+        self.state = Some(match self.state.take().unwrap() {
+            state @ State::Seeking(..) => state,
+            State::InPosition(pos) => {
+                let span = pos.span.clone();
+                let state = self.update_position_state(pos).instrument(span).await?;
+                //let result = self.strategy.analyze_ob(response.get_ref(), &state);
+                // Вопрос пока
+                // match result {
+                //     Action::Open(p, l, d)
+                //     | Action::Close(p, l, d) => {
+                //         let order_id = self.place_order(l, p.clone(), d).await?;
+                //         State::InPosition(Position {
+                //             state: PosState::WaitOpen,
+                //             price_in: p,
+                //             lots: l,
+                //             direction: d,
+                //             price_out: Quotation::default(),
+                //             id: order_id
+                //         })
+                //     },
+                //     Action::Hold => state
+                // }
+                state
+            },
+            State::Sleeping(i, d) => {
+                if i.elapsed() < d {
+                    // wake up earlier if a stop signal comes
+                    tokio::select! {
+                        _ = tokio::time::sleep(SLEEP_INTERVAL_TIME) => {},
+                        _ = stop.changed() => {},
                     }
+                    State::Sleeping(i, d)
+                } else {
+                    self.notifier.message("Проснись и пой!".to_string());
+                    woke = true;
+                    State::Seeking(self.money.clone())
                 }
-            });
+            }
+        });
+        if woke {
+            self.hook(|s, ctx| s.on_session_open(ctx)).await?;
+        }
+        self.dashboard.update(self.snapshot());
+        metrics().loop_seconds.observe(started.elapsed().as_secs_f64());
+        Ok(Step::Done)
+    }
+
+    /// Marks the position to the price it would be closed by.
+    fn update_position_metrics(&self, bid: Option<Quotation>, ask: Option<Quotation>) {
+        let m = metrics();
+        let lots = m.position_lots.with_label_values(&[&self.account.id]);
+        let unrealized_pnl = m.unrealized_pnl.with_label_values(&[&self.account.id, &self.settings.currency]);
+        let pos = match &self.state {
+            Some(State::InPosition(pos)) if pos.state != PosState::WaitOpen => pos,
+            _ => {
+                lots.set(0);
+                unrealized_pnl.set(0.0);
+                return;
+            },
+        };
+        let (sign, mark) = if pos.direction == OrderDirection::Buy { (1, bid) } else { (-1, ask) };
+        lots.set(sign * pos.lots);
+        if let Some(mark) = mark {
+            let pnl = (mark.to_f64() - pos.price_in.to_f64()) * (sign * pos.lots) as f64;
+            unrealized_pnl.set(pnl);
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut orders = Vec::new();
+        let state = match &self.state {
+            None => StateView::Unknown,
+            Some(State::Seeking(money)) => StateView::Seeking { money: money.clone() },
+            Some(State::Sleeping(i, d)) => StateView::Sleeping {
+                slept_secs: i.elapsed().as_secs(),
+                total_secs: d.as_secs(),
+            },
+            Some(State::InPosition(pos)) => {
+                let opposite = match pos.direction {
+                    OrderDirection::Buy => OrderDirection::Sell,
+                    _ => OrderDirection::Buy,
+                };
+                match pos.state {
+                    PosState::WaitOpen | PosState::PartialOpen => orders.push(OrderView {
+                        order_id: pos.id.0.clone(),
+                        direction: dashboard::direction(pos.direction),
+                        lots: pos.lots,
+                        price: pos.price_in.clone(),
+                        purpose: "open".to_string(),
+                    }),
+                    PosState::WaitClose | PosState::PartialClose => orders.push(OrderView {
+                        order_id: pos.id.1.clone(),
+                        direction: dashboard::direction(opposite),
+                        lots: pos.lots,
+                        price: pos.price_out.clone(),
+                        purpose: "close".to_string(),
+                    }),
+                    PosState::Hold => {},
+                }
+                StateView::InPosition(PositionView {
+                    status: match pos.state {
+                        PosState::WaitOpen => "wait_open",
+                        PosState::PartialOpen => "partial_open",
+                        PosState::Hold => "hold",
+                        PosState::WaitClose => "wait_close",
+                        PosState::PartialClose => "partial_close",
+                    }.to_string(),
+                    lots: pos.lots,
+                    direction: dashboard::direction(pos.direction),
+                    price_in: pos.price_in.clone(),
+                    price_out: pos.price_out.clone(),
+                })
+            },
+        };
+        Snapshot {
+            state,
+            paused: self.paused,
+            connection: self.broker.connection().state(),
+            money: self.money(&self.money),
+            total: self.money(&self.total),
+            portfolio: self.portfolio.borrow().clone(),
+            orders,
+            config: Some(ConfigView::new(&self.account, &self.strategy.get_settings())),
+        }
+    }
+
+    /// Gets the data for the chart, see `chart::render_blocking` for the rest.
+    async fn chart(&mut self, kind: ChartKind) -> Result<chart::Draw, ChartError> {
+        match kind {
+            ChartKind::Price => {
+                let now: DateTime<Utc> = SystemTime::now().into();
+                let midnight = now.with_timezone(&Moscow).date_naive().and_hms_opt(0, 0, 0).unwrap();
+                let from: SystemTime = Moscow.from_local_datetime(&midnight).unwrap().into();
+                let req = GetCandlesRequest {
+                    figi: self.settings.figi.clone(),
+                    interval: CandleInterval::CandleInterval1Min.into(),
+                    from: Some(Timestamp::from(from)),
+                    to: Some(Timestamp::from(SystemTime::from(now))),
+                    instrument_id: self.settings.uid.clone(),
+                };
+                let response = self.broker.get_candles(req).await?;
+                let prices: Vec<(f64, f64)> = response.get_ref().candles.iter()
+                    .filter_map(|candle| {
+                        let time = candle.time.as_ref()?;
+                        let time = Utc.timestamp_opt(time.seconds, 0).single()?.with_timezone(&Moscow);
+                        let close = candle.close.as_ref()?;
+                        Some(((time.hour() * 60 + time.minute()) as f64, close.to_f64()))
+                    })
+                    .collect();
+                let trades = self.read_day_stat()?.trades;
+                Ok(Box::new(move || chart::price_chart(&prices, &trades)))
+            },
+            ChartKind::Equity => {
+                let (trades, currency) = (self.read_stat()?.trades, self.settings.currency.clone());
+                Ok(Box::new(move || chart::equity_chart(&trades, &currency)))
+            },
+            ChartKind::DailyPnl => {
+                let (days, currency) = (self.read_stat()?.days, self.settings.currency.clone());
+                Ok(Box::new(move || chart::daily_pnl_chart(&days, &currency)))
+            },
+        }
+    }
+
+    /// Called when the market data is empty: either stops the bot
+    /// or puts it to sleep until the next trading window.
+    /// Returns `true` if the bot is stopping.
+    async fn session_ended(&mut self) -> Result<bool> {
+        if let Some(State::Seeking(..) | State::InPosition(..)) = self.state.as_ref() {
+            self.notifier.send(Event::DayReport(self.read_day_stat()?));
+            self.hook(|s, ctx| s.on_session_close(ctx)).await?;
+            if self.settings.stop_on_session_end {
+                self.stop = Some(StopReason::SessionEnd);
+                return Ok(true);
+            }
+            let d = self.get_sleep_time();
+            self.state = Some(State::Sleeping(Instant::now(), d));
+            self.notifier.message(format!("Идём спать на {} минут!", d.as_secs() / 60));
+        }
+        Ok(false)
+    }
+
+    /// Shutdown sequence: no new signals are generated from here on,
+    /// entry orders are cancelled, the open position is handled
+    /// according to `Settings::shutdown_policy` and the statistics are flushed.
+    async fn shutdown(&mut self, reason: StopReason) -> Result<ShutdownSummary> {
+        let (cancelled_orders, position) =
+            self.close_position(self.settings.shutdown_policy).await?;
+        let day = self.flush_stat()?;
+        Ok(ShutdownSummary { reason, cancelled_orders, position, day })
+    }
+
+    /// Cancels the entry order and handles the open position according to `policy`.
+    /// Returns the number of cancelled orders and what happened to the position.
+    async fn close_position(&mut self, policy: ShutdownPolicy)
+        -> Result<(u32, PositionOutcome)> {
+        let mut pos = match self.state.take() {
+            Some(State::InPosition(pos)) => pos,
+            state => {
+                self.state = state;
+                return Ok((0, PositionOutcome::Flat));
+            }
+        };
+        let mut cancelled_orders = 0;
+        if pos.state == PosState::WaitOpen || pos.state == PosState::PartialOpen {
+            // the order may be filled meanwhile, so the cancel result is not important
+            if self.try_cancel_order(pos.id.0.clone()).await {
+                cancelled_orders += 1;
+            }
+            let order = self.get_order_state(pos.id.0.clone()).await?;
+            pos.lots = order.lots_executed;
+            if let Some(mv) = order.average_position_price.as_ref() {
+                pos.price_in = Quotation { units: mv.units, nano: mv.nano };
+            }
+            pos.state = PosState::Hold;
+        }
+        let outcome = if pos.lots == 0 {
+            PositionOutcome::Flat
+        } else {
+            let span = pos.span.clone();
+            self.close_open_position(pos, policy, &mut cancelled_orders).instrument(span).await?
+        };
+        if !matches!(outcome, PositionOutcome::Kept(..)) {
+            self.state = Some(State::Seeking(self.get_money().await?));
+        }
+        Ok((cancelled_orders, outcome))
+    }
+
+    async fn close_open_position(&mut self, mut pos: Position, policy: ShutdownPolicy,
+                                 cancelled_orders: &mut u32)
+        -> Result<PositionOutcome> {
+        // a market order that is still working by then won't be filled soon
+        const MARKET_DEADLINE: Duration = Duration::new(30, 0);
+
+        let deadline = match policy {
+            ShutdownPolicy::Keep => return Ok(self.keep_position(pos)),
+            ShutdownPolicy::CloseLimit(deadline) => Some(deadline),
+            ShutdownPolicy::Flatten => None,
+        };
+        let direction = if pos.direction == OrderDirection::Buy {
+            OrderDirection::Sell
+        } else {
+            OrderDirection::Buy
+        };
+        // executions of the closing orders: (price, lots)
+        let mut parts = Vec::new();
+        if pos.state == PosState::WaitClose || pos.state == PosState::PartialClose {
+            if self.try_cancel_order(pos.id.1.clone()).await {
+                *cancelled_orders += 1;
+            }
+            let order = self.get_order_state(pos.id.1.clone()).await?;
+            Self::push_execution(&mut parts, &order);
+            pos.state = PosState::Hold;
+        }
+        let mut rest = pos.lots - parts.iter().map(|(_, l)| l).sum::<i64>();
 
-            std::thread::sleep(PAUSE_TIME);
+        if rest > 0 {
+            // what is left is closed from the best price of the moment
+            match self.best_price(direction).await? {
+                Some(price) => pos.intended.1 = price,
+                // the session is over, nothing to close against
+                None => {
+                    warn!(lots = rest, "empty order book, the position is kept");
+                    return self.keep_rest(pos, parts).await;
+                },
+            }
         }
+        if let (Some(deadline), true) = (deadline, rest > 0) {
+            pos.id.1 = self.place_order(rest, pos.intended.1.clone(), direction).await?;
+            rest -= self.wait_close_order(&mut parts, pos.id.1.clone(), deadline, cancelled_orders).await?;
+        }
+        if rest > 0 {
+            pos.id.1 = self.post_order(rest, None, direction, OrderType::Market).await?;
+            rest -= self.wait_close_order(&mut parts, pos.id.1.clone(), MARKET_DEADLINE, cancelled_orders).await?;
+        }
+        if rest > 0 {
+            warn!(lots = rest, "the close orders are not filled, the position is kept");
+            return self.keep_rest(pos, parts).await;
+        }
+
+        pos.price_out = Quotation::average(&parts);
+        let outcome = PositionOutcome::Closed(pos.lots, self.money(&pos.price_out));
+        self.write_stat(pos).await?;
+        Ok(outcome)
+    }
+
+    /// Polls the close order until it is done or the deadline passes, then cancels it.
+    /// Returns the executed lots.
+    async fn wait_close_order(&mut self, parts: &mut Vec<(Quotation, i64)>, order_id: String,
+                              deadline: Duration, cancelled_orders: &mut u32) -> Result<i64> {
+        const PAUSE_TIME: Duration = Duration::new(1, 0);
+
+        let start = Instant::now();
+        let mut order = loop {
+            let order = self.get_order_state(order_id.clone()).await?;
+            // Исполнена | Отклонена | Отменена | время вышло
+            if matches!(order.execution_report_status, 1..=3) || start.elapsed() > deadline {
+                break order;
+            }
+            tokio::time::sleep(PAUSE_TIME).await;
+        };
+        if !matches!(order.execution_report_status, 1..=3) {
+            if self.try_cancel_order(order_id.clone()).await {
+                *cancelled_orders += 1;
+            }
+            order = self.get_order_state(order_id).await?;
+        }
+        Ok(Self::push_execution(parts, &order))
+    }
+
+    /// The closed part goes to the statistics, the rest stays in the bot state.
+    async fn keep_rest(&mut self, mut pos: Position, parts: Vec<(Quotation, i64)>)
+        -> Result<PositionOutcome> {
+        let closed: i64 = parts.iter().map(|(_, l)| l).sum();
+        if closed > 0 {
+            let mut part = pos.clone();
+            part.lots = closed;
+            part.price_out = Quotation::average(&parts);
+            pos.lots -= closed;
+            self.write_stat(part).await?;
+        }
+        Ok(self.keep_position(pos))
+    }
+
+    /// Puts the position back to the bot state.
+    fn keep_position(&mut self, pos: Position) -> PositionOutcome {
+        let outcome = PositionOutcome::Kept(pos.lots, self.money(&pos.price_in));
+        self.state = Some(State::InPosition(pos));
+        outcome
+    }
+
+    fn push_execution(parts: &mut Vec<(Quotation, i64)>, order: &OrderState) -> i64 {
+        if order.lots_executed > 0 {
+            if let Some(mv) = order.average_position_price.as_ref() {
+                parts.push((Quotation { units: mv.units, nano: mv.nano }, order.lots_executed));
+            }
+        }
+        order.lots_executed
+    }
+
+    /// None when the order book is empty, e.g. after the session.
+    async fn best_price(&mut self, direction: OrderDirection) -> Result<Option<Quotation>> {
+        let req = GetOrderBookRequest {
+            figi: self.settings.figi.clone(),
+            depth: 1,
+            instrument_id: self.settings.uid.clone(),
+        };
+        let response = self.broker.get_order_book(req).await?;
+        let book = response.get_ref();
+        let best = if direction == OrderDirection::Sell { &book.bids } else { &book.asks };
+        Ok(best.first().and_then(|order| order.price.clone()))
     }
 
     async fn place_order(&mut self, lots: i64, price: Quotation, direction: OrderDirection)
-        -> Result<String, Status> {
+        -> Result<String> {
+        self.post_order(lots, Some(price), direction, OrderType::Limit).await
+    }
+
+    async fn post_order(&mut self, lots: i64, price: Option<Quotation>,
+                        direction: OrderDirection, order_type: OrderType)
+        -> Result<String> {
         self.order_id = {
             let next = self.order_id.0 + 1;
             let next = (next, next.to_string());
-            Self::update_order_id(&next.1);
+            Self::update_order_id(&self.account, &next.1)?;
             next
         };
         let req = PostOrderRequest {
             figi: self.settings.figi.clone(),
             quantity: lots,
-            price: Some(price),
+            price: price.clone(),
             direction: i32::from(direction),
-            account_id: self.settings.account_id.clone(),
-            order_type: OrderType::Limit.into(),
+            account_id: self.account.id.clone(),
+            order_type: order_type.into(),
             order_id: self.order_id.1.clone(),
             instrument_id: self.settings.uid.clone(),
         };
-        return match self.order_client.post_order(req).await {
-            Ok(response) => Ok(response.get_ref().order_id.clone()),
-            Err(err) => Err(err)
+        let response = match self.broker.post_order(req).await {
+            Ok(response) => response,
+            Err(err) => {
+                self.notifier.send(Event::OrderRejected {
+                    order_id: self.order_id.1.clone(),
+                    message: err.message().to_string(),
+                });
+                self.pending.push(Pending::Rejected(Rejection {
+                    order_id: self.order_id.1.clone(),
+                    message: err.message().to_string(),
+                }));
+                return Err(err.into());
+            }
         };
+        let order_id = response.get_ref().order_id.clone();
+        let price = price.map(|price| self.money(&price));
+        self.notifier.send(Event::OrderPlaced { order_id: order_id.clone(), direction, lots, price });
+        Ok(order_id)
     }
 
-    async fn get_order_time(&mut self, order_id: String) -> Result<String, Status> {
+    async fn get_order_state(&mut self, order_id: String) -> Result<OrderState> {
         let req = GetOrderStateRequest {
-            account_id: self.settings.account_id.clone(),
+            account_id: self.account.id.clone(),
             order_id
         };
-        let response = self.order_client.get_order_state(req).await?;
+        Ok(self.broker.get_order_state(req).await?.into_inner())
+    }
+
+    async fn get_order_time(&mut self, order_id: String) -> Result<String> {
+        let response = self.get_order_state(order_id).await?;
 
-        if let Some(time) = response.get_ref().order_date.clone() {
+        if let Some(time) = response.order_date {
             let dt = {
-                let dt: DateTime<Utc> = Utc.timestamp_nanos(time.nanos as i64);
-                dt.with_timezone(&Moscow)
+                let dt: Option<DateTime<Utc>> = Utc.timestamp_opt(time.seconds, time.nanos as u32).single();
+                dt.map(|dt| dt.with_timezone(&Moscow))
             };
-            Ok(dt.format("%H:%M").to_string())
+            Ok(dt.map(|dt| dt.format("%H:%M").to_string())
+                .unwrap_or_else(|| "time undetermined".to_string()))
         } else {
             Ok("time undetermined".to_string())
         }
     }
 
+    fn today() -> String {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        now.with_timezone(&Moscow).date_naive().to_string()
+    }
+
+    fn read_day_stat(&self) -> Result<DayStat> {
+        DayStat::load(&self.account.name)
+    }
+
+    fn read_stat(&self) -> Result<Statistics> {
+        Statistics::load(&self.account.name)
+    }
+
+    /// Writes the session trading time to `stat.json` and rewrites `today.json`,
+    /// returns today's statistics.
+    fn flush_stat(&mut self) -> Result<DayStat> {
+        let mut stat = self.read_stat()?;
+        stat.trade_secs += self.started.elapsed().as_secs() as u32;
+        self.started = Instant::now();
+        write_json(&self.account.file("stat.json"), &stat)?;
+
+        let day = self.read_day_stat()?;
+        write_json(&self.account.file("today.json"), &day)?;
+        Ok(day)
+    }
+
     async fn write_stat(&mut self,
                   Position {
                       price_in: p_in,
                       price_out: p_out,
                       lots: l,
                       direction: d,
-                      id: (open_id, close_id), ..}: Position) {
-        let mut file = std::fs::read_to_string(
-            Self::ADD_INFO_PATH.to_owned() + "today.json").unwrap();
-        let mut stat: DayStat = serde_json::from_str(file.as_str()).unwrap();
+                      intended: (intended_in, intended_out),
+                      opened,
+                      id: (open_id, close_id), ..}: Position) -> Result<()> {
+        let mut stat = self.read_day_stat()?;
+        let mut total = self.read_stat()?;
 
         let (profit, turnover) = {
             let p = if d == OrderDirection::Buy {
@@ -670,11 +1611,9 @@ impl<'a> Bot {
                 p_in.clone() - p_out.clone()
             };
             let p = Quotation { units: l, nano: 0 } * p;
-            let p_after_fees = p.clone() * self.settings.fee_rate.clone();
-            let p_after_tax = p_after_fees.clone() * self.settings.tax_rate.clone();
             let t = (p_in.clone() + p_out.clone()) * Quotation { units: l, nano: 0 };
             (
-                ProfitStat { net: p, after_fees: p_after_fees, after_tax: p_after_tax },
+                ProfitStat::new(p, &t, &self.settings.fee_rate, &self.settings.tax_rate),
                 t.units as u32
             )
         };
@@ -684,21 +1623,41 @@ impl<'a> Bot {
             direction: d == OrderDirection::Buy,
             profit: profit.clone(),
             turnover,
-            time_in: self.get_order_time(open_id).await.unwrap(),
-            time_out: self.get_order_time(close_id).await.unwrap(),
+            time_in: self.get_order_time(open_id).await?,
+            time_out: self.get_order_time(close_id).await?,
+            date: stat.date.clone(),
+            lots: l,
+            held_secs: opened.elapsed().as_secs(),
+            intended_in: (intended_in.units, intended_in.nano),
+            intended_out: (intended_out.units, intended_out.nano),
+            ticker: self.settings.ticker.clone(),
+            strategy: self.account.strategy.clone(),
         };
         stat.trades_count += 1;
-        stat.trades.push(trade);
         stat.turnover += turnover;
-        stat.profit = ProfitStat {
-            net: stat.profit.net + profit.net,
-            after_fees: stat.profit.after_fees + profit.after_fees,
-            after_tax: stat.profit.after_tax + profit.after_tax,
-        };
-        file.write_str(serde_json::to_string(&stat).unwrap().as_str()).unwrap();
+        stat.profit = stat.profit.clone() + profit.clone();
+        total.trades_count += 1;
+        total.turnover += turnover;
+        total.profit = total.profit.clone() + profit.clone();
+        match total.days.last_mut() {
+            Some(day) if day.date == stat.date => {
+                day.profit = stat.profit.clone();
+                day.trades_count = stat.trades_count;
+            },
+            _ => total.days.push(DayResult {
+                date: stat.date.clone(),
+                profit: stat.profit.clone(),
+                trades_count: stat.trades_count,
+            }),
+        }
+        self.notifier.send(Event::PositionClosed { lots: l, price_in: p_in, price_out: self.money(&p_out), profit });
+        total.trades.push(trade.clone());
+        stat.trades.push(trade);
+        write_json(&self.account.file("today.json"), &stat)?;
+        write_json(&self.account.file("stat.json"), &total)
     }
 
-    async fn update_position_state(&mut self, mut pos: Position) -> Result<State, Status> {
+    async fn update_position_state(&mut self, mut pos: Position) -> Result<State> {
         let order_id = {
             if pos.state == PosState::WaitClose || pos.state == PosState::PartialClose {
                 pos.id.1.clone()
@@ -707,38 +1666,82 @@ impl<'a> Bot {
             }
         };
         let req = GetOrderStateRequest {
-            account_id: self.settings.account_id.clone(),
+            account_id: self.account.id.clone(),
             order_id,
         };
-        let response = self.order_client.get_order_state(req.clone()).await?;
+        let response = self.broker.get_order_state(req.clone()).await?;
         let response = response.get_ref();
 
-        return match response.execution_report_status {
+        match response.execution_report_status {
             1 => {
                 let price = {
-                    let mv = response.average_position_price.as_ref().unwrap();
+                    let mv = response.average_position_price.clone().unwrap_or_default();
                     Quotation { units: mv.units, nano: mv.nano }
                 };
-                if pos.state == PosState::WaitClose || pos.state == PosState::PartialClose {
-                    self.write_stat(pos).await;
+                self.notifier.send(Event::OrderFilled {
+                    order_id: response.order_id.clone(),
+                    lots: response.lots_executed,
+                    price: self.money(&price),
+                });
+                self.dashboard.publish(Feed::Fill {
+                    order_id: response.order_id.clone(),
+                    lots: response.lots_executed,
+                    price: price.clone(),
+                });
+                let closing = pos.state == PosState::WaitClose || pos.state == PosState::PartialClose;
+                self.pending.push(Pending::Fill(Fill {
+                    order_id: response.order_id.clone(),
+                    direction: OrderDirection::from_i32(response.direction).unwrap_or(pos.direction),
+                    lots: response.lots_executed,
+                    price: price.clone(),
+                    opening: !closing,
+                }));
+                if closing {
+                    // the statistics go by the fill, not by the price of the close order
+                    pos.price_out = price;
+                    self.write_stat(pos).await?;
                     Ok(State::Seeking(self.get_money().await?))
                 } else {
+                    self.notifier.send(Event::PositionOpened {
+                        lots: response.lots_executed,
+                        price: self.money(&price),
+                    });
                     pos.state = PosState::Hold;
+                    // the fill may differ from the order in both the price and the lots,
+                    // the take profit and the statistics go by the fill
+                    pos.price_in = price.clone();
+                    pos.lots = response.lots_executed;
+                    pos.opened = Instant::now();
                     let (p, l, d) =
                         self.strategy.get_take_profit(price, response.lots_executed);
                     pos.id.1 = self.place_order(l, p.clone(), d).await?;
+                    pos.span.record("close_id", pos.id.1.as_str());
+                    info!(price = %p, "close order placed");
                     pos.state = PosState::WaitClose;
+                    pos.intended.1 = p.clone();
                     pos.price_out = p;
                     Ok(State::InPosition(pos))
                 }
             }, // Исполнена
             5 => {
-                pos.state = PosState::PartialOpen;
-                pos.price_in = {
-                    let mv = response.average_position_price.as_ref().unwrap();
-                    Quotation { units: mv.units, nano: mv.nano }
-                };
-                pos.lots = response.lots_executed;
+                let closing = pos.state == PosState::WaitClose || pos.state == PosState::PartialClose;
+                let partial = if closing { PosState::PartialClose } else { PosState::PartialOpen };
+                // the close order leaves the position as it is until it is filled
+                if pos.state != partial || !closing && pos.lots != response.lots_executed {
+                    self.notifier.send(Event::OrderPartiallyFilled {
+                        order_id: response.order_id.clone(),
+                        lots_executed: response.lots_executed,
+                        lots_requested: response.lots_requested,
+                    });
+                }
+                pos.state = partial;
+                if !closing {
+                    pos.price_in = {
+                        let mv = response.average_position_price.clone().unwrap_or_default();
+                        Quotation { units: mv.units, nano: mv.nano }
+                    };
+                    pos.lots = response.lots_executed;
+                }
                 // Попозже добавлю, что бот будет закрывать ещё не полностью исполненные
                 //  позиции, и будет изменять заявку закрытия по мере исполнения открытия
                 Ok(State::InPosition(pos))
@@ -746,46 +1749,81 @@ impl<'a> Bot {
             4 => {
                 Ok(State::InPosition(pos))
             }, // Новая (Только размещена и 0 исполнено)
-            2 | 3 | _ => {
+            status => {
+                let order_id = response.order_id.clone();
+                match status {
+                    2 => {
+                        self.pending.push(Pending::Rejected(Rejection {
+                            order_id: order_id.clone(),
+                            message: String::new(),
+                        }));
+                        self.notifier.send(Event::OrderRejected { order_id, message: String::new() })
+                    },
+                    3 => self.notifier.send(Event::OrderCancelled { order_id }),
+                    _ => {},
+                }
                 self.solve_problems().await?;
                 Ok(State::Seeking(self.money.clone()))
             } // Отклонена | Отменена пользователем (?) | none
         }
     }
 
-    async fn cancel_order(&mut self, order_id: String) -> Result<(), Status> {
-        if let Err(err) = self.order_client.cancel_order(
+    /// Cancels the order without panicking, returns `true` if it was cancelled.
+    async fn try_cancel_order(&mut self, order_id: String) -> bool {
+        let cancelled = self.broker.cancel_order(
             CancelOrderRequest {
-                order_id,
-                account_id: self.settings.account_id.clone()
+                order_id: order_id.clone(),
+                account_id: self.account.id.clone()
+            }).await.is_ok();
+        if cancelled {
+            self.notifier.send(Event::OrderCancelled { order_id });
+        }
+        cancelled
+    }
+
+    async fn cancel_order(&mut self, order_id: String) -> Result<()> {
+        if let Err(err) = self.broker.cancel_order(
+            CancelOrderRequest {
+                order_id: order_id.clone(),
+                account_id: self.account.id.clone()
             }).await {
-            send_message(self.tg_bot.clone(), format!("Can't cancel order: {}", err)).await;
-            panic!("Can't cancel order: {}", err)
+            self.notifier.send(Event::Error(format!("Can't cancel order: {}", err)));
+            return Err(err.into());
         }
+        self.notifier.send(Event::OrderCancelled { order_id });
         Ok(())
     }
 
-    async fn portfolio_control(&mut self) -> Result<bool, Status> {
+    async fn portfolio_control(&mut self) -> Result<bool> {
+        let expected = match self.state.as_ref().unwrap() {
+            State::InPosition(pos) => pos.lots,
+            State::Seeking(..) | State::Sleeping(_, _) => 0,
+        };
+        let streamed = {
+            let portfolio = self.portfolio.borrow();
+            (!portfolio.loading).then(|| portfolio.quantity(&self.settings.uid))
+        };
+        if streamed == Some(expected) {
+            return Ok(true);
+        }
+        // the stream may be down or lag behind the order state
         let req = PositionsRequest {
-            account_id: self.settings.account_id.clone(),
+            account_id: self.account.id.clone(),
         };
         let positions = {
-            let response = self.operation_client.get_positions(req).await?;
-            let pos = &response.get_ref().securities;
-            pos[0].balance + pos[0].blocked
+            let response = self.broker.get_positions(req).await?;
+            response.get_ref().securities.iter()
+                .filter(|security| security.instrument_uid == self.settings.uid)
+                .map(|security| security.balance + security.blocked)
+                .sum::<i64>()
         };
-
-        return match self.state.as_ref().unwrap() {
-            State::InPosition(pos) => Ok(pos.lots == positions),
-            State::Seeking(..) | State::Sleeping(_, _)
-            => Ok(positions == 0),
-        }
+        Ok(positions == expected)
     }
-    async fn orders_control(&mut self) -> Result<bool, Status> {
+    async fn orders_control(&mut self) -> Result<bool> {
         let req = GetOrdersRequest {
-            account_id: self.settings.account_id.clone()
+            account_id: self.account.id.clone()
         };
-        let response = self.order_client.get_orders(req).await?;
+        let response = self.broker.get_orders(req).await?;
         let orders = &response.get_ref().orders;
         match self.state.as_ref().unwrap() {
             State::Sleeping(..) | State::Seeking(_) => {
@@ -813,48 +1851,42 @@ impl<'a> Bot {
         }
     }
 
-    async fn solve_problems (&mut self) -> Result<(), Status> {
-        match (self.portfolio_control().await, self.orders_control().await) {
-            (Ok(false), Ok(false)) => {
-                // Надо чёт с портфелем делать
-                send_message(self.tg_bot.clone(),
-                             "Unexpected orders and portfolio state".to_string()).await;
-                panic!("Unexpected order and portfolio state")
-            },
-            (Ok(true), Ok(false)) => {
-                send_message(self.tg_bot.clone(),
-                             "Unexpected orders".to_string()).await;
-                panic!("Unexpected orders")
-            },
-            (Ok(false), Ok(true)) => {
-                send_message(self.tg_bot.clone(),
-                             "Unexpected portfolio state".to_string()).await;
-                panic!("Unexpected portfolio state")
-                // Тип продать что-то или хз
-            }
-            (Err(er), Ok(_)) | (Ok(_), Err(er))
-            => return Err(er),
-            _ =>  {
-                send_message(self.tg_bot.clone(),
-                             "Unexpected in `fn solve_problems`".to_string()).await;
-                panic!("Unexpected in `fn solve_problems`");
-            }
-        }
+    async fn solve_problems (&mut self) -> Result<()> {
+        let problem = match (self.portfolio_control().await?, self.orders_control().await?) {
+            (true, true) => return Ok(()),
+            // Надо чёт с портфелем делать
+            (false, false) => "unexpected orders and portfolio state",
+            (true, false) => "unexpected orders",
+            // Тип продать что-то или хз
+            (false, true) => "unexpected portfolio state",
+        };
+        Err(Error::Inconsistent(problem.to_string()))
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::zero_prefixed_literal)] // the nano as 2 + 7 digits
 mod tests {
     use super::*;
     #[test]
     fn read_write() {
-        let x = Bot::get_order_id();
+        let dir = std::env::temp_dir().join(format!("bot-{}", std::process::id()));
+        let account = Account {
+            id: "2000000001".to_string(),
+            name: dir.to_string_lossy().into_owned(),
+            title: String::new(),
+            kind: crate::tcs::AccountType::Tinkoff,
+            strategy: "scalp".to_string(),
+            margin: None,
+        };
+        crate::create_account_files(std::slice::from_ref(&account.name)).unwrap();
+        let x = Bot::get_order_id(&account).unwrap();
         assert_eq!(x.1, x.0.to_string());
-        Bot::update_order_id(&(x.0 + 1).to_string());
-        let y = Bot::get_order_id();
+        Bot::update_order_id(&account, &(x.0 + 1).to_string()).unwrap();
+        let y = Bot::get_order_id(&account).unwrap();
         assert_eq!(y.1, y.0.to_string());
         assert_eq!(x.0 + 1, y.0);
-        Bot::update_order_id(&(x.0).to_string());
+        std::fs::remove_dir_all(dir).unwrap();
     }
     #[test]
     fn sub_quotation() {
@@ -896,12 +1928,55 @@ mod tests {
         assert_eq!(Quotation { units: 816, nano: 05_1600000 }, x * y);
         let x = Quotation { units: 999999, nano: 99_9999999 };
         let y = Quotation { units: 999999, nano: 99_9999999 };
-        x * y;
+        let _ = x * y;
         let x = Quotation { units: 20, nano: 14_2112222 };
         let y = Quotation { units: 0, nano: 14_0412543 };
         assert_eq!(Quotation { units: 2, nano: 82_8205198 }, x * y);
     }
     #[test]
+    fn average_quotation() {
+        let parts = [
+            (Quotation { units: 5, nano: 90_0000000 }, 1),
+            (Quotation { units: 6, nano: 00_0000000 }, 3),
+        ];
+        assert_eq!(Quotation { units: 5, nano: 97_5000000 }, Quotation::average(&parts));
+        assert_eq!(Quotation::default(), Quotation::average(&[]));
+    }
+    #[test]
+    fn display_quotation() {
+        assert_eq!("5.91", Quotation { units: 5, nano: 91_0000000 }.to_string());
+        assert_eq!("5.05", Quotation { units: 5, nano: 05_0000000 }.to_string());
+        assert_eq!("6", Quotation { units: 6, nano: 0 }.to_string());
+        assert_eq!("-0.5", Quotation { units: -1, nano: 50_0000000 }.to_string());
+        assert_eq!("-0.5", Quotation::from_nanos(-500_000_000).to_string());
+    }
+    #[test]
+    fn migrate_stat() {
+        let money = |units, nano| Quotation { units, nano };
+        let profit = |net, after_fees, after_tax| ProfitStat { net, after_fees, after_tax };
+        let old = TradeStat { turnover: 10000, profit: profit(money(100, 0), money(0, 0), money(0, 0)), ..Default::default() };
+        let new = TradeStat {
+            date: "2023-02-09".to_string(),
+            profit: profit(money(10, 0), money(9, 0), money(7, 83_0000000)),
+            ..Default::default()
+        };
+        let mut stat: Statistics = serde_json::from_value(serde_json::json!({
+            "bot_start_date": "2023-02-08", "trade_secs": 0, "turnover": 10000, "trades_count": 2,
+            "profit": profit(money(110, 0), money(9, 0), money(7, 83_0000000)),
+            "trades": [old, new],
+            "days": [DayResult { date: "2023-02-09".to_string(), profit: profit(money(110, 0), money(9, 0), money(7, 83_0000000)), trades_count: 2 }],
+        })).unwrap();
+        let (fee_rate, tax_rate) = (money(0, 500_000), money(0, 13_0000000));
+
+        assert!(stat.migrate(&fee_rate, &tax_rate));
+        assert_eq!((money(95, 0), money(82, 65_0000000)), (stat.trades[0].profit.after_fees.clone(), stat.trades[0].profit.after_tax.clone()));
+        assert_eq!(money(9, 0), stat.trades[1].profit.after_fees);
+        for total in [&stat.profit, &stat.days[0].profit] {
+            assert_eq!((money(104, 0), money(90, 48_0000000)), (total.after_fees.clone(), total.after_tax.clone()));
+        }
+        assert!(!stat.migrate(&fee_rate, &tax_rate));
+    }
+    #[test]
     fn ser_deser_test() {
         let x = Quotation { units: 2, nano: 10_0000000 };
         let ss = serde_json::to_string(&x).unwrap();
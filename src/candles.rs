@@ -0,0 +1,298 @@
+//! Candle series of several intervals: the smallest one is loaded from the broker,
+//! the others are built from it.
+
+use chrono::{TimeZone, Utc};
+use chrono_tz::Europe::Moscow;
+use prost_types::Timestamp;
+
+use crate::tcs::{Candle, CandleInterval, HistoricCandle, Quotation};
+
+
+/// Length of the interval in seconds, none for `Unspecified`.
+pub fn seconds(interval: CandleInterval) -> Option<i64> {
+    match interval {
+        CandleInterval::Unspecified => None,
+        CandleInterval::CandleInterval1Min => Some(60),
+        CandleInterval::CandleInterval5Min => Some(300),
+        CandleInterval::CandleInterval15Min => Some(900),
+        CandleInterval::Hour => Some(3600),
+        CandleInterval::Day => Some(86_400),
+    }
+}
+
+/// The longest period `GetCandles` gives at once for the interval.
+pub fn max_period(interval: CandleInterval) -> i64 {
+    match interval {
+        CandleInterval::Hour => 7 * 86_400,
+        CandleInterval::Day => 365 * 86_400,
+        _ => 86_400,
+    }
+}
+
+/// Start of the candle of `interval` the moment falls into, days begin at Moscow midnight.
+pub fn start(time: i64, interval: CandleInterval) -> i64 {
+    let Some(length) = seconds(interval) else {
+        return time;
+    };
+    if interval != CandleInterval::Day {
+        return time - time.rem_euclid(length);
+    }
+    Utc.timestamp_opt(time, 0).single()
+        .and_then(|time| time.with_timezone(&Moscow).date_naive().and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Moscow.from_local_datetime(&midnight).earliest())
+        .map(|midnight| midnight.timestamp())
+        .unwrap_or(time)
+}
+
+fn time(candle: &HistoricCandle) -> i64 {
+    candle.time.as_ref().map(|t| t.seconds).unwrap_or_default()
+}
+
+/// A candle from the market data stream, it is complete when the next one comes.
+impl From<&Candle> for HistoricCandle {
+    fn from(candle: &Candle) -> HistoricCandle {
+        HistoricCandle {
+            open: candle.open.clone(),
+            high: candle.high.clone(),
+            low: candle.low.clone(),
+            close: candle.close.clone(),
+            volume: candle.volume,
+            time: candle.time.clone(),
+            is_complete: false,
+        }
+    }
+}
+
+/// Candles of one interval by time, only the last one may be incomplete.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    interval: CandleInterval,
+    candles: Vec<HistoricCandle>,
+}
+
+impl Series {
+    pub fn new(interval: CandleInterval) -> Series {
+        Series { interval, candles: Vec::new() }
+    }
+
+    pub fn interval(&self) -> CandleInterval {
+        self.interval
+    }
+
+    pub fn candles(&self) -> &[HistoricCandle] {
+        &self.candles
+    }
+
+    /// Without the forming candle.
+    pub fn complete(&self) -> &[HistoricCandle] {
+        match self.candles.last() {
+            Some(last) if !last.is_complete => &self.candles[..self.candles.len() - 1],
+            _ => &self.candles,
+        }
+    }
+
+    pub fn last(&self) -> Option<&HistoricCandle> {
+        self.candles.last()
+    }
+
+    /// Adds a new candle or replaces the one of the same time.
+    pub fn push(&mut self, candle: HistoricCandle) {
+        match self.candles.binary_search_by_key(&time(&candle), time) {
+            Ok(i) => self.candles[i] = candle,
+            Err(i) => self.candles.insert(i, candle),
+        }
+        // a newer candle means the older ones are over
+        if let Some((_, older)) = self.candles.split_last_mut() {
+            older.iter_mut().for_each(|candle| candle.is_complete = true);
+        }
+    }
+}
+
+/// Builds the candles of a longer `interval` from shorter ones of `part`, sorted by time.
+/// A candle is complete once its last part is and its time is over,
+/// or when a later candle has started.
+pub fn aggregate(candles: &[HistoricCandle], part: CandleInterval, interval: CandleInterval)
+    -> Vec<HistoricCandle> {
+    let mut result: Vec<HistoricCandle> = Vec::new();
+    for candle in candles {
+        let begins = start(time(candle), interval);
+        match result.last_mut() {
+            Some(last) if time(last) == begins => {
+                last.high = extreme(&last.high, &candle.high, |a, b| a > b);
+                last.low = extreme(&last.low, &candle.low, |a, b| a < b);
+                last.close = candle.close.clone();
+                last.volume += candle.volume;
+            },
+            _ => {
+                if let Some(last) = result.last_mut() {
+                    last.is_complete = true;
+                }
+                result.push(HistoricCandle {
+                    time: Some(Timestamp { seconds: begins, nanos: 0 }),
+                    ..candle.clone()
+                });
+            },
+        }
+    }
+    if let (Some(last), Some(part_last)) = (result.last_mut(), candles.last()) {
+        let ends = time(last) + seconds(interval).unwrap_or_default();
+        let part_ends = time(part_last) + seconds(part).unwrap_or_default();
+        last.is_complete = part_last.is_complete && part_ends >= ends;
+    }
+    result
+}
+
+/// The higher or the lower of two prices, `better(a, b)` tells if `a` wins.
+fn extreme(a: &Option<Quotation>, b: &Option<Quotation>, better: fn(&Quotation, &Quotation) -> bool)
+    -> Option<Quotation> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if better(b, a) { b.clone() } else { a.clone() }),
+        (a, b) => a.clone().or_else(|| b.clone()),
+    }
+}
+
+/// The series a strategy asked for, all kept up to date from the smallest interval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candles {
+    base: Series,
+    higher: Vec<Series>,
+    /// Intervals whose candle was completed by the last update.
+    closed: Vec<CandleInterval>,
+}
+
+impl Candles {
+    /// The smallest of `intervals` is loaded, one minute if none is given.
+    pub fn new(intervals: &[CandleInterval]) -> Candles {
+        let mut intervals: Vec<_> = intervals.iter().copied()
+            .filter(|&interval| seconds(interval).is_some())
+            .collect();
+        intervals.sort_by_key(|&interval| seconds(interval));
+        intervals.dedup();
+        let base = intervals.first().copied().unwrap_or(CandleInterval::CandleInterval1Min);
+        Candles {
+            base: Series::new(base),
+            higher: intervals.into_iter().skip(1).map(Series::new).collect(),
+            closed: Vec::new(),
+        }
+    }
+
+    /// The interval loaded from the broker.
+    pub fn base(&self) -> CandleInterval {
+        self.base.interval
+    }
+
+    pub fn series(&self, interval: CandleInterval) -> Option<&Series> {
+        std::iter::once(&self.base)
+            .chain(&self.higher)
+            .find(|series| series.interval == interval)
+    }
+
+    /// Intervals that got a new complete candle with the last update.
+    pub fn closed(&self) -> &[CandleInterval] {
+        &self.closed
+    }
+
+    /// Where the next request should begin: the last known candle, it may still change.
+    pub fn resume_from(&self) -> Option<i64> {
+        self.base.last().map(time)
+    }
+
+    /// Takes the candles of the base interval. Returns whether anything changed.
+    pub fn update(&mut self, candles: impl IntoIterator<Item = HistoricCandle>) -> bool {
+        let before = self.base.clone();
+        let completed = |series: &Series| series.complete().len();
+        let counts: Vec<usize> = std::iter::once(&self.base).chain(&self.higher).map(completed).collect();
+        for candle in candles {
+            self.base.push(candle);
+        }
+        if self.base == before {
+            self.closed.clear();
+            return false;
+        }
+        for series in &mut self.higher {
+            series.candles = aggregate(&self.base.candles, self.base.interval, series.interval);
+        }
+        self.closed = std::iter::once(&self.base).chain(&self.higher)
+            .zip(counts)
+            .filter(|(series, count)| completed(series) > *count)
+            .map(|(series, _)| series.interval)
+            .collect();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10:00 Moscow.
+    const MORNING: i64 = 1_675_926_000;
+
+    fn candle(minute: i64, price: i64, volume: i64, is_complete: bool) -> HistoricCandle {
+        let q = |units| Some(Quotation { units, nano: 0 });
+        HistoricCandle {
+            open: q(price),
+            high: q(price + 2),
+            low: q(price - 1),
+            close: q(price + 1),
+            volume,
+            time: Some(Timestamp { seconds: MORNING + minute * 60, nanos: 0 }),
+            is_complete,
+        }
+    }
+
+    #[test]
+    fn series_updates() {
+        let mut series = Series::new(CandleInterval::CandleInterval1Min);
+        series.push(candle(0, 100, 10, true));
+        series.push(candle(1, 101, 5, false));
+        assert_eq!(series.complete().len(), 1);
+        // the forming candle changes
+        series.push(candle(1, 102, 8, false));
+        assert_eq!(series.candles().len(), 2);
+        assert_eq!(series.last().unwrap().volume, 8);
+        // the next one closes it even if the broker said nothing
+        series.push(candle(2, 103, 1, false));
+        assert_eq!(series.complete().len(), 2);
+        assert_eq!(series.complete()[1].volume, 8);
+    }
+    #[test]
+    fn higher_intervals() {
+        let minutes: Vec<_> = (0..7).map(|m| candle(m, 100 + m, 10, m < 6)).collect();
+        let five = aggregate(&minutes, CandleInterval::CandleInterval1Min, CandleInterval::CandleInterval5Min);
+        assert_eq!(five.len(), 2);
+        assert_eq!(five[0].open, Some(Quotation { units: 100, nano: 0 }));
+        assert_eq!(five[0].close, Some(Quotation { units: 105, nano: 0 }));
+        assert_eq!(five[0].high, Some(Quotation { units: 106, nano: 0 }));
+        assert_eq!(five[0].low, Some(Quotation { units: 99, nano: 0 }));
+        assert_eq!(five[0].volume, 50);
+        assert!(five[0].is_complete);
+        assert_eq!(five[1].time.as_ref().unwrap().seconds, MORNING + 300);
+        assert!(!five[1].is_complete);
+
+        let day = aggregate(&minutes, CandleInterval::CandleInterval1Min, CandleInterval::Day);
+        assert_eq!(day.len(), 1);
+        assert_eq!(day[0].time.as_ref().unwrap().seconds, MORNING - 10 * 3600);
+        assert_eq!(day[0].volume, 70);
+    }
+    #[test]
+    fn several_intervals() {
+        let mut candles = Candles::new(&[CandleInterval::CandleInterval5Min, CandleInterval::CandleInterval1Min]);
+        assert_eq!(candles.base(), CandleInterval::CandleInterval1Min);
+        assert!(candles.series(CandleInterval::Hour).is_none());
+
+        assert!(candles.update((0..5).map(|m| candle(m, 100, 10, true))));
+        assert_eq!(candles.closed(), [CandleInterval::CandleInterval1Min, CandleInterval::CandleInterval5Min]);
+        assert_eq!(candles.resume_from(), Some(MORNING + 4 * 60));
+
+        // the same candles again
+        assert!(!candles.update([candle(4, 100, 10, true)]));
+        assert!(candles.closed().is_empty());
+
+        assert!(candles.update([candle(4, 100, 10, true), candle(5, 101, 1, false)]));
+        assert!(candles.closed().is_empty());
+        let five = candles.series(CandleInterval::CandleInterval5Min).unwrap();
+        assert_eq!(five.candles().len(), 2);
+        assert_eq!(five.complete().len(), 1);
+    }
+}
@@ -0,0 +1,242 @@
+use std::sync::Once;
+use plotters::prelude::*;
+use image::{ImageEncoder, codecs::png::PngEncoder};
+
+use crate::bot::{DayResult, TradeStat};
+use crate::tcs::Quotation;
+
+
+pub type ChartError = Box<dyn std::error::Error + Send + Sync>;
+/// One of the charts below with its data.
+pub type Draw = Box<dyn FnOnce() -> Result<Vec<u8>, ChartError> + Send>;
+
+const SIZE: (u32, u32) = (900, 500);
+const FONT: &str = "sans-serif";
+
+static FONT_INIT: Once = Once::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartKind {
+    Price,
+    Equity,
+    DailyPnl,
+}
+
+impl ChartKind {
+    pub fn parse(arg: &str) -> Option<ChartKind> {
+        match arg.trim() {
+            "" | "price" => Some(ChartKind::Price),
+            "equity" => Some(ChartKind::Equity),
+            "pnl" => Some(ChartKind::DailyPnl),
+            _ => None,
+        }
+    }
+}
+
+fn register_font() {
+    FONT_INIT.call_once(|| {
+        let _ = plotters::style::register_font(
+            FONT, FontStyle::Normal, include_bytes!("../assets/DejaVuSans.ttf"));
+    });
+}
+
+/// Renders on the blocking threads, plotters would hold up the runtime.
+pub async fn render_blocking(draw: Draw) -> Result<Vec<u8>, ChartError> {
+    tokio::task::spawn_blocking(draw).await?
+}
+
+/// Renders into an RGB buffer and encodes it as PNG.
+fn render<F>(draw: F) -> Result<Vec<u8>, ChartError>
+    where F: FnOnce(DrawingArea<BitMapBackend, plotters::coord::Shift>) -> Result<(), ChartError> {
+    register_font();
+    let mut buffer = vec![0u8; (SIZE.0 * SIZE.1 * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, SIZE).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| e.to_string())?;
+        draw(root.clone())?;
+        root.present().map_err(|e| e.to_string())?;
+    }
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).write_image(&buffer, SIZE.0, SIZE.1, image::ColorType::Rgb8)?;
+    Ok(png)
+}
+
+fn range(values: impl Iterator<Item = f64> + Clone) -> std::ops::Range<f64> {
+    let min = values.clone().fold(f64::INFINITY, f64::min);
+    let max = values.fold(f64::NEG_INFINITY, f64::max);
+    if !min.is_finite() || !max.is_finite() {
+        return 0.0..1.0;
+    }
+    let pad = ((max - min) * 0.05).max(0.01);
+    (min - pad)..(max + pad)
+}
+
+/// "HH:MM" -> minutes since midnight.
+fn minutes(time: &str) -> Option<f64> {
+    let (h, m) = time.split_once(':')?;
+    Some(h.parse::<f64>().ok()? * 60.0 + m.parse::<f64>().ok()?)
+}
+
+fn price(&(units, nano): &(i64, i32)) -> f64 {
+    Quotation { units, nano }.to_f64()
+}
+
+/// Intraday price with entry (green) and exit (red) markers.
+/// `prices` are (minutes since midnight, close price).
+pub fn price_chart(prices: &[(f64, f64)], trades: &[TradeStat]) -> Result<Vec<u8>, ChartError> {
+    let marks_in: Vec<(f64, f64)> = trades.iter()
+        .filter_map(|t| Some((minutes(&t.time_in)?, price(&t.price_in))))
+        .collect();
+    let marks_out: Vec<(f64, f64)> = trades.iter()
+        .filter_map(|t| Some((minutes(&t.time_out)?, price(&t.price_out))))
+        .collect();
+    let all = prices.iter().chain(&marks_in).chain(&marks_out);
+    let x = range(all.clone().map(|p| p.0));
+    let y = range(all.map(|p| p.1));
+
+    render(|root| {
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Цена и сделки за день", (FONT, 24))
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .build_cartesian_2d(x, y)
+            .map_err(|e| e.to_string())?;
+        chart.configure_mesh()
+            .x_label_formatter(&|m| format!("{:02}:{:02}", (*m as i64) / 60, (*m as i64) % 60))
+            .draw().map_err(|e| e.to_string())?;
+        chart.draw_series(LineSeries::new(prices.iter().cloned(), &BLUE))
+            .map_err(|e| e.to_string())?;
+        chart.draw_series(marks_in.iter().map(|&p| TriangleMarker::new(p, 8, GREEN.filled())))
+            .map_err(|e| e.to_string())?;
+        chart.draw_series(marks_out.iter().map(|&p| Cross::new(p, 6, RED.stroke_width(2))))
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+/// `title, USD`, the title alone if the currency is unknown.
+fn caption(title: &str, currency: &str) -> String {
+    if currency.is_empty() { title.to_string() } else { format!("{}, {}", title, currency.to_uppercase()) }
+}
+
+/// Cumulative net profit after every trade, in `currency`.
+pub fn equity_chart(trades: &[TradeStat], currency: &str) -> Result<Vec<u8>, ChartError> {
+    let mut equity = vec![(0.0, 0.0)];
+    for (i, trade) in trades.iter().enumerate() {
+        let last = equity.last().unwrap().1;
+        equity.push(((i + 1) as f64, last + trade.profit.net.to_f64()));
+    }
+    let x = 0.0..(trades.len().max(1) as f64);
+    let y = range(equity.iter().map(|p| p.1));
+
+    render(|root| {
+        let mut chart = ChartBuilder::on(&root)
+            .caption(caption("Кривая доходности", currency), (FONT, 24))
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .build_cartesian_2d(x, y)
+            .map_err(|e| e.to_string())?;
+        chart.configure_mesh()
+            .x_desc("сделка")
+            .draw().map_err(|e| e.to_string())?;
+        chart.draw_series(LineSeries::new(equity.iter().cloned(), &BLUE))
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+/// Net profit per day in `currency`, green for profitable days and red for the others.
+pub fn daily_pnl_chart(days: &[DayResult], currency: &str) -> Result<Vec<u8>, ChartError> {
+    let values: Vec<f64> = days.iter().map(|d| d.profit.net.to_f64()).collect();
+    let y = range(values.iter().cloned().chain([0.0]));
+
+    render(|root| {
+        let mut chart = ChartBuilder::on(&root)
+            .caption(caption("Прибыль по дням", currency), (FONT, 24))
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .build_cartesian_2d((0..days.len().max(1)).into_segmented(), y)
+            .map_err(|e| e.to_string())?;
+        chart.configure_mesh()
+            .disable_x_mesh()
+            .x_label_formatter(&|x| match x {
+                SegmentValue::CenterOf(i) => days.get(*i)
+                    .map(|d| d.date.clone())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            })
+            .draw().map_err(|e| e.to_string())?;
+        chart.draw_series(values.iter().enumerate().map(|(i, &v)| {
+            let style = if v >= 0.0 { GREEN.filled() } else { RED.filled() };
+            Rectangle::new([(SegmentValue::Exact(i), 0.0), (SegmentValue::Exact(i + 1), v)], style)
+        })).map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::ProfitStat;
+
+    const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+    fn trade(time_in: &str, time_out: &str, net: Quotation) -> TradeStat {
+        TradeStat {
+            time_in: time_in.to_string(),
+            time_out: time_out.to_string(),
+            price_in: (5, 90_0000000),
+            price_out: (5, 91_0000000),
+            direction: true,
+            turnover: 12,
+            profit: ProfitStat { net, ..ProfitStat::default() },
+            ..TradeStat::default()
+        }
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(ChartKind::parse(""), Some(ChartKind::Price));
+        assert_eq!(ChartKind::parse(" equity"), Some(ChartKind::Equity));
+        assert_eq!(ChartKind::parse("pnl"), Some(ChartKind::DailyPnl));
+        assert_eq!(ChartKind::parse("candles"), None);
+        assert_eq!(minutes("10:05"), Some(605.0));
+        assert_eq!(minutes("time undetermined"), None);
+        assert_eq!(caption("Прибыль по дням", "usd"), "Прибыль по дням, USD");
+        assert_eq!(caption("Прибыль по дням", ""), "Прибыль по дням");
+    }
+    #[test]
+    fn render_png() {
+        let trades = vec![
+            trade("10:05", "10:20", Quotation { units: 0, nano: 10_0000000 }),
+            trade("time undetermined", "11:00", Quotation { units: -1, nano: 0 }),
+        ];
+        let prices: Vec<(f64, f64)> = (600..700).map(|m| (m as f64, 5.9 + (m % 7) as f64 / 100.0)).collect();
+        let days = vec![
+            DayResult { date: "2023-02-08".to_string(), profit: ProfitStat::default(), trades_count: 0 },
+            DayResult {
+                date: "2023-02-09".to_string(),
+                profit: ProfitStat { net: Quotation { units: -1, nano: 0 }, ..ProfitStat::default() },
+                trades_count: 2,
+            },
+        ];
+        for png in [
+            price_chart(&prices, &trades).unwrap(),
+            equity_chart(&trades, "usd").unwrap(),
+            daily_pnl_chart(&days, "rub").unwrap(),
+            equity_chart(&[], "").unwrap(),
+            daily_pnl_chart(&[], "rub").unwrap(),
+        ] {
+            assert_eq!(png[..8], PNG_SIGNATURE);
+        }
+    }
+    #[tokio::test]
+    async fn render_off_runtime() {
+        let png = render_blocking(Box::new(|| equity_chart(&[], "rub"))).await.unwrap();
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        assert!(render_blocking(Box::new(|| Err("no data".into()))).await.is_err());
+    }
+}
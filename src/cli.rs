@@ -0,0 +1,523 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::Europe::Moscow;
+use clap::{Parser, Subcommand};
+use prost_types::Timestamp;
+use serde_json::{json, Map, Value};
+
+use crate::analytics::{Performance, Summary};
+use crate::bot::Statistics;
+use crate::error::{Error, Result};
+use crate::grpc::Broker;
+use crate::history;
+use crate::optimize;
+use crate::tcs::{
+    AccessLevel, AccountStatus, AccountType, CancelOrderRequest, CloseSandboxAccountRequest,
+    FindInstrumentRequest, GetOrderBookRequest, GetOrdersRequest, InstrumentIdType,
+    InstrumentRequest, MoneyValue, OrderDirection, OrderExecutionReportStatus, PositionsRequest, Quotation,
+    SandboxPayInRequest, TradingSchedulesRequest
+};
+
+
+#[derive(Parser, Debug)]
+#[command(about = "The trading bot and its tools")]
+pub struct Cli {
+    /// JSON instead of the tables.
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// `run` without one.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Runs the bots of the configured accounts.
+    Run,
+    /// Replays the strategy against the order books recorded to `history`, no broker is needed.
+    #[command(alias = "replay")]
+    Backtest {
+        strategy: String,
+        from: NaiveDate,
+        to: NaiveDate,
+        #[arg(long, default_value_t = 50)]
+        latency: u64,
+        #[arg(long, default_value_t = 100_000)]
+        money: i64,
+    },
+    /// Replays the parameter sets and prints the best ones, or the walk-forward folds.
+    Optimize {
+        strategy: String,
+        from: NaiveDate,
+        to: NaiveDate,
+        /// `name=from:to:step` or `name=value`.
+        #[arg(required = true, value_parser = optimize::Range::parse)]
+        ranges: Vec<optimize::Range>,
+        /// `grid`, `random:N` or `adaptive:N`.
+        #[arg(long, default_value = "grid", value_parser = optimize::Search::parse)]
+        search: optimize::Search,
+        /// `<train days>:<test days>`.
+        #[arg(long, value_parser = parse_walk)]
+        walk: Option<(usize, usize)>,
+        #[arg(long)]
+        workers: Option<usize>,
+        #[arg(long, default_value_t = 50)]
+        latency: u64,
+        #[arg(long, default_value_t = 100_000)]
+        money: i64,
+    },
+    /// The trade journal and the days of the period, the taxes of its whole years, in CSV and HTML.
+    Report {
+        from: String,
+        to: String,
+        /// All the configured accounts without it.
+        #[arg(long)]
+        account: Option<String>,
+        /// `reports/<from>_<to>` without it.
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Candles of the days `from..=to` to `history`, the files downloaded before are kept.
+    #[command(alias = "history")]
+    Download {
+        uid: String,
+        /// `1min`, `5min`, `15min`, `hour` or `day`.
+        #[arg(value_parser = history::parse_interval)]
+        interval: crate::tcs::CandleInterval,
+        from: NaiveDate,
+        to: NaiveDate,
+        /// `csv` or `bin`.
+        #[arg(long, default_value = "csv", value_parser = history::Format::parse)]
+        format: history::Format,
+    },
+    /// Order books and trades to `history` until Ctrl+C.
+    Record {
+        uid: String,
+        #[arg(long, default_value_t = 20)]
+        depth: i32,
+        /// In milliseconds.
+        #[arg(long, default_value_t = 1000)]
+        period: u64,
+    },
+    /// Writes the loaded credentials to an encrypted keystore,
+    /// use it then with `CREDENTIALS=keystore:<path>`.
+    SealCredentials { path: PathBuf },
+    /// The accounts of the token.
+    Accounts,
+    /// Looks the instruments up.
+    Instrument {
+        #[command(subcommand)]
+        command: InstrumentCommand,
+    },
+    /// Trading days of the exchanges, today without the dates.
+    Schedule {
+        /// All the exchanges without it.
+        exchange: Option<String>,
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        #[arg(long)]
+        to: Option<NaiveDate>,
+    },
+    /// The order book of the instrument.
+    Orderbook {
+        uid: String,
+        #[arg(long, default_value_t = 10)]
+        depth: i32,
+    },
+    /// The active orders of the account.
+    Orders {
+        #[command(subcommand)]
+        command: OrdersCommand,
+    },
+    /// Money and instruments of the account.
+    Positions {
+        /// `T_ACCOUNT_ID` without it.
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Performance over the trade history of the account.
+    Stats {
+        /// `AccountConfig::name`, the single-account setup without it.
+        #[arg(default_value = "")]
+        account: String,
+    },
+    /// Works with `TINKOFF_ENDPOINT=sandbox` only.
+    Sandbox {
+        #[command(subcommand)]
+        command: SandboxCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InstrumentCommand {
+    /// By a ticker, a name, FIGI or ISIN.
+    Find { query: String },
+    /// By uid, or by ticker with `--class-code`.
+    Info {
+        id: String,
+        #[arg(long)]
+        class_code: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum OrdersCommand {
+    List {
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// `T_ACCOUNT_ID` without `--account`.
+    Cancel {
+        order_id: String,
+        #[arg(long)]
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SandboxCommand {
+    /// Prints the id of the new account.
+    Open,
+    Payin {
+        account: String,
+        /// In rubles, `1000.5`.
+        #[arg(value_parser = parse_amount)]
+        amount: Quotation,
+    },
+    Close { account: String },
+}
+
+fn parse_walk(text: &str) -> Result<(usize, usize)> {
+    let error = || Error::Config(format!("bad walk {}, <train days>:<test days>", text));
+    let (train, test) = text.split_once(':').ok_or_else(error)?;
+    Ok((train.parse().map_err(|_| error())?, test.parse().map_err(|_| error())?))
+}
+
+fn parse_amount(text: &str) -> Result<Quotation> {
+    history::parse_price(text)
+        .filter(|amount| amount.to_nanos() > 0)
+        .ok_or_else(|| Error::Config(format!("bad amount {}", text)))
+}
+
+/// Rows with the same columns, printed aligned or as a JSON array of objects.
+pub struct Table {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new(columns: &[&'static str]) -> Table {
+        Table { columns: columns.to_vec(), rows: Vec::new() }
+    }
+
+    pub fn row(&mut self, values: Vec<Value>) {
+        debug_assert_eq!(values.len(), self.columns.len());
+        self.rows.push(values);
+    }
+
+    pub fn to_json(&self) -> Value {
+        self.rows.iter()
+            .map(|row| Value::Object(self.columns.iter()
+                .map(|column| column.to_string())
+                .zip(row.iter().cloned())
+                .collect::<Map<_, _>>()))
+            .collect()
+    }
+
+    pub fn print(&self, json: bool) {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&self.to_json()).unwrap());
+        } else {
+            print!("{}", self);
+        }
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cells: Vec<Vec<String>> = self.rows.iter().map(|row| row.iter().map(cell).collect()).collect();
+        let widths: Vec<usize> = self.columns.iter().enumerate()
+            .map(|(i, column)| cells.iter().map(|row| row[i].chars().count()).fold(column.len(), usize::max))
+            .collect();
+        let line = |f: &mut Formatter<'_>, row: Vec<&str>| {
+            let text = row.iter().zip(&widths)
+                .map(|(text, &width)| format!("{:width$}", text, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", text.trim_end())
+        };
+        line(f, self.columns.clone())?;
+        for row in &cells {
+            line(f, row.iter().map(String::as_str).collect())?;
+        }
+        Ok(())
+    }
+}
+
+/// Date and time in Moscow, empty without the time.
+fn time(timestamp: &Option<Timestamp>) -> Value {
+    match timestamp.as_ref().and_then(|t| Utc.timestamp_opt(t.seconds, t.nanos as u32).single()) {
+        Some(time) => json!(time.with_timezone(&Moscow).format("%Y-%m-%d %H:%M:%S").to_string()),
+        None => Value::Null,
+    }
+}
+
+fn quotation(value: &Option<Quotation>) -> Value {
+    value.as_ref().map_or(Value::Null, |value| json!(value.to_string()))
+}
+
+fn money(value: &Option<MoneyValue>) -> Value {
+    value.as_ref().map_or(Value::Null, |value| json!(crate::balance::Money::from(value).to_string()))
+}
+
+fn account(account: Option<String>, default: Option<&str>) -> Result<String> {
+    account.or_else(|| default.map(str::to_string))
+        .ok_or_else(|| Error::Config("no account, --account or T_ACCOUNT_ID".to_string()))
+}
+
+/// The commands asking the broker, `default_account` is `T_ACCOUNT_ID`.
+pub async fn request(command: Command, broker: &Broker, default_account: Option<&str>, json: bool)
+    -> Result<()> {
+    match command {
+        Command::Accounts => {
+            let accounts = broker.get_accounts().await?.into_inner().accounts;
+            let mut table = Table::new(&["id", "name", "type", "status", "access", "opened"]);
+            for account in accounts {
+                table.row(vec![
+                    json!(account.id),
+                    json!(account.name),
+                    json!(AccountType::from_i32(account.r#type).unwrap_or(AccountType::Unspecified).as_str_name()),
+                    json!(AccountStatus::from_i32(account.status).unwrap_or(AccountStatus::Unspecified).as_str_name()),
+                    json!(AccessLevel::from_i32(account.access_level)
+                        .unwrap_or(AccessLevel::AccountAccessLevelUnspecified).as_str_name()),
+                    time(&account.opened_date),
+                ]);
+            }
+            table.print(json);
+        },
+        Command::Instrument { command: InstrumentCommand::Find { query } } => {
+            let found = broker.find_instrument(FindInstrumentRequest { query }).await?.into_inner();
+            let mut table = Table::new(&["ticker", "class_code", "type", "name", "uid", "figi", "api"]);
+            for instrument in found.instruments {
+                table.row(vec![
+                    json!(instrument.ticker),
+                    json!(instrument.class_code),
+                    json!(instrument.instrument_type),
+                    json!(instrument.name),
+                    json!(instrument.uid),
+                    json!(instrument.figi),
+                    json!(instrument.api_trade_available_flag),
+                ]);
+            }
+            table.print(json);
+        },
+        Command::Instrument { command: InstrumentCommand::Info { id, class_code } } => {
+            let id_type = if class_code.is_some() { InstrumentIdType::Ticker } else { InstrumentIdType::Uid };
+            let req = InstrumentRequest { id_type: id_type.into(), class_code: class_code.unwrap_or_default(), id };
+            let instrument = broker.get_instrument_by(req).await?.into_inner().instrument
+                .ok_or_else(|| Error::Config("no instrument".to_string()))?;
+            let mut table = Table::new(&["field", "value"]);
+            for (field, value) in [
+                ("ticker", json!(instrument.ticker)),
+                ("class_code", json!(instrument.class_code)),
+                ("name", json!(instrument.name)),
+                ("type", json!(instrument.instrument_type)),
+                ("uid", json!(instrument.uid)),
+                ("figi", json!(instrument.figi)),
+                ("isin", json!(instrument.isin)),
+                ("exchange", json!(instrument.exchange)),
+                ("currency", json!(instrument.currency)),
+                ("lot", json!(instrument.lot)),
+                ("min_price_increment", quotation(&instrument.min_price_increment)),
+                ("short_enabled", json!(instrument.short_enabled_flag)),
+                ("api_trade_available", json!(instrument.api_trade_available_flag)),
+                ("for_qual_investor", json!(instrument.for_qual_investor_flag)),
+            ] {
+                table.row(vec![json!(field), value]);
+            }
+            table.print(json);
+        },
+        Command::Schedule { exchange, from, to } => {
+            let today = Utc::now().with_timezone(&Moscow).date_naive();
+            let (from, to) = (from.unwrap_or(today), to.or(from).unwrap_or(today));
+            let start = |date: NaiveDate| Timestamp { seconds: history::midnight(date), nanos: 0 };
+            let req = TradingSchedulesRequest {
+                exchange: exchange.unwrap_or_default(),
+                from: Some(start(from)),
+                to: Some(start(to)),
+            };
+            let schedules = broker.trading_schedules(req).await?.into_inner();
+            let mut table = Table::new(&["exchange", "date", "trading", "start", "end"]);
+            for schedule in schedules.exchanges {
+                for day in schedule.days {
+                    table.row(vec![
+                        json!(schedule.exchange),
+                        time(&day.date),
+                        json!(day.is_trading_day),
+                        time(&day.start_time),
+                        time(&day.end_time),
+                    ]);
+                }
+            }
+            table.print(json);
+        },
+        Command::Orderbook { uid, depth } => {
+            let req = GetOrderBookRequest { depth, instrument_id: uid, ..Default::default() };
+            let book = broker.get_order_book(req).await?.into_inner();
+            let mut table = Table::new(&["side", "price", "quantity"]);
+            // the best prices meet in the middle
+            for (side, orders) in [("ask", book.asks.iter().rev().collect::<Vec<_>>()), ("bid", book.bids.iter().collect())] {
+                for order in orders {
+                    table.row(vec![json!(side), quotation(&order.price), json!(order.quantity)]);
+                }
+            }
+            table.print(json);
+        },
+        Command::Orders { command: OrdersCommand::List { account: id } } => {
+            let account_id = account(id, default_account)?;
+            let orders = broker.get_orders(GetOrdersRequest { account_id }).await?.into_inner().orders;
+            let mut table = Table::new(&["order_id", "instrument_uid", "direction", "lots", "executed", "price", "status", "date"]);
+            for order in orders {
+                table.row(vec![
+                    json!(order.order_id),
+                    json!(order.instrument_uid),
+                    json!(OrderDirection::from_i32(order.direction).unwrap_or(OrderDirection::Unspecified).as_str_name()),
+                    json!(order.lots_requested),
+                    json!(order.lots_executed),
+                    money(&order.initial_security_price),
+                    json!(OrderExecutionReportStatus::from_i32(order.execution_report_status)
+                        .unwrap_or(OrderExecutionReportStatus::ExecutionReportStatusUnspecified).as_str_name()),
+                    time(&order.order_date),
+                ]);
+            }
+            table.print(json);
+        },
+        Command::Orders { command: OrdersCommand::Cancel { order_id, account: id } } => {
+            let account_id = account(id, default_account)?;
+            let cancelled = broker.cancel_order(CancelOrderRequest { account_id, order_id: order_id.clone() })
+                .await?.into_inner();
+            let mut table = Table::new(&["order_id", "cancelled"]);
+            table.row(vec![json!(order_id), time(&cancelled.time)]);
+            table.print(json);
+        },
+        Command::Positions { account: id } => {
+            let account_id = account(id, default_account)?;
+            let positions = broker.get_positions(PositionsRequest { account_id }).await?.into_inner();
+            let mut table = Table::new(&["kind", "instrument", "balance", "blocked"]);
+            for value in &positions.money {
+                let blocked = positions.blocked.iter().find(|blocked| blocked.currency == value.currency);
+                table.row(vec![json!("money"), json!(value.currency),
+                               money(&Some(value.clone())), money(&blocked.cloned())]);
+            }
+            for security in positions.securities {
+                table.row(vec![json!("security"), json!(security.instrument_uid),
+                               json!(security.balance), json!(security.blocked)]);
+            }
+            for future in positions.futures {
+                table.row(vec![json!("future"), json!(future.instrument_uid),
+                               json!(future.balance), json!(future.blocked)]);
+            }
+            for option in positions.options {
+                table.row(vec![json!("option"), json!(option.instrument_uid),
+                               json!(option.balance), json!(option.blocked)]);
+            }
+            table.print(json);
+        },
+        Command::Sandbox { command } => sandbox(command, broker, json).await?,
+        other => return Err(Error::Config(format!("{:?} is not a broker request", other))),
+    }
+    Ok(())
+}
+
+async fn sandbox(command: SandboxCommand, broker: &Broker, json: bool) -> Result<()> {
+    let mut table = Table::new(&["account", "balance"]);
+    match command {
+        SandboxCommand::Open => {
+            let opened = broker.open_sandbox_account().await?.into_inner();
+            table.row(vec![json!(opened.account_id), Value::Null]);
+        },
+        SandboxCommand::Payin { account, amount } => {
+            let req = SandboxPayInRequest {
+                account_id: account.clone(),
+                amount: Some(MoneyValue { currency: "rub".to_string(), units: amount.units, nano: amount.nano }),
+            };
+            let paid = broker.sandbox_pay_in(req).await?.into_inner();
+            table.row(vec![json!(account), money(&paid.balance)]);
+        },
+        SandboxCommand::Close { account } => {
+            broker.close_sandbox_account(CloseSandboxAccountRequest { account_id: account.clone() }).await?;
+            table.row(vec![json!(account), Value::Null]);
+        },
+    }
+    table.print(json);
+    Ok(())
+}
+
+/// The summary of `Statistics` by the strategies and the instruments.
+pub fn stats(account: &str, json: bool) -> Result<()> {
+    let summary = Summary::new(&Statistics::load(account)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+        return Ok(());
+    }
+    let mut table = Table::new(&["group", "name", "trades", "net", "win_rate", "profit_factor", "max_drawdown", "sharpe"]);
+    let mut add = |group: &str, name: &str, performance: &Performance| {
+        let round = |value: f64| json!((value * 100.0).round() / 100.0);
+        table.row(vec![json!(group), json!(name), json!(performance.trades), round(performance.net),
+                       round(performance.win_rate), round(performance.profit_factor),
+                       round(performance.max_drawdown), round(performance.sharpe)]);
+    };
+    add("total", "", &summary.total);
+    for (name, performance) in &summary.by_strategy {
+        add("strategy", name, performance);
+    }
+    for (name, performance) in &summary.by_instrument {
+        add("instrument", name, performance);
+    }
+    table.print(false);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert!(Cli::try_parse_from(["bot"]).unwrap().command.is_none());
+        let cli = Cli::try_parse_from(["bot", "orders", "cancel", "42", "--account", "2000", "--json"]).unwrap();
+        assert!(cli.json);
+        assert!(matches!(cli.command, Some(Command::Orders { command: OrdersCommand::Cancel { order_id, account } })
+            if order_id == "42" && account.as_deref() == Some("2000")));
+        let cli = Cli::try_parse_from(["bot", "replay", "scalp", "2023-02-09", "2023-02-10"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Backtest { latency: 50, .. })));
+        let cli = Cli::try_parse_from(["bot", "sandbox", "payin", "2000", "1000.5"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Sandbox { command: SandboxCommand::Payin { amount, .. } })
+            if amount == Quotation { units: 1000, nano: 500_000_000 }));
+        assert!(Cli::try_parse_from(["bot", "sandbox", "payin", "2000", "-5"]).is_err());
+        assert!(Cli::try_parse_from(["bot", "optimize", "scalp", "2023-02-09", "2023-02-10"]).is_err());
+        assert_eq!(account(None, Some("2000")).unwrap(), "2000");
+        assert!(account(None, None).unwrap_err().to_string().contains("T_ACCOUNT_ID"));
+    }
+
+    #[test]
+    fn prints_tables() {
+        let mut table = Table::new(&["ticker", "lot", "price"]);
+        table.row(vec![json!("IRAO"), json!(100), json!("5.94")]);
+        table.row(vec![json!("SBER"), json!(10), Value::Null]);
+        assert_eq!(table.to_string(), "ticker  lot  price\nIRAO    100  5.94\nSBER    10\n");
+        assert_eq!(table.to_json(), json!([
+            {"ticker": "IRAO", "lot": 100, "price": "5.94"},
+            {"ticker": "SBER", "lot": 10, "price": null},
+        ]));
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+    time::Duration
+};
+use serde::Serialize;
+use tokio::{sync::watch, time::Instant};
+use tonic::{
+    Code, Status,
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint as TonicEndpoint}
+};
+use tracing::{info, warn};
+
+use crate::error::{Error, Result};
+use crate::grpc::Broker;
+use crate::notify::{Event, Notifier};
+
+
+/// Where the broker API is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Endpoint {
+    Prod,
+    Sandbox,
+    /// Any other address, e.g. `http://127.0.0.1:50051` of a local stand-in.
+    Custom(String),
+}
+
+impl Endpoint {
+    const PROD: &'static str = "https://invest-public-api.tinkoff.ru:443/";
+    const SANDBOX: &'static str = "https://sandbox-invest-public-api.tinkoff.ru:443/";
+
+    pub fn parse(value: &str) -> Endpoint {
+        match value.trim() {
+            "" | "prod" => Endpoint::Prod,
+            "sandbox" => Endpoint::Sandbox,
+            url => Endpoint::Custom(url.to_string()),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        match self {
+            Endpoint::Prod => Self::PROD,
+            Endpoint::Sandbox => Self::SANDBOX,
+            Endpoint::Custom(url) => url,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectionSettings {
+    pub endpoint: Endpoint,
+    /// PEM file with an extra root certificate.
+    pub ca_file: Option<String>,
+    /// Overrides the domain checked in the server certificate.
+    pub domain: Option<String>,
+    /// HTTP/2 pings, a connection without an answer in `keepalive_timeout` is dropped.
+    pub keepalive: Duration,
+    pub keepalive_timeout: Duration,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        ConnectionSettings {
+            endpoint: Endpoint::Prod,
+            ca_file: None,
+            domain: None,
+            keepalive: Duration::from_secs(30),
+            keepalive_timeout: Duration::from_secs(10),
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ConnectionSettings {
+    /// - `TINKOFF_ENDPOINT`: `prod` (default), `sandbox` or an URL
+    /// - `TLS_CA_FILE`, `TLS_DOMAIN`: see the fields
+    pub fn from_env() -> ConnectionSettings {
+        ConnectionSettings {
+            endpoint: Endpoint::parse(&std::env::var("TINKOFF_ENDPOINT").unwrap_or_default()),
+            ca_file: std::env::var("TLS_CA_FILE").ok(),
+            domain: std::env::var("TLS_DOMAIN").ok(),
+            ..ConnectionSettings::default()
+        }
+    }
+
+    /// The channel connects on the first call and reconnects by itself
+    /// after the connection is dropped.
+    pub fn channel(&self) -> Result<Channel> {
+        let url = self.endpoint.url().to_string();
+        let mut endpoint = TonicEndpoint::from_shared(url.clone())
+            .map_err(|err| Error::Config(format!("bad endpoint {}: {}", url, err)))?
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout)
+            .tcp_keepalive(Some(self.keepalive))
+            .http2_keep_alive_interval(self.keepalive)
+            .keep_alive_timeout(self.keepalive_timeout)
+            .keep_alive_while_idle(true);
+        if url.starts_with("https") {
+            let mut tls = ClientTlsConfig::new();
+            if let Some(file) = &self.ca_file {
+                let pem = std::fs::read(file)
+                    .map_err(|err| Error::Config(format!("TLS_CA_FILE {}: {}", file, err)))?;
+                tls = tls.ca_certificate(Certificate::from_pem(pem));
+            }
+            if let Some(domain) = &self.domain {
+                tls = tls.domain_name(domain);
+            }
+            endpoint = endpoint.tls_config(tls)?;
+        }
+        info!(endpoint = %url, "broker endpoint");
+        Ok(endpoint.connect_lazy())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(tag = "state", content = "reason", rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// No call has finished yet.
+    #[default]
+    Connecting,
+    Ready,
+    Lost(String),
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionState::Connecting => write!(f, "подключаемся"),
+            ConnectionState::Ready => write!(f, "есть"),
+            ConnectionState::Lost(reason) => write!(f, "нет ({})", reason),
+        }
+    }
+}
+
+/// The codes that mean the broker is unreachable rather than unhappy with the request.
+pub fn lost(code: Code) -> bool {
+    matches!(code, Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled | Code::Unknown)
+}
+
+/// The connection state as seen by the calls, shared by every clone of `Broker`.
+#[derive(Clone)]
+pub struct Connection {
+    state: Arc<watch::Sender<ConnectionState>>,
+    last_ok: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Connection {
+    pub fn new() -> Connection {
+        Connection {
+            state: Arc::new(watch::channel(ConnectionState::Connecting).0),
+            last_ok: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionState> {
+        self.state.subscribe()
+    }
+
+    /// Called with the result of every call.
+    pub fn report(&self, result: Result<(), &Status>) {
+        let state = match result {
+            Ok(()) => {
+                *self.last_ok.lock().unwrap() = Some(Instant::now());
+                ConnectionState::Ready
+            },
+            Err(status) if lost(status.code()) => ConnectionState::Lost(status.message().to_string()),
+            // the broker answered, so the connection is fine
+            Err(_) => ConnectionState::Ready,
+        };
+        self.state.send_if_modified(|current| {
+            let changed = match (&*current, &state) {
+                (ConnectionState::Lost(_), ConnectionState::Lost(_)) => false,
+                (current, state) => current != state,
+            };
+            if changed {
+                *current = state;
+            }
+            changed
+        });
+    }
+
+    fn idle(&self) -> bool {
+        match *self.last_ok.lock().unwrap() {
+            Some(last_ok) => last_ok.elapsed() >= HEALTH_INTERVAL,
+            None => true,
+        }
+    }
+}
+
+const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Probes the broker when no call succeeded lately, e.g. while the bot sleeps
+/// between sessions, so a dead connection is noticed and reestablished early.
+pub fn health_check(broker: Broker) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEALTH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if broker.connection().idle() {
+                if let Err(status) = broker.get_info().await {
+                    warn!(code = ?status.code(), "health check failed");
+                }
+            }
+        }
+    });
+}
+
+/// Tells the controls when the connection is lost and when it is back.
+pub fn notify(mut state: watch::Receiver<ConnectionState>, notifier: Notifier) {
+    tokio::spawn(async move {
+        let mut was_lost = false;
+        while state.changed().await.is_ok() {
+            let current = state.borrow().clone();
+            match current {
+                ConnectionState::Lost(reason) if !was_lost => {
+                    was_lost = true;
+                    notifier.send(Event::ConnectionLost(reason));
+                },
+                ConnectionState::Ready if was_lost => {
+                    was_lost = false;
+                    notifier.send(Event::ConnectionRestored);
+                },
+                _ => {},
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{Request, Response, transport::Server};
+    use crate::credentials::{Credentials, Secret};
+    use crate::tcs::{
+        GetAccountsRequest, GetAccountsResponse, GetInfoRequest, GetInfoResponse,
+        GetMarginAttributesRequest, GetMarginAttributesResponse, GetUserTariffRequest,
+        GetUserTariffResponse,
+        users_service_server::{UsersService, UsersServiceServer}
+    };
+
+    /// Stands in for the broker.
+    struct StandIn;
+
+    #[tonic::async_trait]
+    impl UsersService for StandIn {
+        async fn get_accounts(&self, _: Request<GetAccountsRequest>)
+            -> Result<Response<GetAccountsResponse>, Status> {
+            Err(Status::unimplemented(""))
+        }
+        async fn get_margin_attributes(&self, _: Request<GetMarginAttributesRequest>)
+            -> Result<Response<GetMarginAttributesResponse>, Status> {
+            Err(Status::unimplemented(""))
+        }
+        async fn get_user_tariff(&self, _: Request<GetUserTariffRequest>)
+            -> Result<Response<GetUserTariffResponse>, Status> {
+            Err(Status::unimplemented(""))
+        }
+        async fn get_info(&self, request: Request<GetInfoRequest>)
+            -> Result<Response<GetInfoResponse>, Status> {
+            match request.metadata().get("authorization") {
+                Some(token) if token == "bearer test" => Ok(Response::new(GetInfoResponse {
+                    tariff: "investor".to_string(),
+                    ..GetInfoResponse::default()
+                })),
+                _ => Err(Status::unauthenticated("40003")),
+            }
+        }
+    }
+
+    /// Serves on `listener` until `stop` is sent.
+    fn serve(listener: TcpListener) -> tokio::sync::oneshot::Sender<()> {
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(Server::builder()
+            .add_service(UsersServiceServer::new(StandIn))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                let _ = stopped.await;
+            }));
+        stop
+    }
+
+    #[test]
+    fn endpoints() {
+        assert_eq!(Endpoint::parse(""), Endpoint::Prod);
+        assert_eq!(Endpoint::parse("sandbox").url(), Endpoint::SANDBOX);
+        assert_eq!(Endpoint::parse("http://127.0.0.1:50051").url(), "http://127.0.0.1:50051");
+        let settings = ConnectionSettings {
+            endpoint: Endpoint::Custom("not an url".to_string()),
+            ..ConnectionSettings::default()
+        };
+        assert!(matches!(settings.channel(), Err(Error::Config(_))));
+    }
+    #[tokio::test]
+    async fn reconnects_to_stand_in() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stop = serve(listener);
+
+        let settings = ConnectionSettings {
+            endpoint: Endpoint::Custom(format!("http://{}", addr)),
+            request_timeout: Duration::from_secs(2),
+            ..ConnectionSettings::default()
+        };
+        let connection = Connection::new();
+        let credentials = Credentials {
+            token: Secret::new("test".to_string()),
+            readonly_token: Some(Secret::new("readonly".to_string())),
+            telegram_token: None,
+            account_id: None,
+            chat_id: None,
+            dashboard_token: None,
+        };
+        let broker = Broker::new(settings.channel().unwrap(), &credentials, connection.clone());
+        assert_eq!(connection.state(), ConnectionState::Connecting);
+
+        let info = broker.get_info().await.unwrap();
+        assert_eq!(info.get_ref().tariff, "investor");
+        assert_eq!(connection.state(), ConnectionState::Ready);
+
+        stop.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(broker.get_info().await.is_err());
+        assert!(matches!(connection.state(), ConnectionState::Lost(_)));
+
+        let _stop = serve(TcpListener::bind(addr).await.unwrap());
+        broker.get_info().await.unwrap();
+        assert_eq!(connection.state(), ConnectionState::Ready);
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+    time::Duration
+};
+use futures::future::BoxFuture;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    sync::mpsc::{self, Receiver, Sender}
+};
+
+use tracing::{error, warn};
+
+use crate::chart::ChartKind;
+use crate::notify::{Sink, StdoutSink};
+
+
+/// Requests to the trading engine, whatever transport they come from.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum RequestType {
+    ChartRequest(ChartKind),
+    ConfigRequest,
+    FlattenRequest,
+    ResumeRequest,
+    PauseRequest,
+    StateRequest,
+    StatRequest,
+    StopRequest,
+}
+
+impl RequestType {
+    /// Parses text commands like `/state` or `chart equity`.
+    pub fn parse(line: &str) -> Option<RequestType> {
+        let line = line.trim().trim_start_matches('/');
+        let (cmd, arg) = line.split_once(' ').unwrap_or((line, ""));
+        Some(match cmd.to_lowercase().as_str() {
+            "state" => RequestType::StateRequest,
+            "stat" => RequestType::StatRequest,
+            "pause" => RequestType::PauseRequest,
+            "resume" => RequestType::ResumeRequest,
+            "flatten" => RequestType::FlattenRequest,
+            "config" => RequestType::ConfigRequest,
+            "stop" => RequestType::StopRequest,
+            "chart" => RequestType::ChartRequest(ChartKind::parse(arg)?),
+            _ => return None,
+        })
+    }
+}
+
+/// A request and where the answers to it go.
+#[derive(Clone)]
+pub struct Request {
+    pub kind: RequestType,
+    /// The chat or the terminal the request came from, see `Notifier::reply`.
+    pub reply: Arc<dyn Sink>,
+}
+
+impl Request {
+    pub fn new(kind: RequestType, reply: Arc<dyn Sink>) -> Request {
+        Request { kind, reply }
+    }
+}
+
+impl Debug for Request {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} from {}", self.kind, self.reply.name())
+    }
+}
+
+/// A way to control the bot and to get its notifications.
+pub trait Control: Send + Sync {
+    fn name(&self) -> &str;
+    /// Where the notifications for this transport go, each is retried on its own.
+    fn sinks(&self) -> Vec<Box<dyn Sink>>;
+    /// Delivers requests to `tx`, resolves when the transport is down.
+    /// Every call starts the transport from scratch.
+    fn run(&self, tx: Sender<Request>) -> BoxFuture<'static, ()>;
+}
+
+/// Runs the control in the background and restarts it when it fails,
+/// the trading engine keeps working meanwhile.
+pub fn supervise(control: Box<dyn Control>, tx: Sender<Request>) {
+    const RESTART_PAUSE: Duration = Duration::new(5, 0);
+
+    tokio::spawn(async move {
+        while !tx.is_closed() {
+            if let Err(err) = tokio::spawn(control.run(tx.clone())).await {
+                error!(control = control.name(), %err, "control crashed");
+            }
+            warn!(control = control.name(), "control is down, restarting");
+            tokio::time::sleep(RESTART_PAUSE).await;
+        }
+    });
+}
+
+/// Gives every request to each of `n` bots, when several accounts trade in one process.
+pub fn fan_out(mut rx: Receiver<Request>, n: usize) -> Vec<Receiver<Request>> {
+    let (txs, rxs): (Vec<_>, Vec<_>) = (0..n).map(|_| mpsc::channel(10)).unzip();
+    tokio::spawn(async move {
+        while let Some(request) = rx.recv().await {
+            for tx in &txs {
+                let _ = tx.send(request.clone()).await;
+            }
+        }
+    });
+    rxs
+}
+
+/// Commands from stdin, notifications to stdout.
+pub struct CliControl;
+
+impl Control for CliControl {
+    fn name(&self) -> &str {
+        "cli"
+    }
+    fn sinks(&self) -> Vec<Box<dyn Sink>> {
+        vec![Box::new(StdoutSink)]
+    }
+    fn run(&self, tx: Sender<Request>) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let stdout: Arc<dyn Sink> = Arc::new(StdoutSink);
+            let mut lines = BufReader::new(tokio::io::stdin()).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match RequestType::parse(&line) {
+                    Some(kind) => if tx.send(Request::new(kind, stdout.clone())).await.is_err() {
+                        return;
+                    },
+                    None => println!("Commands: state, stat, pause, resume, flatten, config, \
+                                      chart [price|equity|pnl], stop"),
+                }
+            }
+            // stdin is closed, there is nothing to restart
+            std::future::pending::<()>().await
+        })
+    }
+}
+
+/// Headless mode: no commands, no notifications.
+pub struct NoopControl;
+
+impl Control for NoopControl {
+    fn name(&self) -> &str {
+        "none"
+    }
+    fn sinks(&self) -> Vec<Box<dyn Sink>> {
+        Vec::new()
+    }
+    fn run(&self, _: Sender<Request>) -> BoxFuture<'static, ()> {
+        Box::pin(std::future::pending())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn parse() {
+        assert_eq!(RequestType::parse("/state"), Some(RequestType::StateRequest));
+        assert_eq!(RequestType::parse("  Stop "), Some(RequestType::StopRequest));
+        assert_eq!(RequestType::parse("chart"), Some(RequestType::ChartRequest(ChartKind::Price)));
+        assert_eq!(RequestType::parse("/chart pnl"), Some(RequestType::ChartRequest(ChartKind::DailyPnl)));
+        assert_eq!(RequestType::parse("chart candles"), None);
+        assert_eq!(RequestType::parse("WTF?!"), None);
+    }
+
+    struct Crashing {
+        runs: Arc<AtomicU32>,
+    }
+
+    impl Control for Crashing {
+        fn name(&self) -> &str {
+            "crashing"
+        }
+        fn sinks(&self) -> Vec<Box<dyn Sink>> {
+            Vec::new()
+        }
+        fn run(&self, tx: Sender<Request>) -> BoxFuture<'static, ()> {
+            let runs = self.runs.clone();
+            Box::pin(async move {
+                if runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("first run crashes");
+                }
+                tx.send(Request::new(RequestType::StateRequest, Arc::new(StdoutSink))).await.unwrap();
+                std::future::pending::<()>().await
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restarts_after_crash() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        supervise(Box::new(Crashing { runs: runs.clone() }), tx);
+        assert_eq!(rx.recv().await.map(|request| request.kind), Some(RequestType::StateRequest));
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+    #[tokio::test]
+    async fn every_bot_gets_requests() {
+        let (tx, rx) = mpsc::channel(10);
+        let mut bots = fan_out(rx, 2);
+        tx.send(Request::new(RequestType::PauseRequest, Arc::new(StdoutSink))).await.unwrap();
+        for bot in bots.iter_mut() {
+            let request = bot.recv().await.unwrap();
+            assert_eq!((request.kind, request.reply.name()), (RequestType::PauseRequest, "stdout"));
+        }
+    }
+}
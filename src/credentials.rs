@@ -0,0 +1,217 @@
+use std::{
+    fmt::{Debug, Formatter},
+    num::NonZeroU32,
+    path::Path
+};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom}
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+
+/// A token that never gets into logs: `Debug` prints `***`.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Secret {
+        Secret(value)
+    }
+
+    /// The only way to read the value, grep for it when reviewing.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "***")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Credentials {
+    /// Full-access Tinkoff token: orders and portfolio.
+    pub token: Secret,
+    /// Read-only Tinkoff token for market data, `token` is used if not given.
+    #[serde(default)]
+    pub readonly_token: Option<Secret>,
+    #[serde(default)]
+    pub telegram_token: Option<Secret>,
+    /// The single account to trade on, see `AccountConfig::load`.
+    #[serde(default)]
+    pub account_id: Option<String>,
+    /// Owner of the bot, see `Access::owner`.
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+    /// Bearer token of the dashboard, see `Dashboard::serve`.
+    #[serde(default)]
+    pub dashboard_token: Option<Secret>,
+}
+
+impl Credentials {
+    /// `CREDENTIALS` tells where the credentials are:
+    /// - `env` (default): `TOKEN_BOT`, `TOKEN_READONLY`, `TELOXIDE_TOKEN`, `T_ACCOUNT_ID`, `TG_CHAT_ID`,
+    ///   `DASHBOARD_TOKEN`
+    /// - `file:<path>`: JSON readable by the owner only
+    /// - `keystore:<path>`: JSON encrypted with `KEYSTORE_PASSWORD`, see `seal`
+    pub fn load() -> Result<Credentials> {
+        let source = std::env::var("CREDENTIALS").unwrap_or_else(|_| String::from("env"));
+        match source.split_once(':') {
+            None if source == "env" => Credentials::from_env(),
+            Some(("file", path)) => Credentials::from_file(Path::new(path)),
+            Some(("keystore", path)) => {
+                let password = Secret::new(Error::env("KEYSTORE_PASSWORD")?);
+                let text = std::fs::read_to_string(path)?;
+                Credentials::open(&text, &password)
+            },
+            _ => Err(Error::Config(format!("unknown CREDENTIALS source {}", source))),
+        }
+    }
+
+    fn from_env() -> Result<Credentials> {
+        let secret = |name| std::env::var(name).ok().map(Secret::new);
+        Ok(Credentials {
+            token: Secret::new(Error::env("TOKEN_BOT")?),
+            readonly_token: secret("TOKEN_READONLY"),
+            telegram_token: secret("TELOXIDE_TOKEN"),
+            account_id: std::env::var("T_ACCOUNT_ID").ok(),
+            chat_id: std::env::var("TG_CHAT_ID").ok().and_then(|id| id.parse().ok()),
+            dashboard_token: secret("DASHBOARD_TOKEN"),
+        })
+    }
+
+    /// Refuses files that the group or others may read.
+    fn from_file(path: &Path) -> Result<Credentials> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(path)?.permissions().mode();
+            if mode & 0o077 != 0 {
+                return Err(Error::Config(format!(
+                    "{} is accessible by others ({:o}), run chmod 600", path.display(), mode & 0o777)));
+            }
+        }
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text)
+            .map_err(|err| Error::Config(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Token for the market data, the only thing that doesn't need the full access.
+    pub fn market_token(&self) -> &Secret {
+        self.readonly_token.as_ref().unwrap_or(&self.token)
+    }
+
+    /// Encrypts the credentials into a keystore, ChaCha20-Poly1305 with a PBKDF2 key.
+    pub fn seal(&self, password: &Secret) -> Result<String> {
+        let random = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        random.fill(&mut salt).and_then(|_| random.fill(&mut nonce))
+            .map_err(|_| Error::Config("no random numbers".to_string()))?;
+
+        let mut data = serde_json::to_vec(self).unwrap();
+        key(password, &salt)
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| Error::Config("can't encrypt".to_string()))?;
+        let keystore = Keystore { version: 1, salt: hex(&salt), nonce: hex(&nonce), data: hex(&data) };
+        Ok(serde_json::to_string_pretty(&keystore).unwrap())
+    }
+
+    pub fn open(keystore: &str, password: &Secret) -> Result<Credentials> {
+        let broken = || Error::Config("keystore is damaged or the password is wrong".to_string());
+        let keystore: Keystore = serde_json::from_str(keystore).map_err(|_| broken())?;
+        let salt = unhex(&keystore.salt).ok_or_else(broken)?;
+        let nonce = unhex(&keystore.nonce)
+            .and_then(|nonce| Nonce::try_assume_unique_for_key(&nonce).ok())
+            .ok_or_else(broken)?;
+        let mut data = unhex(&keystore.data).ok_or_else(broken)?;
+        let plain = key(password, &salt)
+            .open_in_place(nonce, Aad::empty(), &mut data)
+            .map_err(|_| broken())?;
+        serde_json::from_slice(plain).map_err(|_| broken())
+    }
+}
+
+const SALT_LEN: usize = 16;
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+#[derive(Serialize, Deserialize)]
+struct Keystore {
+    version: u32,
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+fn key(password: &Secret, salt: &[u8]) -> LessSafeKey {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, NonZeroU32::new(PBKDF2_ROUNDS).unwrap(),
+                   salt, password.expose().as_bytes(), &mut key);
+    LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &key).unwrap())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials() -> Credentials {
+        Credentials {
+            token: Secret::new("t.full-access".to_string()),
+            readonly_token: None,
+            telegram_token: Some(Secret::new("123:tg-secret".to_string())),
+            account_id: Some("2000000001".to_string()),
+            chat_id: Some(42),
+            dashboard_token: Some(Secret::new("web-secret".to_string())),
+        }
+    }
+
+    #[test]
+    fn never_printed() {
+        let text = format!("{:?}", credentials());
+        assert!(!text.contains("full-access") && !text.contains("tg-secret") && !text.contains("web-secret"),
+                "{}", text);
+        assert_eq!(credentials().market_token().expose(), "t.full-access");
+    }
+    #[test]
+    fn keystore() {
+        let password = Secret::new("correct horse".to_string());
+        let sealed = credentials().seal(&password).unwrap();
+        assert!(!sealed.contains("full-access"));
+        assert_eq!(Credentials::open(&sealed, &password).unwrap(), credentials());
+        let wrong = Secret::new("battery staple".to_string());
+        assert!(matches!(Credentials::open(&sealed, &wrong), Err(Error::Config(_))));
+    }
+    #[cfg(unix)]
+    #[test]
+    fn file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("credentials-{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&credentials()).unwrap()).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(matches!(Credentials::from_file(&path), Err(Error::Config(_))));
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(Credentials::from_file(&path).unwrap(), credentials());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{Arc, RwLock}
+};
+use axum::{
+    Json, Router,
+    extract::{Query, State as Shared, ws::{Message, WebSocket, WebSocketUpgrade}},
+    http::{header, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::error;
+
+use crate::accounts::Account;
+use crate::analytics::Summary;
+use crate::balance::Money;
+use crate::bot::{AnalysisType, DayStat, Settings, Statistics};
+use crate::connection::ConnectionState;
+use crate::credentials::Secret;
+use crate::error::Error;
+use crate::grpc::Broker;
+use crate::portfolio::Portfolio;
+use crate::strategies::Source;
+use crate::tcs::{CandleInterval, GetOrderBookResponse, GetOrdersRequest, OrderDirection, OrderState, Quotation};
+
+
+/// What the bot is doing right now, refreshed every loop iteration.
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct Snapshot {
+    pub state: StateView,
+    pub paused: bool,
+    pub connection: ConnectionState,
+    /// What the bot may spend, in the currency of the instrument.
+    pub money: Money,
+    /// All the money converted to the currency of the instrument.
+    pub total: Money,
+    pub portfolio: Portfolio,
+    /// Orders of the bot that are not executed yet, as the bot sees them; `/api/orders` asks the broker.
+    pub orders: Vec<OrderView>,
+    pub config: Option<ConfigView>,
+}
+
+#[derive(Serialize, Clone, Default, Debug)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum StateView {
+    #[default]
+    Unknown,
+    Seeking { money: Quotation },
+    InPosition(PositionView),
+    Sleeping { slept_secs: u64, total_secs: u64 },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PositionView {
+    /// `wait_open`, `partial_open`, `hold`, `wait_close` or `partial_close`.
+    pub status: String,
+    pub lots: i64,
+    pub direction: String,
+    pub price_in: Quotation,
+    pub price_out: Quotation,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct OrderView {
+    pub order_id: String,
+    pub direction: String,
+    pub lots: i64,
+    pub price: Quotation,
+    /// `open` or `close` the position, `other` for the orders the bot didn't place.
+    pub purpose: String,
+}
+
+impl OrderView {
+    /// An active order from the broker, `bot` are the orders of the bot from `Snapshot`.
+    fn new(order: &OrderState, bot: &[OrderView]) -> OrderView {
+        let price = order.initial_security_price.clone().unwrap_or_default();
+        OrderView {
+            order_id: order.order_id.clone(),
+            direction: direction(OrderDirection::from_i32(order.direction).unwrap_or(OrderDirection::Unspecified)),
+            lots: order.lots_requested - order.lots_executed,
+            price: Quotation { units: price.units, nano: price.nano },
+            purpose: bot.iter()
+                .find(|view| view.order_id == order.order_id)
+                .map_or_else(|| "other".to_string(), |view| view.purpose.clone()),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ConfigView {
+    pub account_id: String,
+    pub ticker: String,
+    pub uid: String,
+    pub figi: String,
+    pub class_code: String,
+    pub currency: String,
+    pub trading_time: Vec<String>,
+    pub data: String,
+    pub fee_rate: Quotation,
+    pub tax_rate: Quotation,
+    pub shutdown_policy: String,
+    pub stop_on_session_end: bool,
+}
+
+impl ConfigView {
+    pub fn new(account: &Account, settings: &Settings) -> ConfigView {
+        ConfigView {
+            account_id: account.id.clone(),
+            ticker: settings.ticker.clone(),
+            uid: settings.uid.clone(),
+            figi: settings.figi.clone(),
+            class_code: settings.class_code.clone(),
+            currency: settings.currency.clone(),
+            trading_time: settings.trading_time.iter()
+                .map(|(fh, fm, th, tm)| format!("{:02}:{:02}-{:02}:{:02}", fh, fm, th, tm))
+                .collect(),
+            data: match &settings.data_type {
+                AnalysisType::OrderBook(depth) => format!("order_book:{}", depth),
+                AnalysisType::Candle(intervals) => format!("candles:{}", intervals.iter()
+                    .map(CandleInterval::as_str_name)
+                    .collect::<Vec<_>>()
+                    .join("+")),
+            } + &settings.sources.iter()
+                .map(|source| match source {
+                    Source::LastPrice => ",last_price",
+                    Source::Trades => ",trades",
+                })
+                .collect::<String>(),
+            fee_rate: settings.fee_rate.clone(),
+            tax_rate: settings.tax_rate.clone(),
+            shutdown_policy: format!("{:?}", settings.shutdown_policy),
+            stop_on_session_end: settings.stop_on_session_end,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Level {
+    pub price: Quotation,
+    pub quantity: i64,
+}
+
+/// Live events pushed to the WebSocket clients.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Feed {
+    OrderBook { time: i64, bids: Vec<Level>, asks: Vec<Level> },
+    Signal { action: String, lots: i64, price: Quotation, direction: String },
+    Fill { order_id: String, lots: i64, price: Quotation },
+}
+
+impl Feed {
+    pub fn order_book(response: &GetOrderBookResponse) -> Feed {
+        let levels = |orders: &[crate::tcs::Order]| orders.iter()
+            .map(|o| Level { price: o.price.clone().unwrap_or_default(), quantity: o.quantity })
+            .collect();
+        Feed::OrderBook {
+            time: response.orderbook_ts.as_ref().map(|ts| ts.seconds).unwrap_or_default(),
+            bids: levels(&response.bids),
+            asks: levels(&response.asks),
+        }
+    }
+}
+
+/// What the WebSocket clients get: the feed of one of the accounts.
+#[derive(Serialize, Clone, Debug)]
+pub struct AccountFeed {
+    pub account: String,
+    #[serde(flatten)]
+    pub feed: Feed,
+}
+
+pub fn direction(direction: OrderDirection) -> String {
+    match direction {
+        OrderDirection::Buy => "buy",
+        OrderDirection::Sell => "sell",
+        OrderDirection::Unspecified => "unspecified",
+    }.to_string()
+}
+
+/// Shared between the bots, which publish, and the HTTP server, which reads.
+#[derive(Clone)]
+pub struct Dashboard {
+    /// By `Account::name`.
+    snapshots: Arc<RwLock<BTreeMap<String, Snapshot>>>,
+    /// `Account::id` by `Account::name`.
+    ids: Arc<RwLock<BTreeMap<String, String>>>,
+    /// For the active orders, none in the replay.
+    broker: Option<Broker>,
+    feed: broadcast::Sender<AccountFeed>,
+    /// Where `update` and `publish` go.
+    account: String,
+}
+
+impl Dashboard {
+    const FEED_CAPACITY: usize = 256;
+
+    pub fn new() -> Dashboard {
+        Dashboard {
+            snapshots: Arc::new(RwLock::new(BTreeMap::new())),
+            ids: Arc::new(RwLock::new(BTreeMap::new())),
+            broker: None,
+            feed: broadcast::channel(Self::FEED_CAPACITY).0,
+            account: String::new(),
+        }
+    }
+
+    pub fn with_broker(self, broker: Broker) -> Dashboard {
+        Dashboard { broker: Some(broker), ..self }
+    }
+
+    /// The handle for the bot of the account, `name` and `id` are of `Account`.
+    pub fn for_account(&self, name: &str, id: &str) -> Dashboard {
+        self.snapshots.write().unwrap().insert(name.to_string(), Snapshot::default());
+        self.ids.write().unwrap().insert(name.to_string(), id.to_string());
+        Dashboard { account: name.to_string(), ..self.clone() }
+    }
+
+    pub fn update(&self, snapshot: Snapshot) {
+        self.snapshots.write().unwrap().insert(self.account.clone(), snapshot);
+    }
+
+    pub fn publish(&self, feed: Feed) {
+        // nobody listens most of the time
+        let _ = self.feed.send(AccountFeed { account: self.account.clone(), feed });
+    }
+
+    fn snapshot(&self, account: &str) -> Result<Snapshot, (StatusCode, String)> {
+        self.snapshots.read().unwrap().get(account)
+            .cloned()
+            .ok_or((StatusCode::NOT_FOUND, format!("Unknown account {}", account)))
+    }
+
+    /// Serves the API in the background, Prometheus `/metrics` is on `metrics::serve`.
+    /// With `token` every request needs `Authorization: Bearer <token>` or `?token=<token>`
+    /// (the browsers can't set headers for `/ws`), without it only the loopback is served.
+    pub fn serve(&self, addr: SocketAddr, token: Option<Secret>) -> Result<(), Error> {
+        if token.is_none() && !addr.ip().is_loopback() {
+            return Err(Error::Config(format!("DASHBOARD_TOKEN is needed to serve {}", addr)));
+        }
+        let app = self.router(token);
+        tokio::spawn(async move {
+            if let Err(err) = axum::Server::bind(&addr).serve(app.into_make_service()).await {
+                error!(%err, "dashboard server stopped");
+            }
+        });
+        Ok(())
+    }
+
+    fn router(&self, token: Option<Secret>) -> Router {
+        let router = Router::new()
+            .route("/api/accounts", get(accounts))
+            .route("/api/state", get(state))
+            .route("/api/orders", get(orders))
+            .route("/api/day", get(day))
+            .route("/api/history", get(history))
+            .route("/api/performance", get(performance))
+            .route("/api/config", get(config))
+            .route("/ws", get(ws))
+            .with_state(self.clone());
+        match token {
+            Some(token) => router.route_layer(middleware::from_fn_with_state(Arc::new(token), authorize)),
+            None => router,
+        }
+    }
+}
+
+async fn authorize<B>(Shared(token): Shared<Arc<Secret>>, request: Request<B>, next: Next<B>) -> Response {
+    let bearer = request.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = request.uri().query().unwrap_or("").split('&')
+        .find_map(|pair| pair.strip_prefix("token="));
+    match bearer.or(query) {
+        Some(given) if same(given.as_bytes(), token.expose().as_bytes()) => next.run(request).await,
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// Takes the same time for any mismatch of the same length.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+fn internal(err: Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
+
+/// `?account=<name>`, see `AccountConfig::name`; the single account has an empty name.
+#[derive(Deserialize)]
+struct AccountQuery {
+    #[serde(default)]
+    account: String,
+}
+
+async fn accounts(Shared(dashboard): Shared<Dashboard>) -> Json<Vec<String>> {
+    Json(dashboard.snapshots.read().unwrap().keys().cloned().collect())
+}
+
+async fn state(Shared(dashboard): Shared<Dashboard>, Query(query): Query<AccountQuery>)
+    -> ApiResult<Snapshot> {
+    dashboard.snapshot(&query.account).map(Json)
+}
+
+/// The active orders of the account from the broker.
+async fn orders(Shared(dashboard): Shared<Dashboard>, Query(query): Query<AccountQuery>)
+    -> ApiResult<Vec<OrderView>> {
+    let snapshot = dashboard.snapshot(&query.account)?;
+    let account_id = dashboard.ids.read().unwrap().get(&query.account).cloned().unwrap_or_default();
+    let broker = dashboard.broker.as_ref()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "No broker".to_string()))?;
+    let response = broker.get_orders(GetOrdersRequest { account_id }).await
+        .map_err(|status| (StatusCode::BAD_GATEWAY, status.message().to_string()))?;
+    Ok(Json(response.get_ref().orders.iter().map(|order| OrderView::new(order, &snapshot.orders)).collect()))
+}
+
+async fn day(Shared(dashboard): Shared<Dashboard>, Query(query): Query<AccountQuery>)
+    -> ApiResult<DayStat> {
+    // only known names get to the file system
+    dashboard.snapshot(&query.account)?;
+    DayStat::load(&query.account).map(Json).map_err(internal)
+}
+
+async fn history(Shared(dashboard): Shared<Dashboard>, Query(query): Query<AccountQuery>)
+    -> ApiResult<Statistics> {
+    dashboard.snapshot(&query.account)?;
+    Statistics::load(&query.account).map(Json).map_err(internal)
+}
+
+async fn performance(Shared(dashboard): Shared<Dashboard>, Query(query): Query<AccountQuery>)
+    -> ApiResult<Summary> {
+    dashboard.snapshot(&query.account)?;
+    Statistics::load(&query.account).map(|stat| Json(Summary::new(&stat))).map_err(internal)
+}
+
+async fn config(Shared(dashboard): Shared<Dashboard>, Query(query): Query<AccountQuery>)
+    -> ApiResult<ConfigView> {
+    dashboard.snapshot(&query.account)?.config
+        .map(Json)
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "The bot is starting".to_string()))
+}
+
+async fn ws(upgrade: WebSocketUpgrade, Shared(dashboard): Shared<Dashboard>) -> Response {
+    let rx = dashboard.feed.subscribe();
+    upgrade.on_upgrade(move |socket| push_feed(socket, rx))
+}
+
+async fn push_feed(mut socket: WebSocket, mut rx: broadcast::Receiver<AccountFeed>) {
+    loop {
+        let feed = match rx.recv().await {
+            Ok(feed) => feed,
+            // a slow client just misses some snapshots
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let text = serde_json::to_string(&feed).unwrap();
+        if socket.send(Message::Text(text)).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    async fn get_json(dashboard: &Dashboard, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = dashboard.router(None)
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    #[tokio::test]
+    async fn state_and_orders() {
+        let dashboard = Dashboard::new().for_account("", "2000000001");
+        let iis = dashboard.for_account("iis", "2000000002");
+        let (status, json) = get_json(&dashboard, "/api/state").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["state"]["state"], "unknown");
+        assert_eq!(json["connection"]["state"], "connecting");
+        assert_eq!(get_json(&dashboard, "/api/config").await.0, StatusCode::SERVICE_UNAVAILABLE);
+
+        let price = Quotation { units: 5, nano: 90_0000000 };
+        dashboard.update(Snapshot {
+            state: StateView::InPosition(PositionView {
+                status: "wait_close".to_string(),
+                lots: 2,
+                direction: direction(OrderDirection::Buy),
+                price_in: price.clone(),
+                price_out: price.clone(),
+            }),
+            orders: vec![OrderView {
+                order_id: "1000001".to_string(),
+                direction: direction(OrderDirection::Sell),
+                lots: 2,
+                price,
+                purpose: "close".to_string(),
+            }],
+            ..Snapshot::default()
+        });
+        let (_, json) = get_json(&dashboard, "/api/state").await;
+        assert_eq!(json["state"]["state"], "in_position");
+        assert_eq!(json["state"]["status"], "wait_close");
+        let (_, json) = get_json(&dashboard, "/api/state").await;
+        assert_eq!(json["orders"][0]["order_id"], "1000001");
+        assert_eq!(json["orders"][0]["price"]["nano"], 90_0000000);
+        // the orders come from the broker only
+        assert_eq!(get_json(&dashboard, "/api/orders").await.0, StatusCode::SERVICE_UNAVAILABLE);
+
+        iis.update(Snapshot { paused: true, ..Snapshot::default() });
+        let (_, json) = get_json(&dashboard, "/api/state?account=iis").await;
+        assert_eq!(json["paused"], true);
+        let (_, json) = get_json(&dashboard, "/api/accounts").await;
+        assert_eq!(json, serde_json::json!(["", "iis"]));
+        assert_eq!(get_json(&dashboard, "/api/day?account=..").await.0, StatusCode::NOT_FOUND);
+    }
+    #[tokio::test]
+    async fn token() {
+        let dashboard = Dashboard::new().for_account("", "2000000001");
+        let router = dashboard.router(Some(Secret::new("s3cret".to_string())));
+        let status = |request: Request<Body>| {
+            let router = router.clone();
+            async move { router.oneshot(request).await.unwrap().status() }
+        };
+        let get = |uri: &str| Request::get(uri);
+        assert_eq!(status(get("/api/state").body(Body::empty()).unwrap()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(get("/api/accounts?token=wrong").body(Body::empty()).unwrap()).await, StatusCode::UNAUTHORIZED);
+        let bearer = get("/api/state").header(header::AUTHORIZATION, "Bearer s3cret").body(Body::empty()).unwrap();
+        assert_eq!(status(bearer).await, StatusCode::OK);
+        assert_eq!(status(get("/api/state?account=&token=s3cret").body(Body::empty()).unwrap()).await, StatusCode::OK);
+
+        assert!(dashboard.serve("0.0.0.0:8080".parse().unwrap(), None).is_err());
+    }
+    #[test]
+    fn broker_orders() {
+        let bot = vec![OrderView {
+            order_id: "1000001".to_string(),
+            direction: direction(OrderDirection::Sell),
+            lots: 2,
+            price: Quotation { units: 5, nano: 91_0000000 },
+            purpose: "close".to_string(),
+        }];
+        let order = |order_id: &str| OrderState {
+            order_id: order_id.to_string(),
+            direction: OrderDirection::Sell.into(),
+            lots_requested: 3,
+            lots_executed: 1,
+            initial_security_price: Some(crate::tcs::MoneyValue { currency: "rub".to_string(), units: 5, nano: 92_0000000 }),
+            ..OrderState::default()
+        };
+        let view = OrderView::new(&order("1000001"), &bot);
+        assert_eq!((view.lots, view.purpose.as_str(), view.price.nano), (2, "close", 92_0000000));
+        assert_eq!(OrderView::new(&order("manual"), &bot).purpose, "other");
+    }
+    #[test]
+    fn feed_json() {
+        let book = GetOrderBookResponse {
+            bids: vec![crate::tcs::Order { price: Some(Quotation { units: 5, nano: 0 }), quantity: 10 }],
+            ..GetOrderBookResponse::default()
+        };
+        let json = serde_json::to_value(Feed::order_book(&book)).unwrap();
+        assert_eq!(json["type"], "order_book");
+        assert_eq!(json["bids"][0]["quantity"], 10);
+        assert_eq!(json["asks"].as_array().unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration
+};
+use tonic::{Code, Status};
+
+use crate::grpc::retryable;
+
+
+/// What the bot does when an error reaches the trading loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Recovery {
+    /// Stop the bot, nothing will get better by itself.
+    Fatal,
+    /// Wait and repeat the step.
+    Retry(Duration),
+    /// Drop the current action and go on.
+    Skip,
+    /// Keep managing the position, but open no new ones until `/resume`.
+    Pause,
+}
+
+impl Recovery {
+    const BACKOFF_BASE: Duration = Duration::new(1, 0);
+    const BACKOFF_MAX: Duration = Duration::new(300, 0);
+
+    /// How long the loop waits after the `failures`-th error in a row: the `Retry` pause,
+    /// doubling from a second up to five minutes for `Skip` and `Pause`.
+    pub fn pause(self, failures: u32) -> Duration {
+        match self {
+            Recovery::Fatal => Duration::ZERO,
+            Recovery::Retry(pause) => pause,
+            Recovery::Skip | Recovery::Pause => Self::BACKOFF_BASE
+                .saturating_mul(1 << failures.saturating_sub(1).min(16))
+                .min(Self::BACKOFF_MAX),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Missing env variable, invalid settings.
+    Config(String),
+    Io(std::io::Error),
+    /// Broken files in `add_info`.
+    Persistence { file: String, message: String },
+    Broker(BrokerError),
+    /// The risk checks refused the action.
+    Risk(String),
+    /// No connection to the broker.
+    Transport(String),
+    /// Orders or positions at the broker don't match the bot state.
+    Inconsistent(String),
+}
+
+/// `Status` with the Tinkoff error code, which comes as the status message.
+#[derive(Debug)]
+pub struct BrokerError {
+    pub code: Code,
+    /// See https://tinkoff.github.io/investAPI/errors/
+    pub tinkoff_code: Option<u32>,
+    pub message: String,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    const RETRY_PAUSE: Duration = Duration::new(5, 0);
+    /// The broker answers `ResourceExhausted` until the minute quota is refreshed.
+    const LIMIT_PAUSE: Duration = Duration::new(60, 0);
+
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            Error::Config(_) => Recovery::Fatal,
+            Error::Io(_) | Error::Persistence { .. } | Error::Inconsistent(_) => Recovery::Pause,
+            Error::Risk(_) => Recovery::Skip,
+            Error::Transport(_) => Recovery::Retry(Self::RETRY_PAUSE),
+            Error::Broker(err) => match err.code {
+                Code::Unauthenticated | Code::PermissionDenied => Recovery::Fatal,
+                Code::ResourceExhausted => Recovery::Retry(Self::LIMIT_PAUSE),
+                code if retryable(code) => Recovery::Retry(Self::RETRY_PAUSE),
+                _ => Recovery::Skip,
+            },
+        }
+    }
+
+    pub fn persistence(file: &str, err: impl Display) -> Error {
+        Error::Persistence { file: file.to_string(), message: err.to_string() }
+    }
+
+    /// Reads a required env variable.
+    pub fn env(name: &str) -> Result<String> {
+        std::env::var(name).map_err(|_| Error::Config(format!("{} is not set", name)))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Config(message) => write!(f, "Ошибка настроек: {}", message),
+            Error::Io(err) => write!(f, "Ошибка ввода-вывода: {}", err),
+            Error::Persistence { file, message } => write!(f, "Файл {} повреждён: {}", file, message),
+            Error::Broker(BrokerError { code, tinkoff_code: Some(tinkoff), message }) =>
+                write!(f, "Брокер вернул {:?} ({}): {}", code, tinkoff, message),
+            Error::Broker(BrokerError { code, tinkoff_code: None, message }) =>
+                write!(f, "Брокер вернул {:?}: {}", code, message),
+            Error::Risk(message) => write!(f, "Риск-контроль: {}", message),
+            Error::Transport(message) => write!(f, "Нет связи с брокером: {}", message),
+            Error::Inconsistent(message) => write!(f, "Неожиданное состояние: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Status> for Error {
+    fn from(status: Status) -> Error {
+        let tinkoff_code = status.message().trim().parse().ok();
+        // the human readable text of a Tinkoff error is in the metadata
+        let message = status.metadata().get("message")
+            .and_then(|m| m.to_str().ok())
+            .unwrap_or(status.message())
+            .to_string();
+        Error::Broker(BrokerError { code: status.code(), tinkoff_code, message })
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<tonic::transport::Error> for Error {
+    fn from(err: tonic::transport::Error) -> Error {
+        Error::Transport(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broker_codes() {
+        let mut status = Status::new(Code::InvalidArgument, "30042");
+        status.metadata_mut().insert("message", "not enough assets".parse().unwrap());
+        let err = Error::from(status);
+        match &err {
+            Error::Broker(BrokerError { tinkoff_code, message, .. }) => {
+                assert_eq!(*tinkoff_code, Some(30042));
+                assert_eq!(message, "not enough assets");
+            },
+            _ => panic!("{:?}", err),
+        }
+        assert_eq!(err.recovery(), Recovery::Skip);
+
+        let err = Error::from(Status::unavailable("connection reset"));
+        assert!(matches!(&err, Error::Broker(BrokerError { tinkoff_code: None, .. })));
+        assert!(matches!(err.recovery(), Recovery::Retry(_)));
+        assert_eq!(Error::from(Status::unauthenticated("40003")).recovery(), Recovery::Fatal);
+    }
+    #[test]
+    fn policies() {
+        assert_eq!(Error::Config("TOKEN_BOT is not set".to_string()).recovery(), Recovery::Fatal);
+        assert_eq!(Error::persistence("stat.json", "EOF").recovery(), Recovery::Pause);
+        assert_eq!(Error::Risk("too big".to_string()).recovery(), Recovery::Skip);
+        assert!(Error::env("SURELY_NOT_SET_VARIABLE").is_err());
+        assert_eq!(Recovery::Skip.pause(1), Duration::from_secs(1));
+        assert_eq!(Recovery::Pause.pause(4), Duration::from_secs(8));
+        assert_eq!(Recovery::Pause.pause(100), Duration::from_secs(300));
+    }
+}
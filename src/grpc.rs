@@ -0,0 +1,520 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+use rand::Rng;
+use tokio::time::Instant;
+use tonic::{
+    Code, Response, Status,
+    codegen::InterceptedService,
+    metadata::MetadataMap,
+    codec::Streaming,
+    transport::Channel
+};
+use tracing::{debug, warn};
+
+use crate::DefaultInterceptor;
+use crate::connection::Connection;
+use crate::credentials::Credentials;
+use crate::logging::rpc;
+use crate::tcs::{
+    CancelOrderRequest, CancelOrderResponse, GetAccountsRequest, GetAccountsResponse,
+    CloseSandboxAccountRequest, CloseSandboxAccountResponse, CurrenciesResponse,
+    FindInstrumentRequest, FindInstrumentResponse, GetCandlesRequest, GetCandlesResponse, GetInfoRequest, GetInfoResponse,
+    GetLastPricesRequest, GetLastPricesResponse, GetLastTradesRequest, GetLastTradesResponse,
+    GetMarginAttributesRequest, GetMarginAttributesResponse, GetOrderBookRequest,
+    GetOrderBookResponse, GetOrderStateRequest, GetOrdersRequest, GetOrdersResponse, OrderState,
+    InstrumentRequest, InstrumentResponse, InstrumentsRequest, OpenSandboxAccountRequest,
+    OpenSandboxAccountResponse, PortfolioRequest, PortfolioResponse, PortfolioStreamRequest,
+    PortfolioStreamResponse, PositionsRequest, PositionsResponse, PositionsStreamRequest,
+    PositionsStreamResponse, PostOrderRequest, PostOrderResponse, SandboxPayInRequest,
+    SandboxPayInResponse, TradingSchedulesRequest, TradingSchedulesResponse,
+    WithdrawLimitsRequest, WithdrawLimitsResponse,
+    instruments_service_client::InstrumentsServiceClient,
+    market_data_service_client::MarketDataServiceClient,
+    operations_service_client::OperationsServiceClient,
+    operations_stream_service_client::OperationsStreamServiceClient,
+    orders_service_client::OrdersServiceClient,
+    sandbox_service_client::SandboxServiceClient,
+    users_service_client::UsersServiceClient
+};
+
+
+type Intercepted = InterceptedService<Channel, DefaultInterceptor>;
+
+/// The broker counts the quotas per service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    MarketData,
+    Orders,
+    Operations,
+    Users,
+    Instruments,
+    Sandbox,
+}
+
+impl Service {
+    /// Unary requests per minute, the broker headers override them.
+    fn default_quota(self) -> u32 {
+        match self {
+            Service::MarketData => 600,
+            Service::Orders => 100,
+            Service::Operations => 200,
+            Service::Users => 100,
+            Service::Instruments => 200,
+            Service::Sandbox => 200,
+        }
+    }
+}
+
+/// Worth repeating: the request may succeed a bit later.
+pub fn retryable(code: Code) -> bool {
+    matches!(code, Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted
+        | Code::Internal | Code::Unknown | Code::Aborted)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub attempts: u32,
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff { attempts: 4, base: Duration::from_millis(200), max: Duration::from_secs(10) }
+    }
+}
+
+impl Backoff {
+    /// Full jitter: random in `[0, min(max, base * 2^attempt)]`.
+    pub fn pause(&self, attempt: u32) -> Duration {
+        let cap = self.base.saturating_mul(1 << attempt.min(16)).min(self.max);
+        cap.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// Calls of one service in the last minute plus what the broker told about the quota.
+#[derive(Debug)]
+struct Limiter {
+    quota: u32,
+    calls: VecDeque<Instant>,
+    remaining: Option<u32>,
+    reset: Option<Instant>,
+}
+
+impl Limiter {
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn new(quota: u32) -> Limiter {
+        Limiter { quota, calls: VecDeque::new(), remaining: None, reset: None }
+    }
+
+    /// How long to wait before the next call, `None` takes a slot right away.
+    fn delay(&mut self, now: Instant) -> Option<Duration> {
+        while matches!(self.calls.front(), Some(&t) if now.duration_since(t) >= Self::WINDOW) {
+            self.calls.pop_front();
+        }
+        match (self.remaining, self.reset) {
+            (Some(0), Some(reset)) if reset > now => return Some(reset - now),
+            (Some(0), _) => self.remaining = None,
+            _ => {},
+        }
+        if self.calls.len() >= self.quota as usize {
+            return Some(*self.calls.front().unwrap() + Self::WINDOW - now);
+        }
+        self.calls.push_back(now);
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+        None
+    }
+
+    /// `x-ratelimit-limit: 200, 200;w=60`, `x-ratelimit-remaining: 199`,
+    /// `x-ratelimit-reset: 41` (seconds).
+    fn update(&mut self, metadata: &MetadataMap, now: Instant) {
+        let header = |name| metadata.get(name).and_then(|value| value.to_str().ok());
+        let number = |name| header(name)
+            .and_then(|value: &str| value.split(',').next())
+            .and_then(|value| value.trim().parse::<u32>().ok());
+        if let Some(limit) = number("x-ratelimit-limit") {
+            self.quota = limit;
+        }
+        if let Some(remaining) = number("x-ratelimit-remaining") {
+            self.remaining = Some(remaining);
+        }
+        if let Some(reset) = number("x-ratelimit-reset") {
+            self.reset = Some(now + Duration::from_secs(reset as u64));
+        }
+    }
+}
+
+/// Per-service limiters shared by every clone of `Broker`.
+#[derive(Debug, Default)]
+pub struct Limits {
+    limiters: Mutex<HashMap<Service, Limiter>>,
+}
+
+impl Limits {
+    async fn acquire(&self, service: Service) {
+        loop {
+            let delay = self.limiters.lock().unwrap()
+                .entry(service)
+                .or_insert_with(|| Limiter::new(service.default_quota()))
+                .delay(Instant::now());
+            match delay {
+                None => return,
+                Some(delay) => {
+                    debug!(?service, ?delay, "waiting for the quota");
+                    tokio::time::sleep(delay).await;
+                },
+            }
+        }
+    }
+
+    fn update(&self, service: Service, metadata: &MetadataMap) {
+        self.limiters.lock().unwrap()
+            .entry(service)
+            .or_insert_with(|| Limiter::new(service.default_quota()))
+            .update(metadata, Instant::now());
+    }
+}
+
+/// Waits for the quota, makes the call and repeats it on retryable codes
+/// if the call is `idempotent`. Never blocks the runtime.
+pub async fn call<T, F, Fut>(limits: &Limits, service: Service, name: &'static str,
+                             idempotent: bool, backoff: Backoff, mut make: F)
+    -> Result<Response<T>, Status>
+    where F: FnMut() -> Fut, Fut: Future<Output = Result<Response<T>, Status>> {
+    let mut attempt = 0;
+    loop {
+        limits.acquire(service).await;
+        let result = rpc(name, make()).await;
+        let status = match result {
+            Ok(response) => {
+                limits.update(service, response.metadata());
+                return Ok(response);
+            },
+            Err(status) => status,
+        };
+        limits.update(service, status.metadata());
+        attempt += 1;
+        if !idempotent || !retryable(status.code()) || attempt >= backoff.attempts {
+            return Err(status);
+        }
+        let pause = backoff.pause(attempt);
+        warn!(rpc = name, code = ?status.code(), attempt, ?pause, "retrying");
+        tokio::time::sleep(pause).await;
+    }
+}
+
+/// The broker API used by the bot: quotas, retries, metrics and tracing in one place.
+#[derive(Clone)]
+pub struct Broker {
+    orders: OrdersServiceClient<Intercepted>,
+    market: MarketDataServiceClient<Intercepted>,
+    operations: OperationsServiceClient<Intercepted>,
+    operations_stream: OperationsStreamServiceClient<Intercepted>,
+    users: UsersServiceClient<Intercepted>,
+    instruments: InstrumentsServiceClient<Intercepted>,
+    /// Answers only at the sandbox endpoint.
+    sandbox: SandboxServiceClient<Intercepted>,
+    limits: Arc<Limits>,
+    backoff: Backoff,
+    connection: Connection,
+}
+
+impl Broker {
+    /// Market data and instruments go with the read-only token if there is one.
+    /// The users service stays on the full token: `GetAccounts` reports the access level
+    /// of the token that asks, and the accounts are checked for full access.
+    pub fn new(channel: Channel, credentials: &Credentials, connection: Connection) -> Broker {
+        let full = DefaultInterceptor { token: credentials.token.clone() };
+        let readonly = DefaultInterceptor { token: credentials.market_token().clone() };
+        Broker {
+            orders: OrdersServiceClient::with_interceptor(channel.clone(), full.clone()),
+            market: MarketDataServiceClient::with_interceptor(channel.clone(), readonly.clone()),
+            operations: OperationsServiceClient::with_interceptor(channel.clone(), full.clone()),
+            operations_stream: OperationsStreamServiceClient::with_interceptor(channel.clone(), full.clone()),
+            users: UsersServiceClient::with_interceptor(channel.clone(), full.clone()),
+            instruments: InstrumentsServiceClient::with_interceptor(channel.clone(), readonly),
+            sandbox: SandboxServiceClient::with_interceptor(channel, full),
+            limits: Arc::new(Limits::default()),
+            backoff: Backoff::default(),
+            connection,
+        }
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Passes the outcome of the call to `Connection`.
+    #[allow(clippy::result_large_err)]
+    fn track<T>(&self, result: Result<Response<T>, Status>) -> Result<Response<T>, Status> {
+        self.connection.report(result.as_ref().map(|_| ()));
+        result
+    }
+
+    pub async fn get_order_book(&self, req: GetOrderBookRequest)
+        -> Result<Response<GetOrderBookResponse>, Status> {
+        self.track(call(&self.limits, Service::MarketData, "GetOrderBook", true, self.backoff, || {
+            let (mut client, req) = (self.market.clone(), req.clone());
+            async move { client.get_order_book(req).await }
+        }).await)
+    }
+
+    pub async fn get_candles(&self, req: GetCandlesRequest)
+        -> Result<Response<GetCandlesResponse>, Status> {
+        self.track(call(&self.limits, Service::MarketData, "GetCandles", true, self.backoff, || {
+            let (mut client, req) = (self.market.clone(), req.clone());
+            async move { client.get_candles(req).await }
+        }).await)
+    }
+
+    /// Not repeated: a timeout doesn't tell whether the order was placed.
+    pub async fn post_order(&self, req: PostOrderRequest)
+        -> Result<Response<PostOrderResponse>, Status> {
+        self.track(call(&self.limits, Service::Orders, "PostOrder", false, self.backoff, || {
+            let (mut client, req) = (self.orders.clone(), req.clone());
+            async move { client.post_order(req).await }
+        }).await)
+    }
+
+    /// Not repeated: the second attempt fails on an already cancelled order.
+    pub async fn cancel_order(&self, req: CancelOrderRequest)
+        -> Result<Response<CancelOrderResponse>, Status> {
+        self.track(call(&self.limits, Service::Orders, "CancelOrder", false, self.backoff, || {
+            let (mut client, req) = (self.orders.clone(), req.clone());
+            async move { client.cancel_order(req).await }
+        }).await)
+    }
+
+    pub async fn get_order_state(&self, req: GetOrderStateRequest)
+        -> Result<Response<OrderState>, Status> {
+        self.track(call(&self.limits, Service::Orders, "GetOrderState", true, self.backoff, || {
+            let (mut client, req) = (self.orders.clone(), req.clone());
+            async move { client.get_order_state(req).await }
+        }).await)
+    }
+
+    pub async fn get_orders(&self, req: GetOrdersRequest)
+        -> Result<Response<GetOrdersResponse>, Status> {
+        self.track(call(&self.limits, Service::Orders, "GetOrders", true, self.backoff, || {
+            let (mut client, req) = (self.orders.clone(), req.clone());
+            async move { client.get_orders(req).await }
+        }).await)
+    }
+
+    /// Cheap call for the health check.
+    pub async fn get_info(&self) -> Result<Response<GetInfoResponse>, Status> {
+        self.track(call(&self.limits, Service::Users, "GetInfo", true, self.backoff, || {
+            let mut client = self.users.clone();
+            async move { client.get_info(GetInfoRequest {}).await }
+        }).await)
+    }
+
+    pub async fn get_accounts(&self) -> Result<Response<GetAccountsResponse>, Status> {
+        self.track(call(&self.limits, Service::Users, "GetAccounts", true, self.backoff, || {
+            let mut client = self.users.clone();
+            async move { client.get_accounts(GetAccountsRequest {}).await }
+        }).await)
+    }
+
+    pub async fn get_margin_attributes(&self, account_id: String)
+        -> Result<Response<GetMarginAttributesResponse>, Status> {
+        self.track(call(&self.limits, Service::Users, "GetMarginAttributes", true, self.backoff, || {
+            let (mut client, req) = (self.users.clone(), GetMarginAttributesRequest {
+                account_id: account_id.clone(),
+            });
+            async move { client.get_margin_attributes(req).await }
+        }).await)
+    }
+
+    pub async fn get_positions(&self, req: PositionsRequest)
+        -> Result<Response<PositionsResponse>, Status> {
+        self.track(call(&self.limits, Service::Operations, "GetPositions", true, self.backoff, || {
+            let (mut client, req) = (self.operations.clone(), req.clone());
+            async move { client.get_positions(req).await }
+        }).await)
+    }
+
+    pub async fn get_portfolio(&self, req: PortfolioRequest)
+        -> Result<Response<PortfolioResponse>, Status> {
+        self.track(call(&self.limits, Service::Operations, "GetPortfolio", true, self.backoff, || {
+            let (mut client, req) = (self.operations.clone(), req.clone());
+            async move { client.get_portfolio(req).await }
+        }).await)
+    }
+
+    /// Only opening the stream is retried, a broken stream is reopened by the caller.
+    pub async fn portfolio_stream(&self, req: PortfolioStreamRequest)
+        -> Result<Response<Streaming<PortfolioStreamResponse>>, Status> {
+        self.track(call(&self.limits, Service::Operations, "PortfolioStream", true, self.backoff, || {
+            let (mut client, req) = (self.operations_stream.clone(), req.clone());
+            async move { client.portfolio_stream(req).await }
+        }).await)
+    }
+
+    pub async fn positions_stream(&self, req: PositionsStreamRequest)
+        -> Result<Response<Streaming<PositionsStreamResponse>>, Status> {
+        self.track(call(&self.limits, Service::Operations, "PositionsStream", true, self.backoff, || {
+            let (mut client, req) = (self.operations_stream.clone(), req.clone());
+            async move { client.positions_stream(req).await }
+        }).await)
+    }
+
+    pub async fn get_withdraw_limits(&self, req: WithdrawLimitsRequest)
+        -> Result<Response<WithdrawLimitsResponse>, Status> {
+        self.track(call(&self.limits, Service::Operations, "GetWithdrawLimits", true, self.backoff, || {
+            let (mut client, req) = (self.operations.clone(), req.clone());
+            async move { client.get_withdraw_limits(req).await }
+        }).await)
+    }
+
+    pub async fn get_last_prices(&self, req: GetLastPricesRequest)
+        -> Result<Response<GetLastPricesResponse>, Status> {
+        self.track(call(&self.limits, Service::MarketData, "GetLastPrices", true, self.backoff, || {
+            let (mut client, req) = (self.market.clone(), req.clone());
+            async move { client.get_last_prices(req).await }
+        }).await)
+    }
+
+    pub async fn get_last_trades(&self, req: GetLastTradesRequest)
+        -> Result<Response<GetLastTradesResponse>, Status> {
+        self.track(call(&self.limits, Service::MarketData, "GetLastTrades", true, self.backoff, || {
+            let (mut client, req) = (self.market.clone(), req.clone());
+            async move { client.get_last_trades(req).await }
+        }).await)
+    }
+
+    pub async fn get_currencies(&self, req: InstrumentsRequest)
+        -> Result<Response<CurrenciesResponse>, Status> {
+        self.track(call(&self.limits, Service::Instruments, "Currencies", true, self.backoff, || {
+            let (mut client, req) = (self.instruments.clone(), req.clone());
+            async move { client.currencies(req).await }
+        }).await)
+    }
+
+    pub async fn get_instrument_by(&self, req: InstrumentRequest)
+        -> Result<Response<InstrumentResponse>, Status> {
+        self.track(call(&self.limits, Service::Instruments, "GetInstrumentBy", true, self.backoff, || {
+            let (mut client, req) = (self.instruments.clone(), req.clone());
+            async move { client.get_instrument_by(req).await }
+        }).await)
+    }
+
+    pub async fn find_instrument(&self, req: FindInstrumentRequest)
+        -> Result<Response<FindInstrumentResponse>, Status> {
+        self.track(call(&self.limits, Service::Instruments, "FindInstrument", true, self.backoff, || {
+            let (mut client, req) = (self.instruments.clone(), req.clone());
+            async move { client.find_instrument(req).await }
+        }).await)
+    }
+
+    pub async fn trading_schedules(&self, req: TradingSchedulesRequest)
+        -> Result<Response<TradingSchedulesResponse>, Status> {
+        self.track(call(&self.limits, Service::Instruments, "TradingSchedules", true, self.backoff, || {
+            let (mut client, req) = (self.instruments.clone(), req.clone());
+            async move { client.trading_schedules(req).await }
+        }).await)
+    }
+
+    /// Not repeated: a timeout doesn't tell whether the account was opened.
+    pub async fn open_sandbox_account(&self) -> Result<Response<OpenSandboxAccountResponse>, Status> {
+        self.track(call(&self.limits, Service::Sandbox, "OpenSandboxAccount", false, self.backoff, || {
+            let mut client = self.sandbox.clone();
+            async move { client.open_sandbox_account(OpenSandboxAccountRequest {}).await }
+        }).await)
+    }
+
+    /// Not repeated: the money could come twice.
+    pub async fn sandbox_pay_in(&self, req: SandboxPayInRequest)
+        -> Result<Response<SandboxPayInResponse>, Status> {
+        self.track(call(&self.limits, Service::Sandbox, "SandboxPayIn", false, self.backoff, || {
+            let (mut client, req) = (self.sandbox.clone(), req.clone());
+            async move { client.sandbox_pay_in(req).await }
+        }).await)
+    }
+
+    pub async fn close_sandbox_account(&self, req: CloseSandboxAccountRequest)
+        -> Result<Response<CloseSandboxAccountResponse>, Status> {
+        self.track(call(&self.limits, Service::Sandbox, "CloseSandboxAccount", false, self.backoff, || {
+            let (mut client, req) = (self.sandbox.clone(), req.clone());
+            async move { client.close_sandbox_account(req).await }
+        }).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const FAST: Backoff = Backoff {
+        attempts: 4,
+        base: Duration::from_millis(100),
+        max: Duration::from_secs(1),
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn local_quota() {
+        let mut limiter = Limiter::new(2);
+        let now = Instant::now();
+        assert_eq!(limiter.delay(now), None);
+        assert_eq!(limiter.delay(now + Duration::from_secs(10)), None);
+        assert_eq!(limiter.delay(now + Duration::from_secs(20)), Some(Duration::from_secs(40)));
+        assert_eq!(limiter.delay(now + Duration::from_secs(60)), None);
+    }
+    #[tokio::test(start_paused = true)]
+    async fn broker_headers() {
+        let mut limiter = Limiter::new(100);
+        let now = Instant::now();
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-ratelimit-limit", "200, 200;w=60".parse().unwrap());
+        metadata.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        metadata.insert("x-ratelimit-reset", "41".parse().unwrap());
+        limiter.update(&metadata, now);
+        assert_eq!(limiter.quota, 200);
+        assert_eq!(limiter.delay(now), Some(Duration::from_secs(41)));
+        assert_eq!(limiter.delay(now + Duration::from_secs(41)), None);
+    }
+    #[tokio::test(start_paused = true)]
+    async fn retries() {
+        let limits = Limits::default();
+        let calls = AtomicU32::new(0);
+        let result = call(&limits, Service::MarketData, "GetOrderBook", true, FAST, || {
+            let n = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if n < 2 { Err(Status::unavailable("")) } else { Ok(Response::new(n)) }
+            }
+        }).await;
+        assert_eq!(result.unwrap().into_inner(), 2);
+
+        calls.store(0, Ordering::SeqCst);
+        let result = call(&limits, Service::Orders, "PostOrder", false, FAST, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err::<Response<()>, _>(Status::unavailable("")) }
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        calls.store(0, Ordering::SeqCst);
+        let result = call(&limits, Service::Orders, "GetOrderState", true, FAST, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err::<Response<()>, _>(Status::invalid_argument("30003")) }
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "fatal codes are not retried");
+
+        calls.store(0, Ordering::SeqCst);
+        let result = call(&limits, Service::Operations, "GetPositions", true, FAST, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err::<Response<()>, _>(Status::unavailable("")) }
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), FAST.attempts);
+    }
+}
@@ -0,0 +1,559 @@
+//! Market data kept on disk for research and backtests: candles downloaded with `GetCandles`,
+//! order books and trades recorded live, the API has no history of them.
+//! The candle strategies start with the stored candles of the last days, see `recent`.
+//!
+//! The files are `history/<uid>/<interval>/<day or year>.<csv|bin>`,
+//! `history/<uid>/orderbook/<day>.jsonl` and `history/<uid>/trades/<day>.csv`.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration
+};
+use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+use chrono_tz::Europe::Moscow;
+use prost_types::Timestamp;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::candles;
+use crate::error::{Error, Recovery, Result};
+use crate::grpc::Broker;
+use crate::tcs::{
+    CandleInterval, GetCandlesRequest, GetLastTradesRequest, GetOrderBookRequest,
+    GetOrderBookResponse, HistoricCandle, Order, OrderDirection, Quotation, Trade
+};
+
+
+pub const HISTORY_PATH: &str = "history";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `time,open,high,low,close,volume`, time in unix seconds.
+    Csv,
+    /// `MAGIC` and 48 bytes a candle: time, the prices in nanos and volume, `i64` little endian.
+    Binary,
+}
+
+impl Format {
+    const MAGIC: &'static [u8] = b"CND1";
+    const RECORD: usize = 48;
+
+    pub fn parse(name: &str) -> Result<Format> {
+        match name {
+            "csv" => Ok(Format::Csv),
+            "bin" => Ok(Format::Binary),
+            other => Err(Error::Config(format!("unknown format {}, csv or bin", other))),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Binary => "bin",
+        }
+    }
+
+    pub fn encode(self, candles: &[HistoricCandle]) -> Vec<u8> {
+        let price = |q: &Option<Quotation>| q.clone().unwrap_or_default();
+        match self {
+            Format::Csv => {
+                let mut text = String::from("time,open,high,low,close,volume\n");
+                for c in candles {
+                    text.push_str(&format!("{},{},{},{},{},{}\n", seconds(&c.time),
+                        price(&c.open), price(&c.high), price(&c.low), price(&c.close), c.volume));
+                }
+                text.into_bytes()
+            },
+            Format::Binary => {
+                let mut bytes = Vec::with_capacity(Self::MAGIC.len() + candles.len() * Self::RECORD);
+                bytes.extend_from_slice(Self::MAGIC);
+                for c in candles {
+                    let prices = [&c.open, &c.high, &c.low, &c.close].map(|p| price(p).to_nanos() as i64);
+                    for value in [seconds(&c.time)].iter().chain(&prices).chain([&c.volume]) {
+                        bytes.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                bytes
+            },
+        }
+    }
+
+    /// The stored candles are all complete.
+    pub fn decode(self, bytes: &[u8]) -> std::result::Result<Vec<HistoricCandle>, String> {
+        let candle = |values: [i64; 6]| HistoricCandle {
+            time: Some(Timestamp { seconds: values[0], nanos: 0 }),
+            open: Some(Quotation::from_nanos(values[1] as i128)),
+            high: Some(Quotation::from_nanos(values[2] as i128)),
+            low: Some(Quotation::from_nanos(values[3] as i128)),
+            close: Some(Quotation::from_nanos(values[4] as i128)),
+            volume: values[5],
+            is_complete: true,
+        };
+        match self {
+            Format::Csv => {
+                let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+                text.lines().skip(1).enumerate()
+                    .map(|(n, line)| {
+                        let fields: Vec<&str> = line.split(',').collect();
+                        let parsed = (fields.len() == 6).then_some(()).and_then(|_| Some([
+                            fields[0].parse().ok()?,
+                            parse_nanos(fields[1])?,
+                            parse_nanos(fields[2])?,
+                            parse_nanos(fields[3])?,
+                            parse_nanos(fields[4])?,
+                            fields[5].parse().ok()?,
+                        ]));
+                        parsed.map(candle).ok_or_else(|| format!("строка {}: {}", n + 2, line))
+                    })
+                    .collect()
+            },
+            Format::Binary => {
+                let records = bytes.strip_prefix(Self::MAGIC).ok_or("нет заголовка")?;
+                if records.len() % Self::RECORD != 0 {
+                    return Err("файл обрезан".to_string());
+                }
+                Ok(records.chunks(Self::RECORD)
+                    .map(|record| {
+                        let mut values = [0; 6];
+                        for (value, bytes) in values.iter_mut().zip(record.chunks(8)) {
+                            *value = i64::from_le_bytes(bytes.try_into().unwrap());
+                        }
+                        candle(values)
+                    })
+                    .collect())
+            },
+        }
+    }
+}
+
+fn seconds(time: &Option<Timestamp>) -> i64 {
+    time.as_ref().map(|t| t.seconds).unwrap_or_default()
+}
+
+/// `5.9` as `Quotation::to_string` writes it, in nanos.
+fn parse_nanos(text: &str) -> Option<i64> {
+    // the sign is for both parts, "-0.5" has none in the units
+    let (sign, text) = text.strip_prefix('-').map_or((1, text), |text| (-1, text));
+    let (units, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if units.is_empty() || !digits(units) || fraction.len() > 9 || !digits(fraction) {
+        return None;
+    }
+    Some(sign * (units.parse::<i64>().ok()? * 1_000_000_000 + format!("{:0<9}", fraction).parse::<i64>().ok()?))
+}
+
+pub fn parse_price(text: &str) -> Option<Quotation> {
+    parse_nanos(text).map(|nanos| Quotation::from_nanos(nanos as i128))
+}
+
+/// `1min`, `5min`, `15min`, `hour` or `day`.
+pub fn parse_interval(name: &str) -> Result<CandleInterval> {
+    match name {
+        "1min" => Ok(CandleInterval::CandleInterval1Min),
+        "5min" => Ok(CandleInterval::CandleInterval5Min),
+        "15min" => Ok(CandleInterval::CandleInterval15Min),
+        "hour" => Ok(CandleInterval::Hour),
+        "day" => Ok(CandleInterval::Day),
+        other => Err(Error::Config(format!("unknown interval {}, 1min, 5min, 15min, hour or day", other))),
+    }
+}
+
+fn interval_name(interval: CandleInterval) -> &'static str {
+    match interval {
+        CandleInterval::CandleInterval5Min => "5min",
+        CandleInterval::CandleInterval15Min => "15min",
+        CandleInterval::Hour => "hour",
+        CandleInterval::Day => "day",
+        _ => "1min",
+    }
+}
+
+/// Moscow midnight of the day in unix seconds.
+pub fn midnight(day: NaiveDate) -> i64 {
+    Moscow.from_local_datetime(&day.and_hms_opt(0, 0, 0).unwrap()).earliest()
+        .map(|time| time.timestamp())
+        .unwrap_or_default()
+}
+
+fn today() -> NaiveDate {
+    Utc::now().with_timezone(&Moscow).date_naive()
+}
+
+/// Days `[start, end)` kept in one file: a day for the intraday candles, a year for the daily ones.
+fn chunks(interval: CandleInterval, from: NaiveDate, to: NaiveDate) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut chunks = Vec::new();
+    let mut day = from;
+    while day <= to {
+        let chunk = if interval == CandleInterval::Day {
+            let start = NaiveDate::from_ymd_opt(day.year(), 1, 1).unwrap();
+            (start, NaiveDate::from_ymd_opt(day.year() + 1, 1, 1).unwrap())
+        } else {
+            (day, day.succ_opt().unwrap())
+        };
+        day = chunk.1;
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Candle files of one format under `dir`.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    format: Format,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>, format: Format) -> Cache {
+        Cache { dir: dir.into(), format }
+    }
+
+    fn path(&self, uid: &str, interval: CandleInterval, (start, _): (NaiveDate, NaiveDate)) -> PathBuf {
+        let name = if interval == CandleInterval::Day { start.year().to_string() } else { start.to_string() };
+        self.dir.join(uid).join(interval_name(interval)).join(name).with_extension(self.format.extension())
+    }
+
+    fn read(&self, path: &Path) -> Result<Option<Vec<HistoricCandle>>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        self.format.decode(&bytes)
+            .map(Some)
+            .map_err(|err| Error::persistence(&path.display().to_string(), err))
+    }
+
+    /// Through a temporary file, an interrupted download leaves no half of a file.
+    fn write(&self, path: &Path, candles: &[HistoricCandle]) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = path.with_extension("part");
+        fs::write(&temporary, self.format.encode(candles))?;
+        fs::rename(temporary, path)?;
+        Ok(())
+    }
+
+    /// The stored candles of the days `from..=to`, missing files are skipped.
+    pub fn load(&self, uid: &str, interval: CandleInterval, from: NaiveDate, to: NaiveDate)
+        -> Result<Vec<HistoricCandle>> {
+        let (begins, ends) = (midnight(from), midnight(to.succ_opt().unwrap()));
+        let mut candles = Vec::new();
+        for chunk in chunks(interval, from, to) {
+            if let Some(stored) = self.read(&self.path(uid, interval, chunk))? {
+                candles.extend(stored.into_iter()
+                    .filter(|c| (begins..ends).contains(&seconds(&c.time))));
+            }
+        }
+        Ok(candles)
+    }
+}
+
+/// The stored candles of the `days` before today in both formats, for the candle strategies
+/// to start with their history; today's candles come from the broker.
+pub fn recent(dir: &Path, uid: &str, interval: CandleInterval, days: i64) -> Result<Vec<HistoricCandle>> {
+    let to = today().pred_opt().unwrap();
+    let from = to - chrono::Duration::days(days - 1);
+    let mut candles = Vec::new();
+    for format in [Format::Csv, Format::Binary] {
+        candles.extend(Cache::new(dir, format).load(uid, interval, from, to)?);
+    }
+    candles.sort_by_key(|c| seconds(&c.time));
+    candles.dedup_by_key(|c| seconds(&c.time));
+    Ok(candles)
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Downloaded {
+    /// Files written.
+    pub files: usize,
+    /// Files left as they were, downloaded before.
+    pub skipped: usize,
+    pub candles: usize,
+}
+
+/// Downloads the candles of the days `from..=to` that are not in the cache yet.
+/// Files of the periods that are not over are downloaded again next time.
+pub async fn download(broker: &Broker, cache: &Cache, uid: &str, interval: CandleInterval,
+                      from: NaiveDate, to: NaiveDate) -> Result<Downloaded> {
+    let mut result = Downloaded::default();
+    let now = Utc::now().timestamp();
+    for chunk in chunks(interval, from, to.min(today())) {
+        let path = cache.path(uid, interval, chunk);
+        let over = chunk.1 <= today();
+        if over && cache.read(&path)?.is_some() {
+            result.skipped += 1;
+            continue;
+        }
+        let mut candles = Vec::new();
+        let (mut page, ends) = (midnight(chunk.0), midnight(chunk.1).min(now));
+        while page < ends {
+            let until = (page + candles::max_period(interval)).min(ends);
+            let response = broker.get_candles(GetCandlesRequest {
+                figi: String::new(),
+                interval: interval.into(),
+                from: Some(Timestamp { seconds: page, nanos: 0 }),
+                to: Some(Timestamp { seconds: until, nanos: 0 }),
+                instrument_id: uid.to_string(),
+            }).await?;
+            candles.extend(response.into_inner().candles.into_iter().filter(|c| c.is_complete));
+            page = until;
+        }
+        cache.write(&path, &candles)?;
+        info!(path = %path.display(), candles = candles.len(), "candles saved");
+        result.files += 1;
+        result.candles += candles.len();
+    }
+    Ok(result)
+}
+
+/// A line of `orderbook/<day>.jsonl`, the prices as decimal strings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BookRecord {
+    /// Unix milliseconds.
+    pub time: i64,
+    pub bids: Vec<(String, i64)>,
+    pub asks: Vec<(String, i64)>,
+}
+
+impl BookRecord {
+    pub fn new(time: i64, book: &GetOrderBookResponse) -> BookRecord {
+        let levels = |orders: &[Order]| orders.iter()
+            .map(|o| (o.price.clone().unwrap_or_default().to_string(), o.quantity))
+            .collect();
+        BookRecord { time, bids: levels(&book.bids), asks: levels(&book.asks) }
+    }
+
+    pub fn to_response(&self) -> GetOrderBookResponse {
+        let orders = |levels: &[(String, i64)]| levels.iter()
+            .map(|(price, quantity)| Order { price: parse_price(price), quantity: *quantity })
+            .collect();
+        GetOrderBookResponse {
+            depth: self.bids.len().max(self.asks.len()) as i32,
+            bids: orders(&self.bids),
+            asks: orders(&self.asks),
+            orderbook_ts: Some(Timestamp {
+                seconds: self.time.div_euclid(1000),
+                nanos: (self.time.rem_euclid(1000) * 1_000_000) as i32,
+            }),
+            ..GetOrderBookResponse::default()
+        }
+    }
+}
+
+/// A line of `trades/<day>.csv`: `time,direction,price,quantity`, time in unix milliseconds.
+pub fn trade_line(trade: &Trade) -> String {
+    let direction = match OrderDirection::from_i32(trade.direction) {
+        Some(OrderDirection::Buy) => "buy",
+        Some(OrderDirection::Sell) => "sell",
+        _ => "",
+    };
+    format!("{},{},{},{}", millis(&trade.time), direction,
+            trade.price.clone().unwrap_or_default(), trade.quantity)
+}
+
+pub fn parse_trade(line: &str) -> Option<Trade> {
+    let fields: Vec<&str> = line.split(',').collect();
+    let [time, direction, price, quantity] = fields[..] else {
+        return None;
+    };
+    let time: i64 = time.parse().ok()?;
+    let direction = match direction {
+        "buy" => OrderDirection::Buy,
+        "sell" => OrderDirection::Sell,
+        _ => OrderDirection::Unspecified,
+    };
+    Some(Trade {
+        direction: direction.into(),
+        price: Some(parse_price(price)?),
+        quantity: quantity.parse().ok()?,
+        time: Some(Timestamp { seconds: time.div_euclid(1000), nanos: (time.rem_euclid(1000) * 1_000_000) as i32 }),
+        ..Trade::default()
+    })
+}
+
+/// Unix milliseconds, zero if there is no time.
+pub fn millis(time: &Option<Timestamp>) -> i64 {
+    time.as_ref().map(|t| t.seconds * 1000 + t.nanos as i64 / 1_000_000).unwrap_or_default()
+}
+
+/// File of the kind (`orderbook`, `trades`) for the Moscow `day`.
+pub fn record_file(dir: &Path, uid: &str, kind: &str, day: NaiveDate, extension: &str) -> PathBuf {
+    dir.join(uid).join(kind).join(day.to_string()).with_extension(extension)
+}
+
+/// File of the kind for the day of `time` in milliseconds.
+fn record_path(dir: &Path, uid: &str, kind: &str, time: i64, extension: &str) -> PathBuf {
+    let day = Utc.timestamp_millis_opt(time).single()
+        .map(|time| time.with_timezone(&Moscow).date_naive())
+        .unwrap_or_else(today);
+    record_file(dir, uid, kind, day, extension)
+}
+
+fn append(path: &Path, line: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Writes order book snapshots and new trades of the instrument every `period` until `stop` resolves.
+/// Errors are logged and the recording goes on, unless they are fatal.
+pub async fn record(broker: &Broker, dir: &Path, uid: &str, depth: i32, period: Duration,
+                    stop: impl std::future::Future<Output = ()>) -> Result<()> {
+    tokio::pin!(stop);
+    let mut tick = tokio::time::interval(period);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_trade = None;
+    loop {
+        tokio::select! {
+            _ = &mut stop => return Ok(()),
+            _ = tick.tick() => {},
+        }
+        if let Err(err) = record_once(broker, dir, uid, depth, &mut last_trade).await {
+            if err.recovery() == Recovery::Fatal {
+                return Err(err);
+            }
+            warn!(error = %err, "recording failed");
+        }
+    }
+}
+
+async fn record_once(broker: &Broker, dir: &Path, uid: &str, depth: i32,
+                     last_trade: &mut Option<i64>) -> Result<()> {
+    let book = broker.get_order_book(GetOrderBookRequest {
+        figi: String::new(),
+        depth,
+        instrument_id: uid.to_string(),
+    }).await?.into_inner();
+    if !book.bids.is_empty() || !book.asks.is_empty() {
+        let time = match millis(&book.orderbook_ts) {
+            0 => Utc::now().timestamp_millis(),
+            time => time,
+        };
+        let line = serde_json::to_string(&BookRecord::new(time, &book))
+            .map_err(|err| Error::persistence("orderbook", err))?;
+        append(&record_path(dir, uid, "orderbook", time, "jsonl"), &line)?;
+    }
+
+    let mut trades = broker.get_last_trades(GetLastTradesRequest {
+        figi: String::new(),
+        from: last_trade.map(|time| Timestamp { seconds: time / 1000, nanos: 0 }),
+        to: Some(Timestamp { seconds: Utc::now().timestamp(), nanos: 0 }),
+        instrument_id: uid.to_string(),
+    }).await?.into_inner().trades;
+    trades.sort_by_key(|t| millis(&t.time));
+    // the request is by seconds, the trades of the last second come again
+    let seen = *last_trade;
+    for trade in trades.iter().filter(|t| Some(millis(&t.time)) > seen) {
+        let time = millis(&trade.time);
+        append(&record_path(dir, uid, "trades", time, "csv"), &trade_line(trade))?;
+        *last_trade = Some(time);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(seconds: i64, close: Quotation) -> HistoricCandle {
+        HistoricCandle {
+            open: Some(Quotation { units: 5, nano: 90_0000000 }),
+            high: Some(Quotation { units: 6, nano: 0 }),
+            low: Some(Quotation { units: 5, nano: 1 }),
+            close: Some(close),
+            volume: 1200,
+            time: Some(Timestamp { seconds, nanos: 0 }),
+            is_complete: true,
+        }
+    }
+
+    #[test]
+    fn formats() {
+        let candles = vec![
+            candle(1_675_926_000, Quotation { units: 5, nano: 95_0000000 }),
+            candle(1_675_926_060, Quotation { units: 0, nano: 25_0000000 }),
+        ];
+        for format in [Format::Csv, Format::Binary] {
+            assert_eq!(format.decode(&format.encode(&candles)).unwrap(), candles);
+        }
+        let csv = String::from_utf8(Format::Csv.encode(&candles[..1])).unwrap();
+        assert_eq!(csv, "time,open,high,low,close,volume\n1675926000,5.9,6,5.000000001,5.95,1200\n");
+        assert_eq!(Format::Binary.encode(&candles).len(), 4 + 2 * 48);
+        assert!(Format::Binary.decode(&Format::Binary.encode(&candles)[..50]).is_err());
+        assert!(Format::Csv.decode(b"time\n1,2,3").is_err());
+    }
+    #[test]
+    fn signed_prices() {
+        assert_eq!(parse_nanos("-0.5"), Some(-500_000_000));
+        assert_eq!(parse_nanos("-1.5"), Some(-1_500_000_000));
+        assert_eq!(parse_nanos("-2"), Some(-2_000_000_000));
+        assert_eq!(parse_nanos("5.000000001"), Some(5_000_000_001));
+        for bad in ["", "-", "--1", "+1", ".5", "1.-5", "1.0000000001"] {
+            assert_eq!(parse_nanos(bad), None, "{}", bad);
+        }
+        let price = Quotation::from_nanos(-500_000_000);
+        assert_eq!(parse_price(&price.to_string()), Some(price));
+    }
+    #[test]
+    fn chunks_of_files() {
+        let day = |m, d| NaiveDate::from_ymd_opt(2023, m, d).unwrap();
+        assert_eq!(chunks(CandleInterval::CandleInterval1Min, day(2, 27), day(3, 1)),
+                   vec![(day(2, 27), day(2, 28)), (day(2, 28), day(3, 1)), (day(3, 1), day(3, 2))]);
+        let years = chunks(CandleInterval::Day, day(2, 27), NaiveDate::from_ymd_opt(2024, 1, 5).unwrap());
+        assert_eq!(years.len(), 2);
+        assert_eq!(years[0].0, day(1, 1));
+
+        let dir = std::env::temp_dir().join(format!("history-{}", std::process::id()));
+        let cache = Cache::new(&dir, Format::Binary);
+        let chunk = (day(2, 9), day(2, 10));
+        let path = cache.path("uid", CandleInterval::CandleInterval1Min, chunk);
+        assert!(path.ends_with("uid/1min/2023-02-09.bin"));
+        let candles = vec![candle(midnight(day(2, 9)) + 36_000, Quotation { units: 6, nano: 0 })];
+        cache.write(&path, &candles).unwrap();
+        assert_eq!(cache.load("uid", CandleInterval::CandleInterval1Min, day(2, 8), day(2, 9)).unwrap(), candles);
+        assert!(cache.load("uid", CandleInterval::CandleInterval1Min, day(2, 10), day(2, 10)).unwrap().is_empty());
+
+        // yesterday in both formats, once
+        let yesterday = today().pred_opt().unwrap();
+        let candles = vec![candle(midnight(yesterday) + 36_000, Quotation { units: 6, nano: 0 })];
+        for format in [Format::Csv, Format::Binary] {
+            let cache = Cache::new(&dir, format);
+            cache.write(&cache.path("uid", CandleInterval::CandleInterval1Min, (yesterday, today())), &candles).unwrap();
+        }
+        assert_eq!(recent(&dir, "uid", CandleInterval::CandleInterval1Min, 2).unwrap(), candles);
+        assert!(recent(&dir, "other", CandleInterval::CandleInterval1Min, 2).unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn records() {
+        let book = GetOrderBookResponse {
+            bids: vec![Order { price: Some(Quotation { units: 5, nano: 89_0000000 }), quantity: 300 }],
+            asks: vec![Order { price: Some(Quotation { units: 5, nano: 91_0000000 }), quantity: 100 }],
+            ..GetOrderBookResponse::default()
+        };
+        let record = BookRecord::new(1_675_926_000_250, &book);
+        let line = serde_json::to_string(&record).unwrap();
+        assert_eq!(line, r#"{"time":1675926000250,"bids":[["5.89",300]],"asks":[["5.91",100]]}"#);
+        let back = serde_json::from_str::<BookRecord>(&line).unwrap().to_response();
+        assert_eq!(back.bids, book.bids);
+        assert_eq!(back.orderbook_ts, Some(Timestamp { seconds: 1_675_926_000, nanos: 250_000_000 }));
+
+        let trade = Trade {
+            direction: OrderDirection::Sell.into(),
+            price: Some(Quotation { units: 5, nano: 9_0000000 }),
+            quantity: 7,
+            time: Some(Timestamp { seconds: 1_675_926_000, nanos: 5_000_000 }),
+            ..Trade::default()
+        };
+        assert_eq!(trade_line(&trade), "1675926000005,sell,5.09,7");
+        assert_eq!(parse_trade(&trade_line(&trade)), Some(trade));
+        assert_eq!(parse_trade("1,buy,x,1"), None);
+    }
+}
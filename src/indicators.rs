@@ -0,0 +1,833 @@
+//! Streaming indicators: every `update` takes the next closed candle
+//! and gives `None` until there is enough data.
+//! `strategies::trend` uses EMA, RSI, ADX and ATR, the others wait for a strategy.
+
+use std::collections::VecDeque;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Europe::Moscow;
+
+use crate::tcs::{Candle, HistoricCandle, Quotation};
+
+
+/// A candle in floats, the indicators don't need the exact prices.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bar {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    /// Unix seconds of the candle start.
+    pub time: i64,
+}
+
+fn price(q: &Option<Quotation>) -> f64 {
+    q.as_ref().map(Quotation::to_f64).unwrap_or_default()
+}
+
+impl From<&HistoricCandle> for Bar {
+    fn from(c: &HistoricCandle) -> Bar {
+        Bar {
+            open: price(&c.open),
+            high: price(&c.high),
+            low: price(&c.low),
+            close: price(&c.close),
+            volume: c.volume as f64,
+            time: c.time.as_ref().map(|t| t.seconds).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&Candle> for Bar {
+    fn from(c: &Candle) -> Bar {
+        Bar {
+            open: price(&c.open),
+            high: price(&c.high),
+            low: price(&c.low),
+            close: price(&c.close),
+            volume: c.volume as f64,
+            time: c.time.as_ref().map(|t| t.seconds).unwrap_or_default(),
+        }
+    }
+}
+
+pub trait Indicator {
+    type Output;
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output>;
+}
+
+/// The last `n` values with their sum.
+#[derive(Debug, Clone)]
+struct Window {
+    n: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Window {
+    fn new(n: usize) -> Window {
+        assert!(n > 0, "period must be positive");
+        Window { n, values: VecDeque::with_capacity(n + 1), sum: 0.0 }
+    }
+
+    fn push(&mut self, value: f64) {
+        self.values.push_back(value);
+        self.sum += value;
+        if self.values.len() > self.n {
+            self.sum -= self.values.pop_front().unwrap();
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.values.len() == self.n
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum / self.values.len() as f64
+    }
+}
+
+/// Simple moving average.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Sma(Window);
+
+#[allow(dead_code)]
+impl Sma {
+    pub fn new(n: usize) -> Sma {
+        Sma(Window::new(n))
+    }
+
+    pub fn next(&mut self, value: f64) -> Option<f64> {
+        self.0.push(value);
+        self.0.full().then(|| self.0.mean())
+    }
+}
+
+/// Exponential moving average, `2 / (n + 1)`, starts from the SMA of the first `n` values.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    alpha: f64,
+    seed: Window,
+    value: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(n: usize) -> Ema {
+        Ema { alpha: 2.0 / (n as f64 + 1.0), seed: Window::new(n), value: None }
+    }
+
+    pub fn next(&mut self, value: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(prev) => Some(self.alpha * value + (1.0 - self.alpha) * prev),
+            None => {
+                self.seed.push(value);
+                self.seed.full().then(|| self.seed.mean())
+            },
+        };
+        self.value
+    }
+}
+
+/// Weighted moving average, the newest value weighs `n`, the oldest 1.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Wma(Window);
+
+#[allow(dead_code)]
+impl Wma {
+    pub fn new(n: usize) -> Wma {
+        Wma(Window::new(n))
+    }
+
+    pub fn next(&mut self, value: f64) -> Option<f64> {
+        self.0.push(value);
+        if !self.0.full() {
+            return None;
+        }
+        let n = self.0.n as f64;
+        let weighted: f64 = self.0.values.iter().enumerate()
+            .map(|(i, v)| (i + 1) as f64 * v)
+            .sum();
+        Some(weighted / (n * (n + 1.0) / 2.0))
+    }
+}
+
+/// Wilder's smoothing: the average of the first `n` values, then `(prev * (n - 1) + x) / n`.
+#[derive(Debug, Clone)]
+struct Wilder {
+    seed: Window,
+    value: Option<f64>,
+}
+
+impl Wilder {
+    fn new(n: usize) -> Wilder {
+        Wilder { seed: Window::new(n), value: None }
+    }
+
+    fn next(&mut self, value: f64) -> Option<f64> {
+        let n = self.seed.n as f64;
+        self.value = match self.value {
+            Some(prev) => Some((prev * (n - 1.0) + value) / n),
+            None => {
+                self.seed.push(value);
+                self.seed.full().then(|| self.seed.mean())
+            },
+        };
+        self.value
+    }
+}
+
+/// Relative strength index with Wilder's smoothing, 0..100.
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    prev: Option<f64>,
+    gain: Wilder,
+    loss: Wilder,
+}
+
+impl Rsi {
+    pub fn new(n: usize) -> Rsi {
+        Rsi { prev: None, gain: Wilder::new(n), loss: Wilder::new(n) }
+    }
+
+    pub fn next(&mut self, value: f64) -> Option<f64> {
+        let change = value - self.prev.replace(value)?;
+        let gain = self.gain.next(change.max(0.0));
+        let loss = self.loss.next((-change).max(0.0));
+        let (gain, loss) = (gain?, loss?);
+        if loss == 0.0 {
+            return Some(100.0);
+        }
+        Some(100.0 - 100.0 / (1.0 + gain / loss))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub struct MacdValue {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// Moving average convergence/divergence, usually 12, 26, 9.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+#[allow(dead_code)]
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Macd {
+        Macd { fast: Ema::new(fast), slow: Ema::new(slow), signal: Ema::new(signal) }
+    }
+
+    pub fn next(&mut self, value: f64) -> Option<MacdValue> {
+        let (fast, slow) = (self.fast.next(value), self.slow.next(value));
+        let macd = fast? - slow?;
+        let signal = self.signal.next(macd)?;
+        Some(MacdValue { macd, signal, histogram: macd - signal })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub struct Band {
+    pub lower: f64,
+    pub middle: f64,
+    pub upper: f64,
+}
+
+/// Bollinger bands: SMA and `k` population standard deviations around it.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Bollinger {
+    window: Window,
+    k: f64,
+}
+
+#[allow(dead_code)]
+impl Bollinger {
+    pub fn new(n: usize, k: f64) -> Bollinger {
+        Bollinger { window: Window::new(n), k }
+    }
+
+    pub fn next(&mut self, value: f64) -> Option<Band> {
+        self.window.push(value);
+        if !self.window.full() {
+            return None;
+        }
+        let middle = self.window.mean();
+        let variance = self.window.values.iter()
+            .map(|v| (v - middle).powi(2))
+            .sum::<f64>() / self.window.n as f64;
+        let width = self.k * variance.sqrt();
+        Some(Band { lower: middle - width, middle, upper: middle + width })
+    }
+}
+
+/// True range of the bar, the first one has no previous close.
+fn true_range(bar: &Bar, prev_close: Option<f64>) -> f64 {
+    match prev_close {
+        Some(close) => (bar.high - bar.low)
+            .max((bar.high - close).abs())
+            .max((bar.low - close).abs()),
+        None => bar.high - bar.low,
+    }
+}
+
+/// Average true range with Wilder's smoothing.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    prev_close: Option<f64>,
+    range: Wilder,
+}
+
+impl Atr {
+    pub fn new(n: usize) -> Atr {
+        Atr { prev_close: None, range: Wilder::new(n) }
+    }
+}
+
+impl Indicator for Atr {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        let range = true_range(bar, self.prev_close.replace(bar.close));
+        self.range.next(range)
+    }
+}
+
+/// Volume weighted average of the typical price `(high + low + close) / 3`,
+/// starts over every trading day (Moscow time).
+#[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
+pub struct Vwap {
+    day: Option<NaiveDate>,
+    value: f64,
+    volume: f64,
+}
+
+#[allow(dead_code)]
+impl Vwap {
+    pub fn new() -> Vwap {
+        Vwap::default()
+    }
+}
+
+impl Indicator for Vwap {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        let day = Utc.timestamp_opt(bar.time, 0).single()
+            .map(|time: DateTime<Utc>| time.with_timezone(&Moscow).date_naive());
+        if day != self.day {
+            *self = Vwap { day, ..Vwap::default() };
+        }
+        self.value += (bar.high + bar.low + bar.close) / 3.0 * bar.volume;
+        self.volume += bar.volume;
+        (self.volume > 0.0).then(|| self.value / self.volume)
+    }
+}
+
+/// On-balance volume, starts from zero.
+#[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
+pub struct Obv {
+    prev_close: Option<f64>,
+    value: f64,
+}
+
+#[allow(dead_code)]
+impl Obv {
+    pub fn new() -> Obv {
+        Obv::default()
+    }
+}
+
+impl Indicator for Obv {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        if let Some(prev) = self.prev_close.replace(bar.close) {
+            if bar.close > prev {
+                self.value += bar.volume;
+            } else if bar.close < prev {
+                self.value -= bar.volume;
+            }
+        }
+        Some(self.value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub struct StochasticValue {
+    pub k: f64,
+    pub d: f64,
+}
+
+/// Fast stochastic: %K over `k` bars and %D as SMA of `d` last %K, 0..100.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Stochastic {
+    bars: VecDeque<Bar>,
+    k: usize,
+    d: Sma,
+}
+
+#[allow(dead_code)]
+impl Stochastic {
+    pub fn new(k: usize, d: usize) -> Stochastic {
+        Stochastic { bars: VecDeque::with_capacity(k + 1), k, d: Sma::new(d) }
+    }
+}
+
+impl Indicator for Stochastic {
+    type Output = StochasticValue;
+    fn update(&mut self, bar: &Bar) -> Option<StochasticValue> {
+        self.bars.push_back(*bar);
+        if self.bars.len() > self.k {
+            self.bars.pop_front();
+        }
+        if self.bars.len() < self.k {
+            return None;
+        }
+        let high = self.bars.iter().map(|b| b.high).fold(f64::MIN, f64::max);
+        let low = self.bars.iter().map(|b| b.low).fold(f64::MAX, f64::min);
+        // a flat market is right in the middle
+        let k = if high > low { 100.0 * (bar.close - low) / (high - low) } else { 50.0 };
+        let d = self.d.next(k)?;
+        Some(StochasticValue { k, d })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdxValue {
+    pub adx: f64,
+    pub plus_di: f64,
+    pub minus_di: f64,
+}
+
+/// Running sum with Wilder's smoothing: the sum of the first `n` values,
+/// then `prev - prev / n + x`.
+#[derive(Debug, Clone)]
+struct WilderSum {
+    n: usize,
+    count: usize,
+    value: f64,
+}
+
+impl WilderSum {
+    fn new(n: usize) -> WilderSum {
+        WilderSum { n, count: 0, value: 0.0 }
+    }
+
+    fn next(&mut self, value: f64) -> Option<f64> {
+        self.value = if self.count < self.n {
+            self.value + value
+        } else {
+            self.value - self.value / self.n as f64 + value
+        };
+        self.count += 1;
+        (self.count >= self.n).then_some(self.value)
+    }
+}
+
+/// Average directional index with the directional indicators, Wilder's way.
+/// The first value comes on the `2n`-th bar.
+#[derive(Debug, Clone)]
+pub struct Adx {
+    prev: Option<Bar>,
+    range: WilderSum,
+    plus: WilderSum,
+    minus: WilderSum,
+    adx: Wilder,
+}
+
+impl Adx {
+    pub fn new(n: usize) -> Adx {
+        Adx {
+            prev: None,
+            range: WilderSum::new(n),
+            plus: WilderSum::new(n),
+            minus: WilderSum::new(n),
+            adx: Wilder::new(n),
+        }
+    }
+}
+
+impl Indicator for Adx {
+    type Output = AdxValue;
+    fn update(&mut self, bar: &Bar) -> Option<AdxValue> {
+        let prev = self.prev.replace(*bar)?;
+        let up = bar.high - prev.high;
+        let down = prev.low - bar.low;
+        let plus_dm = if up > down && up > 0.0 { up } else { 0.0 };
+        let minus_dm = if down > up && down > 0.0 { down } else { 0.0 };
+        let range = self.range.next(true_range(bar, Some(prev.close)));
+        let (plus, minus) = (self.plus.next(plus_dm), self.minus.next(minus_dm));
+        let (range, plus, minus) = (range?, plus?, minus?);
+        let (plus_di, minus_di) = if range > 0.0 {
+            (100.0 * plus / range, 100.0 * minus / range)
+        } else {
+            (0.0, 0.0)
+        };
+        let sum = plus_di + minus_di;
+        let dx = if sum > 0.0 { 100.0 * (plus_di - minus_di).abs() / sum } else { 0.0 };
+        let adx = self.adx.next(dx)?;
+        Some(AdxValue { adx, plus_di, minus_di })
+    }
+}
+
+/// The price indicators over the close of the candle.
+macro_rules! on_close {
+    ($($name:ident => $output:ty),*) => {$(
+        impl Indicator for $name {
+            type Output = $output;
+            fn update(&mut self, bar: &Bar) -> Option<$output> {
+                self.next(bar.close)
+            }
+        }
+    )*};
+}
+
+on_close!(Sma => f64, Ema => f64, Wma => f64, Rsi => f64, Macd => MacdValue, Bollinger => Band);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars() -> Vec<Bar> {
+        (0..40)
+            .map(|i| {
+                let close = 100.0 + ((i * 7) % 11) as f64 - ((i * 3) % 5) as f64 + i as f64 * 0.25;
+                Bar {
+                    open: close - 0.5,
+                    high: close + 1.0 + (i % 3) as f64 * 0.5,
+                    low: close - 1.0 - (i % 4) as f64 * 0.25,
+                    close,
+                    volume: (1000 + (i * 37) % 500) as f64,
+                    // one day, 10:00 Moscow
+                    time: 1_675_926_000 + i * 60,
+                }
+            })
+            .collect()
+    }
+
+    /// All the values of the indicator over `bars`, `None` included.
+    fn run<I: Indicator>(mut indicator: I, bars: &[Bar]) -> Vec<Option<I::Output>> {
+        bars.iter().map(|bar| indicator.update(bar)).collect()
+    }
+
+    fn close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    /// Same values at the same bars.
+    fn same<T>(values: &[Option<T>], expected: &[Option<T>], field: impl Fn(&T) -> f64) {
+        assert_eq!(values.len(), expected.len());
+        for (i, (value, expected)) in values.iter().zip(expected).enumerate() {
+            match (value, expected) {
+                (Some(value), Some(expected)) => close(field(value), field(expected)),
+                (None, None) => {},
+                _ => panic!("bar {}: {} != {}", i, value.is_some(), expected.is_some()),
+            }
+        }
+    }
+
+    /// The textbook formulas over the whole series, bar by bar, without the running state.
+    mod textbook {
+        use super::Bar;
+
+        fn mean(values: &[f64]) -> f64 {
+            values.iter().sum::<f64>() / values.len() as f64
+        }
+
+        /// The last `n` values before `i` and at it.
+        fn last(values: &[f64], n: usize, i: usize) -> Option<&[f64]> {
+            (i + 1 >= n).then(|| &values[i + 1 - n..=i])
+        }
+
+        pub fn sma(values: &[f64], n: usize) -> Vec<Option<f64>> {
+            (0..values.len()).map(|i| last(values, n, i).map(mean)).collect()
+        }
+
+        pub fn wma(values: &[f64], n: usize) -> Vec<Option<f64>> {
+            (0..values.len())
+                .map(|i| last(values, n, i).map(|window| {
+                    let weighted: f64 = window.iter().zip(1..).map(|(v, w)| v * w as f64).sum();
+                    weighted / (1..=n).sum::<usize>() as f64
+                }))
+                .collect()
+        }
+
+        /// The SMA of the first `n` values, then `prev + alpha * (x - prev)`.
+        fn smoothed(values: &[f64], n: usize, alpha: f64) -> Vec<Option<f64>> {
+            let mut result = vec![None; values.len()];
+            if values.len() < n {
+                return result;
+            }
+            let mut value = mean(&values[..n]);
+            result[n - 1] = Some(value);
+            for i in n..values.len() {
+                value += alpha * (values[i] - value);
+                result[i] = Some(value);
+            }
+            result
+        }
+
+        pub fn ema(values: &[f64], n: usize) -> Vec<Option<f64>> {
+            smoothed(values, n, 2.0 / (n as f64 + 1.0))
+        }
+
+        pub fn wilder(values: &[f64], n: usize) -> Vec<Option<f64>> {
+            smoothed(values, n, 1.0 / n as f64)
+        }
+
+        /// Of the defined values only, put back at their bars.
+        fn skipping(values: &[Option<f64>], f: impl Fn(&[f64]) -> Vec<Option<f64>>) -> Vec<Option<f64>> {
+            let skip = values.iter().take_while(|v| v.is_none()).count();
+            let defined: Vec<f64> = values[skip..].iter().map(|v| v.unwrap()).collect();
+            let mut result = vec![None; skip];
+            result.extend(f(&defined));
+            result
+        }
+
+        pub fn rsi(closes: &[f64], n: usize) -> Vec<Option<f64>> {
+            let changes: Vec<f64> = closes.windows(2).map(|w| w[1] - w[0]).collect();
+            let gains = wilder(&changes.iter().map(|c| c.max(0.0)).collect::<Vec<_>>(), n);
+            let losses = wilder(&changes.iter().map(|c| (-c).max(0.0)).collect::<Vec<_>>(), n);
+            let mut result = vec![None];
+            result.extend(gains.iter().zip(&losses).map(|(gain, loss)| {
+                let (gain, loss) = (gain.as_ref()?, loss.as_ref()?);
+                Some(if *loss == 0.0 { 100.0 } else { 100.0 * gain / (gain + loss) })
+            }));
+            result
+        }
+
+        /// (macd, signal)
+        pub fn macd(closes: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<Option<(f64, f64)>> {
+            let line: Vec<Option<f64>> = ema(closes, fast).iter().zip(ema(closes, slow))
+                .map(|(fast, slow)| Some(fast.as_ref()? - slow?))
+                .collect();
+            let signals = skipping(&line, |line| ema(line, signal));
+            line.iter().zip(signals).map(|(macd, signal)| Some((macd.as_ref().copied()?, signal?))).collect()
+        }
+
+        /// (lower, middle, upper)
+        pub fn bollinger(closes: &[f64], n: usize, k: f64) -> Vec<Option<(f64, f64, f64)>> {
+            (0..closes.len())
+                .map(|i| last(closes, n, i).map(|window| {
+                    let middle = mean(window);
+                    let deviation = mean(&window.iter().map(|v| (v - middle).powi(2)).collect::<Vec<_>>()).sqrt();
+                    (middle - k * deviation, middle, middle + k * deviation)
+                }))
+                .collect()
+        }
+
+        fn true_ranges(bars: &[Bar]) -> Vec<f64> {
+            bars.iter().enumerate()
+                .map(|(i, bar)| match i {
+                    0 => bar.high - bar.low,
+                    _ => {
+                        let prev = bars[i - 1].close;
+                        (bar.high - bar.low).max((bar.high - prev).abs()).max((bar.low - prev).abs())
+                    },
+                })
+                .collect()
+        }
+
+        pub fn atr(bars: &[Bar], n: usize) -> Vec<Option<f64>> {
+            wilder(&true_ranges(bars), n)
+        }
+
+        /// Cumulative since the start of the Moscow day, UTC+3.
+        pub fn vwap(bars: &[Bar]) -> Vec<Option<f64>> {
+            let day = |bar: &Bar| (bar.time + 3 * 3600).div_euclid(86_400);
+            (0..bars.len())
+                .map(|i| {
+                    let today = bars[..=i].iter().filter(|bar| day(bar) == day(&bars[i]));
+                    let value: f64 = today.clone().map(|b| (b.high + b.low + b.close) / 3.0 * b.volume).sum();
+                    let volume: f64 = today.map(|b| b.volume).sum();
+                    Some(value / volume)
+                })
+                .collect()
+        }
+
+        pub fn obv(bars: &[Bar]) -> Vec<Option<f64>> {
+            (0..bars.len())
+                .map(|i| Some((1..=i)
+                    .map(|j| (bars[j].close - bars[j - 1].close).signum() * bars[j].volume)
+                    .sum()))
+                .collect()
+        }
+
+        /// (%K, %D)
+        pub fn stochastic(bars: &[Bar], k: usize, d: usize) -> Vec<Option<(f64, f64)>> {
+            let ks: Vec<Option<f64>> = (0..bars.len())
+                .map(|i| {
+                    let window = &bars[(i + 1).checked_sub(k)?..=i];
+                    let high = window.iter().map(|b| b.high).fold(f64::MIN, f64::max);
+                    let low = window.iter().map(|b| b.low).fold(f64::MAX, f64::min);
+                    Some(100.0 * (bars[i].close - low) / (high - low))
+                })
+                .collect();
+            let ds = skipping(&ks, |ks| sma(ks, d));
+            ks.iter().zip(ds).map(|(k, d)| Some((k.as_ref().copied()?, d?))).collect()
+        }
+
+        /// (adx, +DI, -DI): the sums of the first `n` moves smoothed as `sum - sum / n + x`,
+        /// the ADX is Wilder's average of DX.
+        pub fn adx(bars: &[Bar], n: usize) -> Vec<Option<(f64, f64, f64)>> {
+            let ranges = true_ranges(bars);
+            let moves: Vec<(f64, f64, f64)> = (1..bars.len())
+                .map(|i| {
+                    let up = bars[i].high - bars[i - 1].high;
+                    let down = bars[i - 1].low - bars[i].low;
+                    let plus = if up > down && up > 0.0 { up } else { 0.0 };
+                    let minus = if down > up && down > 0.0 { down } else { 0.0 };
+                    (ranges[i], plus, minus)
+                })
+                .collect();
+            let sums = |field: fn(&(f64, f64, f64)) -> f64| -> Vec<Option<f64>> {
+                let values: Vec<f64> = moves.iter().map(field).collect();
+                wilder(&values, n).into_iter().map(|v| v.map(|v| v * n as f64)).collect()
+            };
+            let (range, plus, minus) = (sums(|m| m.0), sums(|m| m.1), sums(|m| m.2));
+            let di: Vec<Option<(f64, f64)>> = (0..moves.len())
+                .map(|i| Some((100.0 * plus[i]? / range[i]?, 100.0 * minus[i]? / range[i]?)))
+                .collect();
+            let dx: Vec<Option<f64>> = di.iter()
+                .map(|di| di.map(|(plus, minus)| 100.0 * (plus - minus).abs() / (plus + minus)))
+                .collect();
+            let adx = skipping(&dx, |dx| wilder(dx, n));
+            let mut result = vec![None];
+            result.extend(adx.iter().zip(di).map(|(adx, di)| Some((adx.as_ref().copied()?, di?.0, di?.1))));
+            result
+        }
+    }
+
+    /// The worked examples of StockCharts ChartSchool, the tables are rounded to cents.
+    #[test]
+    fn published() {
+        let near = |values: Vec<f64>, expected: &[f64], tolerance: f64| {
+            assert_eq!(values.len(), expected.len());
+            for (value, expected) in values.iter().zip(expected) {
+                assert!((value - expected).abs() <= tolerance, "{} != {}", value, expected);
+            }
+        };
+        let prices = [
+            22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39, 22.38, 22.61,
+            23.36, 24.05, 23.75, 23.83, 23.95, 23.63, 23.82, 23.87, 23.65, 23.19, 23.10, 23.33, 22.68, 23.10,
+            22.40, 22.17,
+        ];
+        let mut sma = Sma::new(10);
+        near(prices.iter().filter_map(|&p| sma.next(p)).collect(), &[
+            22.22, 22.21, 22.23, 22.26, 22.31, 22.42, 22.61, 22.77, 22.91, 23.08, 23.21, 23.38, 23.53, 23.65,
+            23.71, 23.69, 23.61, 23.51, 23.43, 23.28, 23.13,
+        ], 0.01);
+        let mut ema = Ema::new(10);
+        near(prices.iter().filter_map(|&p| ema.next(p)).collect(), &[
+            22.22, 22.21, 22.24, 22.27, 22.33, 22.52, 22.80, 22.97, 23.13, 23.28, 23.34, 23.43, 23.51, 23.54,
+            23.47, 23.40, 23.39, 23.26, 23.23, 23.08, 22.92,
+        ], 0.01);
+
+        // Wilder's RSI, 14 days
+        let closes = [
+            44.3389, 44.0902, 44.1497, 43.6124, 44.3278, 44.8264, 45.0955, 45.4245, 45.8433, 46.0826, 45.8931,
+            46.0328, 45.6140, 46.2820, 46.2820, 46.0028, 46.0328, 46.4116, 46.2222, 45.6439, 46.2122, 46.2521,
+            45.7137, 46.4515, 45.7835, 45.3548, 44.0288, 44.1783, 44.2181, 44.5672, 43.4205, 42.6628, 43.1314,
+        ];
+        let mut rsi = Rsi::new(14);
+        near(closes.iter().filter_map(|&p| rsi.next(p)).collect(), &[
+            70.53, 66.32, 66.55, 69.41, 66.36, 57.97, 62.93, 63.26, 56.06, 62.38, 54.71, 50.42, 39.99, 41.46,
+            41.87, 45.46, 37.30, 33.08, 37.77,
+        ], 0.005);
+    }
+    #[test]
+    fn moving_averages() {
+        let bars = bars();
+        let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        same(&run(Sma::new(5), &bars), &textbook::sma(&closes, 5), |v| *v);
+        same(&run(Ema::new(5), &bars), &textbook::ema(&closes, 5), |v| *v);
+        same(&run(Wma::new(5), &bars), &textbook::wma(&closes, 5), |v| *v);
+
+        // by hand
+        let mut sma = Sma::new(3);
+        assert_eq!([1.0, 2.0, 3.0, 4.0].map(|v| sma.next(v)), [None, None, Some(2.0), Some(3.0)]);
+        let mut ema = Ema::new(3);
+        assert_eq!([1.0, 2.0, 3.0, 4.0, 5.0].map(|v| ema.next(v)), [None, None, Some(2.0), Some(3.0), Some(4.0)]);
+        let mut wma = Wma::new(3);
+        assert_eq!([1.0, 2.0, 4.0].map(|v| wma.next(v))[2], Some((1.0 + 4.0 + 12.0) / 6.0));
+    }
+    #[test]
+    fn oscillators() {
+        let bars = bars();
+        let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        same(&run(Rsi::new(14), &bars), &textbook::rsi(&closes, 14), |v| *v);
+        let macd: Vec<_> = run(Macd::new(12, 26, 9), &bars).iter()
+            .map(|v| v.map(|v| (v.macd, v.signal, v.histogram)))
+            .collect();
+        let expected: Vec<_> = textbook::macd(&closes, 12, 26, 9).iter()
+            .map(|v| v.map(|(macd, signal)| (macd, signal, macd - signal)))
+            .collect();
+        assert_eq!(macd.iter().position(Option::is_some), Some(33));
+        same(&macd, &expected, |v| v.0);
+        same(&macd, &expected, |v| v.1);
+        same(&macd, &expected, |v| v.2);
+        let stochastic: Vec<_> = run(Stochastic::new(14, 3), &bars).iter().map(|v| v.map(|v| (v.k, v.d))).collect();
+        let expected = textbook::stochastic(&bars, 14, 3);
+        same(&stochastic, &expected, |v| v.0);
+        same(&stochastic, &expected, |v| v.1);
+
+        // by hand: +1, -1, +1, the averages of 2 are (0.5, 0.5), then (0.75, 0.25)
+        let mut rsi = Rsi::new(2);
+        assert_eq!([1.0, 2.0, 1.0, 2.0].map(|v| rsi.next(v)), [None, None, Some(50.0), Some(75.0)]);
+    }
+    #[test]
+    fn volatility() {
+        let bars = bars();
+        let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        let bands: Vec<_> = run(Bollinger::new(20, 2.0), &bars).iter()
+            .map(|v| v.map(|v| (v.lower, v.middle, v.upper)))
+            .collect();
+        let expected = textbook::bollinger(&closes, 20, 2.0);
+        same(&bands, &expected, |v| v.0);
+        same(&bands, &expected, |v| v.1);
+        same(&bands, &expected, |v| v.2);
+        same(&run(Atr::new(14), &bars), &textbook::atr(&bars, 14), |v| *v);
+
+        // by hand: 2, 4 and 6 around 4
+        let mut bands = Bollinger::new(3, 1.0);
+        let band = [2.0, 4.0, 6.0].map(|v| bands.next(v))[2].unwrap();
+        close(band.upper, 4.0 + (8.0f64 / 3.0).sqrt());
+    }
+    #[test]
+    fn volume() {
+        let bars = bars();
+        same(&run(Vwap::new(), &bars), &textbook::vwap(&bars), |v| *v);
+        same(&run(Obv::new(), &bars), &textbook::obv(&bars), |v| *v);
+
+        // the next day starts over
+        let mut day: Vec<Bar> = bars[..10].to_vec();
+        for bar in &mut day[5..] {
+            bar.time += 86_400;
+        }
+        same(&run(Vwap::new(), &day), &textbook::vwap(&day), |v| *v);
+        let next = day[5];
+        close(run(Vwap::new(), &day)[5].unwrap(), (next.high + next.low + next.close) / 3.0);
+    }
+    #[test]
+    fn trend() {
+        let bars = bars();
+        let adx: Vec<_> = run(Adx::new(14), &bars).iter().map(|v| v.map(|v| (v.adx, v.plus_di, v.minus_di))).collect();
+        let expected = textbook::adx(&bars, 14);
+        // the 2n-th bar
+        assert_eq!(adx.iter().position(Option::is_some), Some(27));
+        same(&adx, &expected, |v| v.0);
+        same(&adx, &expected, |v| v.1);
+        same(&adx, &expected, |v| v.2);
+    }
+}
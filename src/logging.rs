@@ -0,0 +1,125 @@
+use std::future::Future;
+use tonic::{metadata::MetadataMap, Response, Status};
+use tracing::{debug, field::Empty, info_span, warn, Instrument, Span};
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{fmt, EnvFilter};
+
+use crate::metrics::timed;
+
+
+pub const TRACKING_ID: &str = "x-tracking-id";
+
+/// Sets up the global subscriber, `log` records (teloxide, hyper) go there too.
+/// - `RUST_LOG`: levels, `info` by default
+/// - `LOG_FORMAT=json`: one JSON object per line
+/// - `LOG_DIR`: write to `LOG_DIR/bot.log.<date>` rotated by `LOG_ROTATION`
+///   (`daily` by default, `hourly` or `never`) instead of stderr
+///
+/// Keep the guard until the end of `main`, it flushes the file.
+pub fn init() -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let json = std::env::var("LOG_FORMAT").map(|f| f == "json").unwrap_or(false);
+    let builder = fmt().with_env_filter(filter);
+
+    let (writer, guard) = match std::env::var("LOG_DIR") {
+        Ok(dir) => {
+            let appender = match std::env::var("LOG_ROTATION").as_deref() {
+                Ok("hourly") => rolling::hourly(dir, "bot.log"),
+                Ok("never") => rolling::never(dir, "bot.log"),
+                _ => rolling::daily(dir, "bot.log"),
+            };
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(writer), Some(guard))
+        },
+        Err(_) => (None, None),
+    };
+    match (writer, json) {
+        (Some(writer), true) => builder.json().with_writer(writer).init(),
+        (Some(writer), false) => builder.with_ansi(false).with_writer(writer).init(),
+        (None, true) => builder.json().init(),
+        (None, false) => builder.init(),
+    }
+    guard
+}
+
+/// Called by the interceptor: the id we send goes to the current `grpc` span.
+pub fn record_tracking_id(id: &str) {
+    Span::current().record("tracking_id", id);
+}
+
+fn broker_tracking_id(metadata: &MetadataMap) -> &str {
+    metadata.get(TRACKING_ID).and_then(|id| id.to_str().ok()).unwrap_or("")
+}
+
+/// Runs the call in a `grpc` span with both tracking ids, counts it in the metrics.
+pub async fn rpc<T, F>(name: &'static str, call: F) -> Result<Response<T>, Status>
+    where F: Future<Output = Result<Response<T>, Status>> {
+    let span = info_span!("grpc", rpc = name, tracking_id = Empty, broker_tracking_id = Empty);
+    async move {
+        let result = timed(name, call).await;
+        let span = Span::current();
+        match &result {
+            Ok(response) => {
+                span.record("broker_tracking_id", broker_tracking_id(response.metadata()));
+                debug!("ok");
+            },
+            Err(status) => {
+                span.record("broker_tracking_id", broker_tracking_id(status.metadata()));
+                warn!(code = ?status.code(), message = status.message(), "failed");
+            },
+        }
+        result
+    }.instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io, sync::{Arc, Mutex}};
+    use tonic::{service::Interceptor, Code, Request};
+    use crate::DefaultInterceptor;
+    use crate::credentials::Secret;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn tracking_ids() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = fmt()
+            .json()
+            .with_writer(move || writer.clone())
+            .finish();
+        let _default = tracing::subscriber::set_default(subscriber);
+
+        let mut interceptor = DefaultInterceptor { token: Secret::new("t.token".to_string()) };
+        let mut sent = String::new();
+        let _ = rpc("PostOrder", async {
+            let request = interceptor.call(Request::new(())).unwrap();
+            sent = request.metadata().get(TRACKING_ID).unwrap().to_str().unwrap().to_string();
+            let mut status = Status::new(Code::InvalidArgument, "30079");
+            status.metadata_mut().insert(TRACKING_ID, "broker-id".parse().unwrap());
+            Err::<Response<()>, _>(status)
+        }).await;
+
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["fields"]["code"], "InvalidArgument");
+        assert_eq!(line["span"]["rpc"], "PostOrder");
+        assert!(uuid::Uuid::parse_str(&sent).is_ok());
+        // the id in the header is the one logged
+        assert_eq!(line["span"]["tracking_id"], sent.as_str());
+        assert_eq!(line["span"]["broker_tracking_id"], "broker-id");
+    }
+}
@@ -1,34 +1,59 @@
-use tonic::{Status, service::Interceptor, transport::{Channel, ClientTlsConfig}, Code};
-use tcs::{
-    {PositionsRequest, Quotation},
-    operations_service_client::OperationsServiceClient
-};
-use crate::bot::{Bot, Statistics, DayStat, ProfitStat};
-use tokio::{join, try_join};
-
-#[cfg(debug_assertions)]
-use tcs::{
-    {GetAccountsRequest, InstrumentRequest, GetTradingStatusRequest, GetOrderStateRequest,
-     PostOrderRequest, TradingSchedulesRequest, CandleInterval,
-     GetCandlesRequest
-    },
-    orders_service_client::OrdersServiceClient,
-    market_data_service_client::MarketDataServiceClient,
-    users_service_client::UsersServiceClient,
-    instruments_service_client::InstrumentsServiceClient,
-
-};
 
+use clap::Parser;
+use tonic::{Status, metadata::MetadataValue, service::Interceptor};
+use crate::bot::{Bot, Statistics, DayStat};
+use crate::error::Error;
+use crate::grpc::Broker;
+use crate::connection::{Connection, ConnectionSettings};
+use crate::credentials::{Credentials, Secret};
+use crate::accounts::AccountConfig;
+use crate::balance::Wallet;
+use crate::shutdown::{ShutdownPolicy, StopReason};
+use crate::access::Access;
+use crate::notify::{Notifier, NotifySettings};
+use crate::control::{CliControl, Control, NoopControl};
+use crate::tg::TelegramControl;
+use crate::dashboard::Dashboard;
+use tokio::sync::{mpsc, watch};
+use std::time::SystemTime;
+use chrono::{DateTime, NaiveDate, Utc};
+use crate::cli::{Cli, Command};
+use chrono_tz::Europe::Moscow;
 
+#[allow(clippy::all)]
 pub mod tcs;
 mod bot;
 pub mod tg;
 pub mod strategies;
+mod shutdown;
+mod access;
+mod notify;
+mod chart;
+mod control;
+mod dashboard;
+mod metrics;
+mod logging;
+mod error;
+mod grpc;
+mod connection;
+mod credentials;
+mod accounts;
+mod balance;
+mod portfolio;
+mod candles;
+mod history;
+mod replay;
+mod optimize;
+mod analytics;
+mod report;
+mod cli;
+mod orderbook;
+mod indicators;
 
 
 #[derive(Debug)]
 pub struct DefaultInterceptor {
-    token: String,
+    token: Secret,
 }
 impl Clone for DefaultInterceptor {
     fn clone(&self) -> DefaultInterceptor {
@@ -38,13 +63,16 @@ impl Clone for DefaultInterceptor {
 impl Interceptor for DefaultInterceptor {
     fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
         let mut req = request;
+        let mut authorization: MetadataValue<_> =
+            format!("bearer {}", self.token.expose()).parse().unwrap();
+        // keeps the token out of `Debug` of the request
+        authorization.set_sensitive(true);
+        req.metadata_mut().append("authorization", authorization);
+        let tracking_id = uuid::Uuid::new_v4().to_string();
+        logging::record_tracking_id(&tracking_id);
         req.metadata_mut().append(
-            "authorization",
-            format!("bearer {}", self.token).parse().unwrap(),
-        );
-        req.metadata_mut().append(
-            "x-tracking-id",
-            uuid::Uuid::new_v4().to_string().parse().unwrap(),
+            logging::TRACKING_ID,
+            tracking_id.parse().unwrap(),
         );
         req.metadata_mut()
             .append("x-app-name", "bo1oto".parse().unwrap());
@@ -53,304 +81,279 @@ impl Interceptor for DefaultInterceptor {
     }
 }
 
-static mut ACCOUNT_ID: String = String::new();
-
-
 #[tokio::main]
-async fn main() -> Result<(), Status> {
-    unsafe { ACCOUNT_ID = std::env::var("T_ACCOUNT_ID").unwrap() }
-    create_env();
-    let inter = DefaultInterceptor { token: std::env::var("TOKEN_BOT").unwrap() };
-    let channel = Channel::from_static("https://invest-public-api.tinkoff.ru:443/")
-        .tls_config(ClientTlsConfig::new()).unwrap()
-        .connect()
-        .await.unwrap();
-
-    {
-        //get_account(channel.clone()).await?;
-        //get_status(channel.clone()).await?;
-        //get_instrument(channel, inter).await?;
-        //test_get_order(channel.clone()).await?;
-        //test_order(channel.clone()).await?;
-        //get_schedule(channel.clone()).await?;
-        //test_candles(channel.clone()).await?;
-        //return Ok(());
-    }// to check something
-
-    loop {
-        let (mut disp, rx, tg_bot) = tg::start().await;
-        let scalp = strategies::scalp::Scalp::new();
-
-        let bot = Bot::new(channel.clone(),
-                               inter.clone(),
-                               get_money(channel.clone(), inter.clone()).await?,
-                               Box::new(scalp),
-                               tg_bot
-        );
-        let f = || async move {
-            disp.dispatch().await;
-            println!("f ended");
-            if true {
-                Err(Status::new(Code::Unknown, "Tg crash"))
-            } else {
-                Ok(())
-            }
-        };
-        //let tg_t = tokio::spawn(f());
-        // let bb = bot.handler(rx);
+async fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    let _log_guard = logging::init();
+    let command = match cli.command.unwrap_or(Command::Run) {
+        Command::Backtest { strategy, from, to, latency, money } =>
+            return replay_market(&strategy, from, to, latency, money).await,
+        Command::Optimize { strategy, from, to, ranges, search, walk, workers, latency, money } =>
+            return optimize_strategy(&strategy, from, to, ranges, search, walk, workers, latency, money),
+        Command::Report { from, to, account, dir } => return export_report(&from, &to, account, dir),
+        Command::Stats { account } => return cli::stats(&account, cli.json),
+        command => command,
+    };
+    let credentials = Credentials::load()?;
+    match command {
+        Command::Run => {},
+        Command::SealCredentials { path } => return seal_credentials(&credentials, &path),
+        command => {
+            let channel = ConnectionSettings::from_env().channel()?;
+            let broker = Broker::new(channel, &credentials, Connection::new());
+            return match command {
+                Command::Download { uid, interval, from, to, format } =>
+                    download_history(&broker, &uid, interval, from, to, format).await,
+                Command::Record { uid, depth, period } => record_market(&broker, &uid, depth, period).await,
+                command => cli::request(command, &broker, credentials.account_id.as_deref(), cli.json).await,
+            };
+        },
+    }
+    let configs = AccountConfig::load(credentials.account_id.as_deref())?;
+    create_env(credentials.chat_id, &configs.iter().map(|c| c.name.clone()).collect::<Vec<_>>())?;
+    let channel = ConnectionSettings::from_env().channel()?;
+    let shutdown_policy = ShutdownPolicy::from_env()?;
 
-        let result = try_join!(bot.handler(rx), f());
-        println!("try_join ended");
+    let (stop_tx, stop_rx) = watch::channel(None);
+    tokio::spawn(async move {
+        shutdown::wait_signal().await;
+        let _ = stop_tx.send(Some(StopReason::Signal));
+    });
 
-        if let Err(err) = result {
-            if err.message().eq("Tg crash") {
-                println!("Something going wrong in tg_bot, but we will return soon!");
-            } else {
-                panic!("Something going wrong...\n{}", err)
-            }
-        } else if let Ok(_) = result {
-            break;
-        } else {
-        }
+    let (tx, rx) = mpsc::channel(10);
+    let controls = controls(&credentials)?;
+    let notify_settings = NotifySettings::load()?;
+    let notifier = Notifier::start(
+        &notify_settings,
+        notify::sinks(&notify_settings, controls.iter().flat_map(|c| c.sinks()).collect()));
+    for control in controls {
+        control::supervise(control, tx.clone());
     }
 
-    Ok(())
-}
+    let connection = Connection::new();
+    connection::notify(connection.subscribe(), notifier.clone());
+    let broker = Broker::new(channel.clone(), &credentials, connection);
+    connection::health_check(broker.clone());
 
-fn create_env() {
-    use std::{fs, time};
-    use chrono::{DateTime, Local};
+    let dashboard = Dashboard::new().with_broker(broker.clone());
+    if let Ok(addr) = std::env::var("DASHBOARD_ADDR") {
+        let addr = addr.parse().map_err(|_| Error::Config("DASHBOARD_ADDR must be like 127.0.0.1:8080".to_string()))?;
+        dashboard.serve(addr, credentials.dashboard_token.clone())?;
+    }
+    if let Ok(addr) = std::env::var("METRICS_ADDR") {
+        let addr = addr.parse().map_err(|_| Error::Config("METRICS_ADDR must be like 127.0.0.1:9090".to_string()))?;
+        metrics::serve(addr);
+    }
 
-    let default_json = {
-        let today = {
-            let now: DateTime<Local> = time::SystemTime::now().into();
-            now.date_naive().to_string()
-        };
-        let stat = Statistics {
-            bot_start_date: today,
-            trade_secs: 0,
-            turnover: 0,
-            profit: ProfitStat {
-                net: Quotation { units: 0, nano: 0 },
-                after_fees: Quotation { units: 0, nano: 0 },
-                after_tax: Quotation { units: 0, nano: 0 }
-            },
-            trades: Vec::new(),
-            trades_count: 0
-        };
-        serde_json::to_string(&stat).unwrap()
-    };
-    let default_json_today = {
-        let today = {
-            let now: DateTime<Local> = time::SystemTime::now().into();
-            now.date_naive().to_string()
-        };
-        let stat =  DayStat {
-            date: today,
-            turnover: 0,
-            profit: ProfitStat {
-                net: Quotation { units: 0, nano: 0 },
-                after_fees: Quotation { units: 0, nano: 0 },
-                after_tax: Quotation { units: 0, nano: 0 }
-            },
-            trades: Vec::new(),
-            trades_count: 0
+    let accounts = accounts::discover(&broker, configs).await?;
+    let several = accounts.len() > 1;
+    // the bots share the currency instruments, they are looked up once
+    let wallet = Wallet::new(broker.clone());
+    let receivers = control::fan_out(rx, accounts.len());
+    let mut bots = Vec::new();
+    for (account, rx) in accounts.into_iter().zip(receivers) {
+        let strategy = strategies::by_name(&account.strategy)
+            .ok_or_else(|| Error::Config(format!("unknown strategy {}", account.strategy)))?;
+        let notifier = if several {
+            notifier.for_account(if account.name.is_empty() { &account.id } else { &account.name })
+        } else {
+            notifier.clone()
         };
-        serde_json::to_string(&stat).unwrap()
-    };
-    match fs::read_dir(".\\add_info\\") {
-        Err(_) => {
-            fs::create_dir(".\\add_info\\").unwrap();
-            if cfg!(debug_assertions) {
-                fs::write(".\\add_info\\oid.txt", "10000").unwrap();
-            } else {
-                fs::write(".\\add_info\\oid.txt", "1000000").unwrap();
-            }
-            fs::write(".\\add_info\\stat.json", default_json).unwrap();
-            fs::write(".\\add_info\\today.json", default_json_today).unwrap();
-        }
-        Ok(_) => {
-            if let Err(_) = fs::read(".\\add_info\\oid.txt") {
-                if cfg!(debug_assertions) {
-                    fs::write(".\\add_info\\oid.txt", "10000").unwrap();
-                } else {
-                    fs::write(".\\add_info\\oid.txt", "1000000").unwrap();
-                }
-            }
-            if let Err(_) = fs::read(".\\add_info\\stat.json") {
-                fs::write(".\\add_info\\stat.json", default_json).unwrap();
-            }
-            if let Err(_) = fs::read(".\\add_info\\today.json") {
-                fs::write(".\\add_info\\today.json", default_json_today).unwrap();
-            }
+        let dashboard = dashboard.for_account(&account.name, &account.id);
+        let portfolio = portfolio::watch(broker.clone(), account.id.clone());
+        let mut bot = Bot::new(account, broker.clone(), wallet.clone(), portfolio, strategy, notifier, dashboard)?;
+        if let Some(policy) = shutdown_policy {
+            bot = bot.with_shutdown_policy(policy);
         }
+        bots.push(bot.handler(rx, stop_rx.clone()));
     }
+    // the bots stop together on a signal or `/stop`
+    futures::future::join_all(bots).await.into_iter().collect()
 }
 
-pub async fn get_money(channel: Channel, inter: DefaultInterceptor) -> Result<Quotation, Status> {
-    let req = PositionsRequest {
-        account_id: unsafe { ACCOUNT_ID.clone() },
-    };
-    let mut client =
-        OperationsServiceClient::with_interceptor(channel, inter);
-    let response = client.get_positions(req).await?;
-    let money = &response.get_ref().money;
-    for val in money {
-        if val.currency.eq("rub") {
-            return Ok(Quotation { units: val.units, nano: val.nano });
-        }
-    }
-    panic!("Can't take RUB money!")
+/// `CONTROL` is a comma separated list of `telegram` (default), `cli` and `none`.
+fn controls(credentials: &Credentials) -> Result<Vec<Box<dyn Control>>, Error> {
+    let names = std::env::var("CONTROL").unwrap_or_else(|_| String::from("telegram"));
+    names.split(',')
+        .map(|name| -> Result<Box<dyn Control>, Error> {
+            Ok(match name.trim() {
+                "telegram" => {
+                    let token = credentials.telegram_token.as_ref()
+                        .ok_or_else(|| Error::Config("no telegram token".to_string()))?;
+                    Box::new(TelegramControl::new(token, Access::load()?))
+                },
+                "cli" => Box::new(CliControl),
+                "none" => Box::new(NoopControl),
+                other => return Err(Error::Config(format!("unknown control {}", other))),
+            })
+        })
+        .collect()
 }
 
-#[allow(dead_code)]
-#[cfg(debug_assertions)]
-async fn get_schedule(channel: Channel) -> Result<(), Status> {
-    use prost_types::Timestamp;
-    use std::time::SystemTime;
-    use chrono::{TimeZone, Utc};
-
-    let inter = DefaultInterceptor {
-        token: String::from(std::env::var("TOKEN_BOT").unwrap())
-    };
-    let mut client =
-        InstrumentsServiceClient::with_interceptor(channel, inter);
-    let x: SystemTime = Utc.with_ymd_and_hms(2023, 2, 1, 9, 0, 0).unwrap().into();
-    let from = Timestamp::from(x);
-    let x: SystemTime = Utc.with_ymd_and_hms(2023, 2, 2, 9, 0, 0).unwrap().into();
-    let to = Timestamp::from(x);
-    let req = TradingSchedulesRequest {
-        from: Some(from),
-        to: Some(to),
-        exchange: "".to_string(),
-    };
-
-    let response = client.trading_schedules(req).await?;
-    println!("{:#?}", response.into_inner());
+/// Writes the loaded credentials to an encrypted keystore.
+fn seal_credentials(credentials: &Credentials, path: &std::path::Path) -> Result<(), Error> {
+    let password = Secret::new(Error::env("KEYSTORE_PASSWORD")?);
+    std::fs::write(path, credentials.seal(&password)?)?;
+    println!("Credentials are saved to {}", path.display());
     Ok(())
 }
-#[allow(dead_code)]
-#[cfg(debug_assertions)]
-async fn get_instrument(channel: Channel) -> Result<(), Status> {
-    let inter = DefaultInterceptor {
-        token: String::from(std::env::var("TOKEN_BOT").unwrap())
-    };
-    let mut client =
-        InstrumentsServiceClient::with_interceptor(channel, inter);
-    let req = InstrumentRequest {
-        //instrument_status: i32::from(InstrumentType::Share),
-        id_type: 2,
-        class_code: "TQBR".to_string(),
-        id: "IRAO".to_string(),
-    };
 
-    let response = client.get_instrument_by(req).await?;
-    println!("{:#?}", response.into_inner());
+async fn download_history(broker: &Broker, uid: &str, interval: tcs::CandleInterval, from: NaiveDate,
+                          to: NaiveDate, format: history::Format) -> Result<(), Error> {
+    let cache = history::Cache::new(history::HISTORY_PATH, format);
+    let result = history::download(broker, &cache, uid, interval, from, to).await?;
+    println!("{} candles in {} files are saved to {}, {} files were there already",
+             result.candles, result.files, history::HISTORY_PATH, result.skipped);
     Ok(())
 }
-#[allow(dead_code)]
-#[cfg(debug_assertions)]
-async fn get_status(channel: Channel) -> Result<(), Status> {
-    let inter = DefaultInterceptor {
-        token: String::from(std::env::var("TOKEN_BOT").unwrap())
-    };
-    let mut client =
-        MarketDataServiceClient::with_interceptor(channel, inter);
-    let req = GetTradingStatusRequest {
-        figi: "BBG000000001".to_string(),// IRAO figi: "BBG004S68473"
-        instrument_id: "e2d0dbac-d354-4c36-a5ed-e5aae42ffc76".to_string(),// IRAO uid: "427f9bcc-2cab-4561-bf94-942d4261fbb7"
-    };
 
-    let response = client.get_trading_status(req).await?;
-    println!("{:#?}", response.into_inner());
-    Ok(())
+async fn record_market(broker: &Broker, uid: &str, depth: i32, period: u64) -> Result<(), Error> {
+    println!("Recording {} to {}, Ctrl+C to stop", uid, history::HISTORY_PATH);
+    history::record(broker, std::path::Path::new(history::HISTORY_PATH), uid, depth,
+                    std::time::Duration::from_millis(period), shutdown::wait_signal()).await
 }
-#[allow(dead_code)]
-#[cfg(debug_assertions)]
-async fn get_account(channel: Channel) -> Result<(), Status> {
-    let inter = DefaultInterceptor {
-        token: String::from(std::env::var("TOKEN_BOT").unwrap())
-    };
-    let mut client =
-        UsersServiceClient::with_interceptor(channel, inter);
-    let req = GetAccountsRequest { };
 
-    let response = client.get_accounts(req).await?;
-    println!("{:#?}", response.into_inner());
+async fn replay_market(name: &str, from: NaiveDate, to: NaiveDate, latency: u64, money: i64)
+    -> Result<(), Error> {
+    let strategy = strategies::by_name(name)
+        .ok_or_else(|| Error::Config(format!("unknown strategy {}", name)))?;
+    let (tape, params) = load_tape(strategy.as_ref(), from, to, latency, money)?;
+    let dir = std::path::Path::new(replay::DIR).join(name);
+    let report = replay::run(&dir, tape, params, strategy).await?;
+    println!("{}", report);
+    println!("The statistics are in {}", dir.display());
     Ok(())
 }
-#[allow(dead_code)]
-#[cfg(debug_assertions)]
-async fn test_get_order(channel: Channel) -> Result<(), Status> {
-    let inter = DefaultInterceptor {
-        token: String::from(std::env::var("TOKEN_BOT").unwrap())
-    };
-    let req = GetOrderStateRequest {
-        account_id: unsafe { ACCOUNT_ID.clone() },
-        order_id: "1000012".to_string()
+
+/// The recorded days of the strategy instrument and the replay settings.
+fn load_tape(strategy: &dyn strategies::Strategy, from: NaiveDate, to: NaiveDate, latency: u64, money: i64)
+    -> Result<(replay::Tape, replay::Params), Error> {
+    let settings = strategy.get_settings();
+    let tape = replay::Tape::load(std::path::Path::new(history::HISTORY_PATH), &settings.uid, from, to)?;
+    println!("{} order books and {} trades of {}", tape.books.len(), tape.trades.len(), settings.ticker);
+    let params = replay::Params {
+        latency: std::time::Duration::from_millis(latency),
+        fee_rate: settings.fee_rate.clone(),
+        money: tcs::Quotation { units: money, nano: 0 },
+        currency: settings.currency.clone(),
     };
-    let mut client = OrdersServiceClient::with_interceptor(
-        channel, inter);
-    let response = client.get_order_state(req).await?;
-    println!("{:?}", response.into_inner());
-    Ok(())
+    Ok((tape, params))
 }
-#[allow(dead_code)]
-#[cfg(debug_assertions)]
-async fn test_order(channel: Channel) -> Result<(), Status> {
-    let inter = DefaultInterceptor {
-        token: String::from(std::env::var("TOKEN_BOT").unwrap())
-    };
-    let req = PostOrderRequest {
-        figi: "BBG000000001".to_string(),
-        quantity: 1,
-        price: Some(Quotation { units: 5, nano: 94_0000000 }),
-        direction: 1,
-        account_id: unsafe { ACCOUNT_ID.clone() },
-        order_type: 1,// LIMIT
-        order_id: "7".to_string(),//"34177515807"
-        instrument_id: "e2d0dbac-d354-4c36-a5ed-e5aae42ffc76".to_string(),
-    };
-    let mut client = OrdersServiceClient::with_interceptor(
-        channel, inter);
-    let response = client.post_order(req).await?;
-    let order_id = response.get_ref().order_id.clone();
-    println!("{:?}", response.into_inner());
-    std::thread::sleep(std::time::Duration::new(5, 0));
-    let req = GetOrderStateRequest {
-        account_id: unsafe { ACCOUNT_ID.clone() },
-        order_id: order_id,
+
+#[allow(clippy::too_many_arguments)]
+fn optimize_strategy(name: &str, from: NaiveDate, to: NaiveDate, ranges: Vec<optimize::Range>,
+                     search: optimize::Search, walk: Option<(usize, usize)>, workers: Option<usize>,
+                     latency: u64, money: i64) -> Result<(), Error> {
+    let strategy = strategies::by_name(name)
+        .ok_or_else(|| Error::Config(format!("unknown strategy {}", name)))?;
+    let (tape, params) = load_tape(strategy.as_ref(), from, to, latency, money)?;
+    let mut optimizer = optimize::Optimizer::new(name, params, ranges, search);
+    if let Some(workers) = workers {
+        optimizer.workers = workers.max(1);
+    }
+    // the workers run their own runtimes
+    tokio::task::block_in_place(|| match walk {
+        None => {
+            let trials = optimizer.optimize(&tape)?;
+            println!("{} parameter sets, the best first:", trials.len());
+            for trial in trials.iter().take(20) {
+                println!("{}", trial);
+            }
+            Ok(())
+        },
+        Some((train, test)) => {
+            let folds = optimizer.walk_forward(&tape, train, test)?;
+            let mut results = Vec::new();
+            for fold in &folds {
+                println!("train {}..{}: {}", fold.train.0, fold.train.1, fold.best);
+                println!("test  {}..{}: {}", fold.test.0, fold.test.1, fold.out_of_sample.score);
+                results.extend_from_slice(&fold.out_of_sample.results);
+            }
+            println!("out of sample: {}", optimize::Score::new(optimizer.params.money.to_f64(), &results));
+            Ok(())
+        },
+    })
+}
+
+/// From the statistics of every account or of the given one.
+fn export_report(from: &str, to: &str, account: Option<String>, dir: Option<std::path::PathBuf>)
+    -> Result<(), Error> {
+    let (from, to) = report::parse_period(from, to)?;
+    // the statistics need no account ids
+    let configs = AccountConfig::load(Some(""))?;
+    let accounts = match account {
+        Some(account) => vec![account],
+        None => configs.iter().map(|config| config.name.clone()).collect(),
     };
-    let response = client.get_order_state(req).await?;
-    println!("{:?}", response.into_inner());
+    let mut report = report::Report::load(&accounts, from, to)?;
+    // the currencies of the strategies, the charts of several ones are labelled with all of them
+    let mut currencies: Vec<String> = configs.iter()
+        .filter(|config| accounts.contains(&config.name))
+        .filter_map(|config| strategies::by_name(&config.strategy))
+        .map(|strategy| strategy.get_settings().currency)
+        .collect();
+    currencies.sort();
+    currencies.dedup();
+    report.currency = currencies.join("+");
+    println!("{} trades and {} days of {} accounts", report.trades.len(), report.days.len(), accounts.len());
+    if report.undated > 0 {
+        println!("{} trades of the older statistics have no dates and are left out", report.undated);
+    }
+    let dir = dir.unwrap_or_else(|| std::path::PathBuf::from(format!("reports/{}_{}", from, to)));
+    for path in report.write(&dir)? {
+        println!("{}", path.display());
+    }
     Ok(())
 }
-#[allow(dead_code)]
-#[cfg(debug_assertions)]
-async fn test_candles(channel: Channel) -> Result<(), Status> {
-    use prost_types::Timestamp;
-    use std::time::SystemTime;
-    use chrono::{TimeZone, Utc};
 
-    let inter = DefaultInterceptor {
-        token: String::from(std::env::var("TOKEN_BOT").unwrap())
-    };
-    let x: SystemTime = Utc.with_ymd_and_hms(2023, 2, 9, 9, 0, 0).unwrap().into();
-    let from = Timestamp::from(x);
-    let x: SystemTime = Utc.with_ymd_and_hms(2023, 2, 9, 23, 0, 0).unwrap().into();
-    let to = Timestamp::from(x);
-    let req = GetCandlesRequest {
-        figi: "BBG000000001".to_string(),
-        interval: CandleInterval::CandleInterval5Min.into(),
-        from: Some(from),
-        to: Some(to),
-        instrument_id: "e2d0dbac-d354-4c36-a5ed-e5aae42ffc76".to_string(),
-    };
-    let mut client = MarketDataServiceClient::with_interceptor(
-        channel, inter);
-    let response = client.get_candles(req).await?;
-    println!("{:#?}", response.into_inner());
+/// `dirs` are `AccountConfig::name` of the accounts.
+fn create_env(owner: Option<i64>, dirs: &[String]) -> Result<(), Error> {
+    use std::fs;
+    use crate::bot::{add_info, ADD_INFO_PATH};
+
+    if fs::read_dir(ADD_INFO_PATH).is_err() {
+        fs::create_dir(ADD_INFO_PATH)?;
+    }
+    create_account_files(dirs)?;
+    if fs::read(add_info("notify.json")).is_err() {
+        let notify = serde_json::to_string_pretty(&NotifySettings::default()).unwrap();
+        fs::write(add_info("notify.json"), notify)?;
+    }
+    if fs::read(add_info("access.json")).is_err() {
+        let access = serde_json::to_string_pretty(&Access::owner(owner)?).unwrap();
+        fs::write(add_info("access.json"), access)?;
+    }
     Ok(())
 }
 
+/// The order id and the empty statistics of the accounts, the files that are there are kept.
+pub fn create_account_files(dirs: &[String]) -> Result<(), Error> {
+    use std::fs;
+    use crate::bot::{account_file, add_info};
+
+    let today = {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        now.with_timezone(&Moscow).date_naive().to_string()
+    };
+    let default_json = serde_json::to_string(&Statistics::new(today.clone())).unwrap();
+    let default_json_today = serde_json::to_string(&DayStat::new(today)).unwrap();
 
+    for dir in dirs {
+        fs::create_dir_all(add_info(dir))?;
+        let file = |name| add_info(&account_file(dir, name));
+        if fs::read(file("oid.txt")).is_err() {
+            if cfg!(debug_assertions) {
+                fs::write(file("oid.txt"), "10000")?;
+            } else {
+                fs::write(file("oid.txt"), "1000000")?;
+            }
+        }
+        if fs::read(file("stat.json")).is_err() {
+            fs::write(file("stat.json"), &default_json)?;
+        }
+        if fs::read(file("today.json")).is_err() {
+            fs::write(file("today.json"), &default_json_today)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::{future::Future, net::SocketAddr, sync::OnceLock, time::Instant};
+use axum::{Router, routing::get};
+use prometheus::{
+    Encoder, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGaugeVec, Opts, Registry, TextEncoder
+};
+use tonic::Status;
+use tracing::error;
+
+use crate::notify::Event;
+
+
+pub struct Metrics {
+    registry: Registry,
+    /// Seconds per call, by RPC method.
+    pub grpc_latency: HistogramVec,
+    /// Failed calls by RPC method and `Status` code.
+    pub grpc_errors: IntCounterVec,
+    /// The "So fast or network error!" branch of the trading loop.
+    pub rate_limit_hits: IntCounter,
+    pub loop_seconds: Histogram,
+    /// By `Action` variant: open, close, hold.
+    pub signals: IntCounterVec,
+    /// Order lifecycle events by status: placed, filled, partially_filled, rejected, cancelled.
+    pub orders: IntCounterVec,
+    /// By account, signed: positive for long, negative for short.
+    pub position_lots: IntGaugeVec,
+    /// By account and the currency of the instrument, in that currency.
+    pub unrealized_pnl: GaugeVec,
+    /// Failed delivery attempts by notification sink (telegram, file, ...).
+    pub sink_failures: IntCounterVec,
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        let registry = Registry::new_custom(Some("bot".to_string()), None).unwrap();
+        let metrics = Metrics {
+            grpc_latency: HistogramVec::new(
+                HistogramOpts::new("grpc_latency_seconds", "gRPC call latency")
+                    .buckets(vec![0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]),
+                &["rpc"]).unwrap(),
+            grpc_errors: IntCounterVec::new(
+                Opts::new("grpc_errors_total", "gRPC errors by status code"),
+                &["rpc", "code"]).unwrap(),
+            rate_limit_hits: IntCounter::new(
+                "rate_limit_hits_total", "Market data requests failed by rate limit or network").unwrap(),
+            loop_seconds: Histogram::with_opts(
+                HistogramOpts::new("loop_iteration_seconds", "Trading loop iteration time")
+                    .buckets(vec![0.1, 0.25, 0.5, 0.75, 1.0, 2.0, 5.0, 10.0, 60.0])).unwrap(),
+            signals: IntCounterVec::new(
+                Opts::new("signals_total", "Strategy signals by action"),
+                &["action"]).unwrap(),
+            orders: IntCounterVec::new(
+                Opts::new("orders_total", "Order events by status"),
+                &["status"]).unwrap(),
+            position_lots: IntGaugeVec::new(
+                Opts::new("position_lots", "Current position in lots"),
+                &["account"]).unwrap(),
+            unrealized_pnl: GaugeVec::new(
+                Opts::new("unrealized_pnl", "Unrealized P&L of the position in the instrument currency"),
+                &["account", "currency"]).unwrap(),
+            sink_failures: IntCounterVec::new(
+                Opts::new("notify_failures_total", "Failed notification attempts by sink"),
+                &["sink"]).unwrap(),
+            registry,
+        };
+        let r = &metrics.registry;
+        r.register(Box::new(metrics.grpc_latency.clone())).unwrap();
+        r.register(Box::new(metrics.grpc_errors.clone())).unwrap();
+        r.register(Box::new(metrics.rate_limit_hits.clone())).unwrap();
+        r.register(Box::new(metrics.loop_seconds.clone())).unwrap();
+        r.register(Box::new(metrics.signals.clone())).unwrap();
+        r.register(Box::new(metrics.orders.clone())).unwrap();
+        r.register(Box::new(metrics.position_lots.clone())).unwrap();
+        r.register(Box::new(metrics.unrealized_pnl.clone())).unwrap();
+        r.register(Box::new(metrics.sink_failures.clone())).unwrap();
+        metrics
+    }
+
+    /// Prometheus text format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    /// Measures the call latency and counts its errors by code.
+    pub async fn timed<T, F>(&self, rpc: &str, call: F) -> Result<T, Status>
+        where F: Future<Output = Result<T, Status>> {
+        let start = Instant::now();
+        let result = call.await;
+        self.grpc_latency.with_label_values(&[rpc]).observe(start.elapsed().as_secs_f64());
+        if let Err(status) = &result {
+            self.grpc_errors.with_label_values(&[rpc, &format!("{:?}", status.code())]).inc();
+        }
+        result
+    }
+
+    /// Counts order lifecycle events, the others are ignored.
+    pub fn observe_event(&self, event: &Event) {
+        let status = match event {
+            Event::OrderPlaced { .. } => "placed",
+            Event::OrderFilled { .. } => "filled",
+            Event::OrderPartiallyFilled { .. } => "partially_filled",
+            Event::OrderRejected { .. } => "rejected",
+            Event::OrderCancelled { .. } => "cancelled",
+            _ => return,
+        };
+        self.orders.with_label_values(&[status]).inc();
+    }
+}
+
+/// See `Metrics::timed`.
+pub async fn timed<T, F>(rpc: &str, call: F) -> Result<T, Status>
+    where F: Future<Output = Result<T, Status>> {
+    metrics().timed(rpc, call).await
+}
+
+/// Serves `/metrics` for Prometheus in the background, apart from the dashboard
+/// and its token: the scrapers are usually set up without one.
+pub fn serve(addr: SocketAddr) {
+    let app = Router::new().route("/metrics", get(|| async { metrics().encode() }));
+    tokio::spawn(async move {
+        if let Err(err) = axum::Server::bind(&addr).serve(app.into_make_service()).await {
+            error!(%err, "metrics server stopped");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[tokio::test]
+    async fn export() {
+        // its own registry, the global one is shared with the other tests
+        let metrics = Metrics::new();
+        let _ = metrics.timed("GetOrderBook", async { Ok::<_, Status>(()) }).await;
+        let _ = metrics.timed("GetOrderBook", async {
+            Err::<(), _>(Status::new(Code::ResourceExhausted, "limit"))
+        }).await;
+        metrics.observe_event(&Event::OrderCancelled { order_id: "1".to_string() });
+        metrics.observe_event(&Event::Message("ignored".to_string()));
+        metrics.unrealized_pnl.with_label_values(&["2000000001", "usd"]).set(-1.5);
+
+        let text = metrics.encode();
+        assert!(text.contains("bot_grpc_latency_seconds_count{rpc=\"GetOrderBook\"} 2"));
+        assert!(text.contains("bot_grpc_errors_total{code=\"ResourceExhausted\",rpc=\"GetOrderBook\"} 1"));
+        assert!(text.contains("bot_orders_total{status=\"cancelled\"} 1"));
+        assert!(!text.contains("bot_orders_total{status=\"placed\"}"));
+        assert!(text.contains("bot_unrealized_pnl{account=\"2000000001\",currency=\"usd\"} -1.5"));
+    }
+}
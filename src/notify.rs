@@ -0,0 +1,539 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime}
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Europe::Moscow;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    oneshot,
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender}
+};
+
+use crate::balance::Money;
+use crate::bot::{add_info, read_json, DayStat, ProfitStat};
+use crate::error::Result;
+use crate::tcs::{OrderDirection, Quotation};
+use crate::metrics::metrics;
+use tracing::{error, info};
+
+
+pub type SinkError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug)]
+pub enum Event {
+    OrderPlaced { order_id: String, direction: OrderDirection, lots: i64, price: Option<Money> },
+    OrderFilled { order_id: String, lots: i64, price: Money },
+    OrderPartiallyFilled { order_id: String, lots_executed: i64, lots_requested: i64 },
+    OrderRejected { order_id: String, message: String },
+    OrderCancelled { order_id: String },
+    PositionOpened { lots: i64, price: Money },
+    PositionClosed { lots: i64, price_in: Quotation, price_out: Money, profit: ProfitStat },
+    RiskLimit(String),
+    Error(String),
+    ConnectionLost(String),
+    ConnectionRestored,
+    DayReport(DayStat),
+    /// Answers to commands and other plain text.
+    Message(String),
+    /// PNG image, not batched with the text events.
+    Chart { caption: String, png: Vec<u8> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    OrderPlaced,
+    OrderFilled,
+    OrderPartiallyFilled,
+    OrderRejected,
+    OrderCancelled,
+    PositionOpened,
+    PositionClosed,
+    RiskLimit,
+    Error,
+    ConnectionLost,
+    ConnectionRestored,
+    DayReport,
+    Message,
+    Chart,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::OrderPlaced { .. } => EventKind::OrderPlaced,
+            Event::OrderFilled { .. } => EventKind::OrderFilled,
+            Event::OrderPartiallyFilled { .. } => EventKind::OrderPartiallyFilled,
+            Event::OrderRejected { .. } => EventKind::OrderRejected,
+            Event::OrderCancelled { .. } => EventKind::OrderCancelled,
+            Event::PositionOpened { .. } => EventKind::PositionOpened,
+            Event::PositionClosed { .. } => EventKind::PositionClosed,
+            Event::RiskLimit(_) => EventKind::RiskLimit,
+            Event::Error(_) => EventKind::Error,
+            Event::ConnectionLost(_) => EventKind::ConnectionLost,
+            Event::ConnectionRestored => EventKind::ConnectionRestored,
+            Event::DayReport(_) => EventKind::DayReport,
+            Event::Message(_) => EventKind::Message,
+            Event::Chart { .. } => EventKind::Chart,
+        }
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let direction = |d: &OrderDirection| {
+            if *d == OrderDirection::Buy { "покупка" } else { "продажа" }
+        };
+        match self {
+            Event::OrderPlaced { order_id, direction: d, lots, price: Some(price) } =>
+                write!(f, "Заявка {} выставлена: {} {} лотов по {}",
+                       order_id, direction(d), lots, price),
+            Event::OrderPlaced { order_id, direction: d, lots, price: None } =>
+                write!(f, "Заявка {} выставлена: {} {} лотов по рынку",
+                       order_id, direction(d), lots),
+            Event::OrderFilled { order_id, lots, price } =>
+                write!(f, "Заявка {} исполнена: {} лотов по {}", order_id, lots, price),
+            Event::OrderPartiallyFilled { order_id, lots_executed, lots_requested } =>
+                write!(f, "Заявка {} исполнена частично: {} из {} лотов",
+                       order_id, lots_executed, lots_requested),
+            Event::OrderRejected { order_id, message } if message.is_empty() =>
+                write!(f, "Заявка {} отклонена", order_id),
+            Event::OrderRejected { order_id, message } =>
+                write!(f, "Заявка {} отклонена: {}", order_id, message),
+            Event::OrderCancelled { order_id } =>
+                write!(f, "Заявка {} отменена", order_id),
+            Event::PositionOpened { lots, price } =>
+                write!(f, "Позиция открыта: {} лотов по {}", lots, price),
+            Event::PositionClosed { lots, price_in, price_out, profit } =>
+                write!(f, "Позиция закрыта: {} лотов, {} -> {}\nПрибыль: {}",
+                       lots, price_in, price_out, profit),
+            Event::RiskLimit(message) => write!(f, "Риск-лимит: {}", message),
+            Event::Error(message) => write!(f, "Ошибка: {}", message),
+            Event::ConnectionLost(message) => write!(f, "Связь потеряна: {}", message),
+            Event::ConnectionRestored => write!(f, "Связь восстановлена"),
+            Event::DayReport(stat) => write!(f, "Итоги дня\n{}", stat),
+            Event::Message(message) => write!(f, "{}", message),
+            Event::Chart { caption, .. } => write!(f, "{}", caption),
+        }
+    }
+}
+
+/// Somewhere to deliver notifications to.
+pub trait Sink: Send + Sync {
+    fn name(&self) -> &str;
+    fn send<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<(), SinkError>>;
+    /// Sinks that can't show images get only the caption.
+    fn send_image<'a>(&'a self, caption: &'a str, _png: &'a [u8])
+        -> BoxFuture<'a, Result<(), SinkError>> {
+        self.send(caption)
+    }
+}
+
+pub struct StdoutSink;
+
+impl Sink for StdoutSink {
+    fn name(&self) -> &str {
+        "stdout"
+    }
+    fn send<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<(), SinkError>> {
+        Box::pin(async move {
+            println!("{}", text);
+            Ok(())
+        })
+    }
+}
+
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> FileSink {
+        FileSink { path }
+    }
+}
+
+impl Sink for FileSink {
+    fn name(&self) -> &str {
+        "file"
+    }
+    fn send<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<(), SinkError>> {
+        Box::pin(async move {
+            let time: DateTime<Utc> = SystemTime::now().into();
+            let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            writeln!(file, "[{}]\n{}", time.with_timezone(&Moscow).format("%Y-%m-%d %H:%M:%S"), text)?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkType {
+    Telegram,
+    Stdout,
+    File,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SinkSettings {
+    pub sink: SinkType,
+    /// Subscribed events, empty means all of them.
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+    /// For the file sink, relative to `add_info`.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Stored in `add_info/notify.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NotifySettings {
+    /// Events coming within this window are sent as one message.
+    pub batch_ms: u64,
+    pub max_batch: usize,
+    /// Minimal pause between two messages of the same sink.
+    pub min_interval_ms: u64,
+    pub retries: u32,
+    pub sinks: Vec<SinkSettings>,
+}
+
+impl Default for NotifySettings {
+    fn default() -> Self {
+        NotifySettings {
+            batch_ms: 1000,
+            max_batch: 20,
+            min_interval_ms: 1000,
+            retries: 3,
+            sinks: vec![
+                SinkSettings { sink: SinkType::Telegram, events: Vec::new(), path: None },
+                SinkSettings {
+                    sink: SinkType::File,
+                    events: Vec::new(),
+                    path: Some(String::from("notify.log")),
+                },
+            ],
+        }
+    }
+}
+
+impl NotifySettings {
+    const FILE: &'static str = "notify.json";
+
+    pub fn load() -> Result<NotifySettings> {
+        read_json(Self::FILE)
+    }
+}
+
+struct Subscriber {
+    sink: Arc<dyn Sink>,
+    events: Vec<EventKind>,
+    last_sent: Option<Instant>,
+}
+
+impl Subscriber {
+    fn is_subscribed(&self, kind: EventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// The event and the account it is about, if there are several.
+struct Tagged {
+    account: Option<String>,
+    event: Event,
+}
+
+impl Display for Tagged {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.account {
+            Some(account) => write!(f, "{}: {}", account, self.event),
+            None => write!(f, "{}", self.event),
+        }
+    }
+}
+
+enum Command {
+    Event(Tagged),
+    /// Goes to the requester only, not batched.
+    Reply(Arc<dyn Sink>, Tagged),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Cheap to clone handle, events are delivered by a background task.
+#[derive(Clone)]
+pub struct Notifier {
+    tx: UnboundedSender<Command>,
+    account: Option<String>,
+}
+
+impl Notifier {
+    /// `sinks` are matched with `settings.sinks` by the caller, see `main`.
+    pub fn start(settings: &NotifySettings, sinks: Vec<(Box<dyn Sink>, Vec<EventKind>)>) -> Notifier {
+        let (tx, rx) = unbounded_channel();
+        let subscribers = sinks.into_iter()
+            .map(|(sink, events)| Subscriber { sink: Arc::from(sink), events, last_sent: None })
+            .collect();
+        tokio::spawn(run(settings.clone(), subscribers, rx));
+        Notifier { tx, account: None }
+    }
+
+    /// The events sent through the handle are prefixed with `account`.
+    pub fn for_account(&self, account: &str) -> Notifier {
+        Notifier { tx: self.tx.clone(), account: Some(account.to_string()) }
+    }
+
+    pub fn send(&self, event: Event) {
+        metrics().observe_event(&event);
+        info!(kind = ?event.kind(), account = self.account.as_deref(), "{}", event);
+        let _ = self.tx.send(Command::Event(Tagged { account: self.account.clone(), event }));
+    }
+
+    pub fn message(&self, message: String) {
+        self.send(Event::Message(message));
+    }
+
+    /// The answer to a request, to where it came from, see `Request::reply`.
+    pub fn reply(&self, to: &Arc<dyn Sink>, event: Event) {
+        metrics().observe_event(&event);
+        info!(kind = ?event.kind(), account = self.account.as_deref(), sink = to.name(), "{}", event);
+        let _ = self.tx.send(Command::Reply(to.clone(), Tagged { account: self.account.clone(), event }));
+    }
+
+    /// Waits until everything sent before is delivered (or given up).
+    pub async fn flush(&self) {
+        let (tx, rx) = oneshot::channel();
+        if self.tx.send(Command::Flush(tx)).is_ok() {
+            let _ = rx.await;
+        }
+    }
+}
+
+async fn run(settings: NotifySettings, mut subscribers: Vec<Subscriber>,
+             mut rx: UnboundedReceiver<Command>) {
+    let window = Duration::from_millis(settings.batch_ms);
+    while let Some(command) = rx.recv().await {
+        let first = match command {
+            Command::Event(event) => event,
+            Command::Reply(sink, tagged) => {
+                reply(&settings, sink, &tagged).await;
+                continue;
+            },
+            Command::Flush(ack) => {
+                let _ = ack.send(());
+                continue;
+            },
+        };
+        let mut batch = vec![first];
+        let mut replies = Vec::new();
+        let mut flush = None;
+        let deadline = tokio::time::Instant::now() + window;
+        while batch.len() < settings.max_batch {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(Command::Event(event))) => batch.push(event),
+                Ok(Some(Command::Reply(sink, tagged))) => replies.push((sink, tagged)),
+                Ok(Some(Command::Flush(ack))) => {
+                    flush = Some(ack);
+                    break;
+                },
+                _ => break,
+            }
+        }
+        for subscriber in subscribers.iter_mut() {
+            let text = batch.iter()
+                .filter(|tagged| tagged.event.kind() != EventKind::Chart)
+                .filter(|tagged| subscriber.is_subscribed(tagged.event.kind()))
+                .map(|tagged| tagged.to_string())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            if !text.is_empty() {
+                deliver(&settings, subscriber, Payload::Text(&text)).await;
+            }
+            for tagged in &batch {
+                if let (Event::Chart { png, .. }, true) =
+                    (&tagged.event, subscriber.is_subscribed(EventKind::Chart)) {
+                    deliver(&settings, subscriber, Payload::Image(&tagged.to_string(), png)).await;
+                }
+            }
+        }
+        for (sink, tagged) in replies {
+            reply(&settings, sink, &tagged).await;
+        }
+        if let Some(ack) = flush {
+            let _ = ack.send(());
+        }
+    }
+}
+
+/// Retried like the notifications, every event on its own.
+async fn reply(settings: &NotifySettings, sink: Arc<dyn Sink>, tagged: &Tagged) {
+    let mut subscriber = Subscriber { sink, events: Vec::new(), last_sent: None };
+    let text = tagged.to_string();
+    let payload = match &tagged.event {
+        Event::Chart { png, .. } => Payload::Image(&text, png),
+        _ => Payload::Text(&text),
+    };
+    deliver(settings, &mut subscriber, payload).await;
+}
+
+enum Payload<'a> {
+    Text(&'a str),
+    Image(&'a str, &'a [u8]),
+}
+
+async fn deliver(settings: &NotifySettings, subscriber: &mut Subscriber, payload: Payload<'_>) {
+    let min_interval = Duration::from_millis(settings.min_interval_ms);
+    if let Some(last) = subscriber.last_sent {
+        if last.elapsed() < min_interval {
+            tokio::time::sleep(min_interval - last.elapsed()).await;
+        }
+    }
+    let mut pause = Duration::from_secs(1);
+    for attempt in 0..=settings.retries {
+        let result = match payload {
+            Payload::Text(text) => subscriber.sink.send(text).await,
+            Payload::Image(caption, png) => subscriber.sink.send_image(caption, png).await,
+        };
+        if result.is_err() {
+            metrics().sink_failures.with_label_values(&[subscriber.sink.name()]).inc();
+        }
+        match result {
+            Ok(()) => break,
+            Err(err) if attempt == settings.retries => {
+                error!(sink = subscriber.sink.name(), %err, "can't notify");
+            },
+            Err(_) => {
+                tokio::time::sleep(pause).await;
+                pause *= 2;
+            },
+        }
+    }
+    subscriber.last_sent = Some(Instant::now());
+}
+
+/// Matches `settings.sinks` with the sink implementations.
+/// Sinks of the controls (see `Control::sinks`) missing in the settings get all events.
+pub fn sinks(settings: &NotifySettings, mut controls: Vec<Box<dyn Sink>>)
+    -> Vec<(Box<dyn Sink>, Vec<EventKind>)> {
+    let mut take = |name: &str| controls.iter()
+        .position(|sink| sink.name() == name)
+        .map(|i| controls.remove(i));
+    let mut sinks: Vec<(Box<dyn Sink>, Vec<EventKind>)> = Vec::new();
+    for SinkSettings { sink, events, path } in &settings.sinks {
+        match sink {
+            // one for every chat
+            SinkType::Telegram => while let Some(telegram) = take("telegram") {
+                sinks.push((telegram, events.clone()));
+            },
+            SinkType::Stdout => {
+                let stdout = take("stdout").unwrap_or_else(|| Box::new(StdoutSink));
+                sinks.push((stdout, events.clone()));
+            },
+            SinkType::File => {
+                let path = add_info(path.as_deref().unwrap_or("notify.log"));
+                sinks.push((Box::new(FileSink::new(path)), events.clone()));
+            },
+        }
+    }
+    sinks.extend(controls.into_iter().map(|sink| (sink, Vec::new())));
+    sinks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicI32, AtomicU32, Ordering}
+    };
+
+    struct FlakySink {
+        failures: AtomicI32,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl Sink for FlakySink {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+        fn send<'a>(&'a self, _: &'a str) -> BoxFuture<'a, Result<(), SinkError>> {
+            Box::pin(async move {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                if self.failures.fetch_sub(1, Ordering::SeqCst) > 0 {
+                    Err("network".into())
+                } else {
+                    Ok(())
+                }
+            })
+        }
+    }
+
+    fn settings() -> NotifySettings {
+        NotifySettings {
+            batch_ms: 50,
+            max_batch: 20,
+            min_interval_ms: 0,
+            retries: 3,
+            sinks: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_and_subscriptions() {
+        let path = std::env::temp_dir().join(format!("notify_{}.log", uuid::Uuid::new_v4()));
+        let notifier = Notifier::start(&settings(), vec![
+            (Box::new(FileSink::new(path.clone())), vec![EventKind::OrderFilled, EventKind::Message]),
+        ]);
+        notifier.send(Event::OrderCancelled { order_id: "1".to_string() });
+        notifier.send(Event::OrderFilled {
+            order_id: "2".to_string(), lots: 3, price: Money::new(Quotation { units: 5, nano: 91_0000000 }, "rub")
+        });
+        notifier.message("Мы начали!".to_string());
+        notifier.for_account("iis").message("Пауза".to_string());
+        notifier.flush().await;
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        // one batch, the cancel is filtered out
+        assert_eq!(log.matches('[').count(), 1);
+        assert!(log.contains("Заявка 2 исполнена: 3 лотов по 5.91 RUB\n\nМы начали!\n\niis: Пауза"));
+        assert!(!log.contains("отменена"));
+    }
+
+    #[tokio::test]
+    async fn reply_to_requester() {
+        let path = |name| std::env::temp_dir().join(format!("notify_{}_{}.log", name, uuid::Uuid::new_v4()));
+        let (subscribed, requester) = (path("subscribed"), path("requester"));
+        let notifier = Notifier::start(&settings(), vec![
+            (Box::new(FileSink::new(subscribed.clone())), Vec::new()),
+        ]);
+        let chat: Arc<dyn Sink> = Arc::new(FileSink::new(requester.clone()));
+        notifier.for_account("iis").reply(&chat, Event::Message("Пауза".to_string()));
+        notifier.flush().await;
+
+        let log = std::fs::read_to_string(&requester).unwrap();
+        let _ = std::fs::remove_file(&requester);
+        assert!(log.contains("iis: Пауза"));
+        assert!(std::fs::read_to_string(&subscribed).is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let healthy = Arc::new(AtomicU32::new(0));
+        // like the chats of the telegram control, a sink each
+        let notifier = Notifier::start(&settings(), vec![
+            (Box::new(FlakySink { failures: AtomicI32::new(2), calls: calls.clone() }), Vec::new()),
+            (Box::new(FlakySink { failures: AtomicI32::new(0), calls: healthy.clone() }), Vec::new()),
+        ]);
+        notifier.message("test".to_string());
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(healthy.load(Ordering::SeqCst), 1);
+    }
+}
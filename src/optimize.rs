@@ -0,0 +1,486 @@
+//! Tunes the constants of a strategy on recorded market data. Every parameter set is
+//! a replay with the real bot (see `replay`), the sets run in parallel, a thread each.
+
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    fs,
+    path::PathBuf,
+    sync::{Mutex, atomic::{AtomicUsize, Ordering}}
+};
+use chrono::NaiveDate;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::analytics;
+use crate::error::{Error, Result};
+use crate::replay::{self, Report, Tape};
+use crate::strategies::{self, Params};
+use crate::tcs::OrderDirection;
+
+
+/// Values of a parameter: `from..=to` by `step`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub name: String,
+    pub from: f64,
+    pub to: f64,
+    pub step: f64,
+}
+
+impl Range {
+    /// `name=from:to:step` or `name=value`.
+    pub fn parse(text: &str) -> Result<Range> {
+        let error = || Error::Config(format!("bad range {}, name=from:to:step", text));
+        let (name, values) = text.split_once('=').ok_or_else(error)?;
+        let numbers = values.split(':')
+            .map(|value| value.parse::<f64>().map_err(|_| error()))
+            .collect::<Result<Vec<_>>>()?;
+        let (from, to, step) = match numbers[..] {
+            [value] => (value, value, 1.0),
+            [from, to, step] if step > 0.0 && from <= to => (from, to, step),
+            _ => return Err(error()),
+        };
+        Ok(Range { name: name.to_string(), from, to, step })
+    }
+
+    pub fn values(&self) -> Vec<f64> {
+        let count = ((self.to - self.from) / self.step + 1e-9).floor() as usize + 1;
+        // without the noise of the float steps, 0.30000000000000004 and the like
+        (0..count).map(|i| ((self.from + i as f64 * self.step) * 1e9).round() / 1e9).collect()
+    }
+}
+
+/// How the parameter sets are picked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Search {
+    /// Every combination.
+    Grid,
+    /// That many combinations at random.
+    Random(usize),
+    /// That many, in rounds: random first, then around the best sets so far,
+    /// closer with every round. A cheap stand-in for a Bayesian search.
+    Adaptive(usize),
+}
+
+impl Search {
+    /// `grid`, `random:N` or `adaptive:N`.
+    pub fn parse(text: &str) -> Result<Search> {
+        let error = || Error::Config(format!("bad search {}, grid, random:N or adaptive:N", text));
+        let count = |count: &str| count.parse::<usize>().ok().filter(|&n| n > 0).ok_or_else(error);
+        match text.split_once(':') {
+            None if text == "grid" => Ok(Search::Grid),
+            Some(("random", n)) => Ok(Search::Random(count(n)?)),
+            Some(("adaptive", n)) => Ok(Search::Adaptive(count(n)?)),
+            _ => Err(error()),
+        }
+    }
+}
+
+/// How a parameter set did, over the round trips.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    pub trades: usize,
+    /// In the currency of the instrument, the fees taken.
+    pub net: f64,
+    /// Mean over standard deviation of the trade returns, not annualized,
+    /// unlike `analytics::Performance::sharpe` over the days.
+    pub trade_sharpe: f64,
+    /// The deepest fall of the equity from its peak, a fraction of the peak.
+    pub max_drawdown: f64,
+    pub win_rate: f64,
+    /// Gross profit over gross loss, infinite without losses.
+    pub profit_factor: f64,
+}
+
+impl Score {
+    /// Over the results of the round trips in order, the equity starts at `money`.
+    pub fn new(money: f64, results: &[f64]) -> Score {
+        let mut equity = money;
+        let mut peak = money;
+        let mut max_drawdown: f64 = 0.0;
+        let mut returns = Vec::with_capacity(results.len());
+        for &result in results {
+            returns.push(if equity > 0.0 { result / equity } else { 0.0 });
+            equity += result;
+            peak = peak.max(equity);
+            if peak > 0.0 {
+                max_drawdown = max_drawdown.max((peak - equity) / peak);
+            }
+        }
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n.max(1.0);
+        let deviation = if returns.len() > 1 {
+            (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        Score {
+            trades: results.len(),
+            // an empty float sum is -0
+            net: results.iter().fold(0.0, |sum, r| sum + r),
+            trade_sharpe: if deviation > 0.0 { mean / deviation } else { 0.0 },
+            max_drawdown,
+            win_rate: analytics::win_rate(results),
+            profit_factor: analytics::profit_factor(results),
+        }
+    }
+
+    /// What the search maximizes: the Sharpe of the trades, then the net result.
+    fn objective(&self) -> (f64, f64) {
+        (self.trade_sharpe, self.net)
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "trades {}, net {:.2}, trade sharpe {:.2}, max drawdown {:.2}%, win rate {:.0}%, profit factor {:.2}",
+               self.trades, self.net, self.trade_sharpe, self.max_drawdown * 100.0, self.win_rate * 100.0,
+               self.profit_factor)
+    }
+}
+
+/// Results of the round trips of the replay, from flat to flat, the fees included.
+pub fn round_trips(report: &Report) -> Vec<f64> {
+    let mut results = Vec::new();
+    let (mut position, mut result) = (0, 0.0);
+    for execution in &report.executions {
+        let sign = if execution.direction == OrderDirection::Buy { 1 } else { -1 };
+        result -= sign as f64 * execution.price.to_f64() * execution.lots as f64
+            + execution.commission.to_f64();
+        position += sign * execution.lots;
+        if position == 0 {
+            results.push(result);
+            result = 0.0;
+        }
+    }
+    results
+}
+
+/// A replay of one parameter set.
+#[derive(Debug, Clone)]
+pub struct Trial {
+    pub params: Params,
+    pub results: Vec<f64>,
+    pub score: Score,
+}
+
+impl Display for Trial {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let params = self.params.iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "{}: {}", params, self.score)
+    }
+}
+
+/// A step of the walk-forward analysis: the best set of the train days on the test days after them.
+#[derive(Debug, Clone)]
+pub struct Fold {
+    pub train: (NaiveDate, NaiveDate),
+    pub test: (NaiveDate, NaiveDate),
+    pub best: Trial,
+    pub out_of_sample: Trial,
+}
+
+/// Train and test windows over `days`, the next fold starts `test` days later.
+pub fn folds(days: usize, train: usize, test: usize) -> Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+    if train == 0 || test == 0 {
+        return Vec::new();
+    }
+    (0..).step_by(test)
+        .take_while(|start| start + train + test <= days)
+        .map(|start| (start..start + train, start + train..start + train + test))
+        .collect()
+}
+
+pub struct Optimizer {
+    pub strategy: String,
+    pub params: replay::Params,
+    pub ranges: Vec<Range>,
+    pub search: Search,
+    /// Replays at once.
+    pub workers: usize,
+    pub seed: u64,
+    /// Numbers the replays.
+    runs: AtomicUsize,
+    /// The statistics of the replays, a temporary one per optimizer: several runs in one
+    /// directory don't touch each other's files.
+    dir: PathBuf,
+}
+
+impl Optimizer {
+    /// A worker a core.
+    pub fn new(strategy: &str, params: replay::Params, ranges: Vec<Range>, search: Search) -> Optimizer {
+        Optimizer {
+            strategy: strategy.to_string(),
+            params,
+            ranges,
+            search,
+            workers: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: 1,
+            runs: AtomicUsize::new(0),
+            dir: std::env::temp_dir().join(format!("optimize-{}", uuid::Uuid::new_v4())),
+        }
+    }
+
+    /// Every set the search tried on the tape, the best first.
+    pub fn optimize(&self, tape: &Tape) -> Result<Vec<Trial>> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut tried: Vec<(Vec<usize>, Trial)> = Vec::new();
+        let (total, round) = match self.search {
+            Search::Grid => (self.grid().len(), usize::MAX),
+            Search::Random(n) => (n, n),
+            Search::Adaptive(n) => (n, self.workers.max(2)),
+        };
+        let mut shrink = 1.0;
+        while tried.len() < total {
+            let want = round.min(total - tried.len());
+            let points = match self.search {
+                Search::Grid => self.grid(),
+                Search::Adaptive(_) if !tried.is_empty() => {
+                    shrink /= 2.0;
+                    self.around(&mut rng, &tried, want, shrink)
+                },
+                _ => self.random(&mut rng, &tried, want),
+            };
+            if points.is_empty() {
+                // the grid is smaller than asked
+                break;
+            }
+            let trials = self.evaluate(tape, points.iter().map(|point| self.params_of(point)).collect())?;
+            tried.extend(points.into_iter().zip(trials));
+        }
+        let mut trials: Vec<Trial> = tried.into_iter().map(|(_, trial)| trial).collect();
+        trials.sort_by(|a, b| b.score.objective().partial_cmp(&a.score.objective())
+            .unwrap_or(std::cmp::Ordering::Equal));
+        Ok(trials)
+    }
+
+    /// Optimizes on `train` days and replays the best set on the `test` days after them.
+    pub fn walk_forward(&self, tape: &Tape, train: usize, test: usize) -> Result<Vec<Fold>> {
+        let days = tape.days();
+        let windows = folds(days.len(), train, test);
+        if windows.is_empty() {
+            return Err(Error::Config(format!(
+                "{} recorded days are not enough for {} train and {} test days", days.len(), train, test)));
+        }
+        windows.into_iter()
+            .map(|(train, test)| {
+                let train = (days[train.start], days[train.end - 1]);
+                let test = (days[test.start], days[test.end - 1]);
+                let best = self.optimize(&tape.between(train.0, train.1))?.remove(0);
+                let out_of_sample = self.evaluate(&tape.between(test.0, test.1), vec![best.params.clone()])?.remove(0);
+                Ok(Fold { train, test, best, out_of_sample })
+            })
+            .collect()
+    }
+
+    fn params_of(&self, point: &[usize]) -> Params {
+        self.ranges.iter().zip(point)
+            .map(|(range, &i)| (range.name.clone(), range.values()[i]))
+            .collect()
+    }
+
+    fn grid(&self) -> Vec<Vec<usize>> {
+        self.ranges.iter().fold(vec![Vec::new()], |points, range| {
+            points.into_iter()
+                .flat_map(|point| (0..range.values().len()).map(move |i| {
+                    let mut point = point.clone();
+                    point.push(i);
+                    point
+                }))
+                .collect()
+        })
+    }
+
+    /// Up to `want` points of `pick` not tried yet.
+    fn sample(&self, tried: &[(Vec<usize>, Trial)], want: usize,
+              mut pick: impl FnMut() -> Vec<usize>) -> Vec<Vec<usize>> {
+        let mut seen: HashSet<Vec<usize>> = tried.iter().map(|(point, _)| point.clone()).collect();
+        let mut points = Vec::new();
+        // a small space runs out of new points
+        for _ in 0..want * 20 {
+            if points.len() == want {
+                break;
+            }
+            let point = pick();
+            if seen.insert(point.clone()) {
+                points.push(point);
+            }
+        }
+        points
+    }
+
+    fn random(&self, rng: &mut StdRng, tried: &[(Vec<usize>, Trial)], want: usize) -> Vec<Vec<usize>> {
+        self.sample(tried, want, || self.ranges.iter()
+            .map(|range| rng.gen_range(0..range.values().len()))
+            .collect())
+    }
+
+    /// Near the best quarter of the sets, `shrink` of a range away at most.
+    fn around(&self, rng: &mut StdRng, tried: &[(Vec<usize>, Trial)], want: usize, shrink: f64)
+        -> Vec<Vec<usize>> {
+        let mut ranked: Vec<&(Vec<usize>, Trial)> = tried.iter().collect();
+        ranked.sort_by(|a, b| b.1.score.objective().partial_cmp(&a.1.score.objective())
+            .unwrap_or(std::cmp::Ordering::Equal));
+        let best: Vec<&Vec<usize>> = ranked.iter().take((tried.len() / 4).max(1)).map(|(point, _)| point).collect();
+        self.sample(tried, want, || {
+            let center = best[rng.gen_range(0..best.len())];
+            self.ranges.iter().zip(center)
+                .map(|(range, &i)| {
+                    let count = range.values().len() as i64;
+                    let reach = ((count as f64 * shrink).round() as i64).max(1);
+                    (i as i64 + rng.gen_range(-reach..=reach)).clamp(0, count - 1) as usize
+                })
+                .collect()
+        })
+    }
+
+    /// Replays the sets on the workers, the trials in the order of `sets`.
+    fn evaluate(&self, tape: &Tape, sets: Vec<Params>) -> Result<Vec<Trial>> {
+        let next = AtomicUsize::new(0);
+        let done = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for _ in 0..self.workers.clamp(1, sets.len().max(1)) {
+                scope.spawn(|| {
+                    // the bot is not `Send`, each worker runs its own runtime
+                    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                        Ok(runtime) => runtime,
+                        Err(err) => {
+                            done.lock().unwrap().push((0, Err(err.into())));
+                            return;
+                        },
+                    };
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(params) = sets.get(i) else {
+                            return;
+                        };
+                        let trial = runtime.block_on(self.trial(tape, params));
+                        done.lock().unwrap().push((i, trial));
+                    }
+                });
+            }
+        });
+        let mut done = done.into_inner().unwrap();
+        done.sort_by_key(|(i, _)| *i);
+        done.into_iter().map(|(_, trial)| trial).collect()
+    }
+
+    async fn trial(&self, tape: &Tape, params: &Params) -> Result<Trial> {
+        let strategy = strategies::with_params(&self.strategy, params)?;
+        let dir = self.dir.join(self.runs.fetch_add(1, Ordering::Relaxed).to_string());
+        let report = replay::run(&dir, tape.clone(), self.params.clone(), strategy).await;
+        // the statistics of a trial are of no use afterwards
+        let _ = fs::remove_dir_all(&dir);
+        let results = round_trips(&report?);
+        Ok(Trial {
+            params: params.clone(),
+            score: Score::new(self.params.money.to_f64(), &results),
+            results,
+        })
+    }
+}
+
+impl Drop for Optimizer {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::Execution;
+    use crate::tcs::Quotation;
+
+    fn optimizer(search: Search) -> Optimizer {
+        let params = replay::Params {
+            latency: std::time::Duration::ZERO,
+            fee_rate: Quotation::default(),
+            money: Quotation { units: 100, nano: 0 },
+            currency: "rub".to_string(),
+        };
+        let ranges = vec![Range::parse("bid_ratio=2:4:0.5").unwrap(), Range::parse("thinning=2:3:1").unwrap()];
+        Optimizer::new("scalp", params, ranges, search)
+    }
+    fn trial(net: f64) -> Trial {
+        Trial { params: Params::new(), results: vec![net], score: Score { trade_sharpe: net, ..Score::new(100.0, &[]) } }
+    }
+
+    #[test]
+    fn ranges_and_searches() {
+        assert_eq!(Range::parse("bid_ratio=2:4:0.5").unwrap().values(), [2.0, 2.5, 3.0, 3.5, 4.0]);
+        assert_eq!(Range::parse("thinning=3").unwrap().values(), [3.0]);
+        assert_eq!(Range::parse("ask_ratio=0.1:0.3:0.1").unwrap().values(), [0.1, 0.2, 0.3]);
+        assert!(Range::parse("thinning=4:2:1").is_err());
+        assert_eq!(Search::parse("adaptive:30").unwrap(), Search::Adaptive(30));
+        assert!(Search::parse("random:0").is_err());
+
+        let optimizer = optimizer(Search::Random(4));
+        let grid = optimizer.grid();
+        assert_eq!(grid.len(), 10);
+        assert_eq!(optimizer.params_of(&grid[3]),
+                   [("bid_ratio".to_string(), 2.5), ("thinning".to_string(), 3.0)].into_iter().collect());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let first = optimizer.random(&mut rng, &[], 4);
+        assert_eq!(first.iter().collect::<HashSet<_>>().len(), 4);
+        // the rest of the grid and nothing more
+        let tried: Vec<_> = first.into_iter().map(|point| (point, trial(0.0))).collect();
+        assert_eq!(optimizer.random(&mut rng, &tried, 20).len(), 6);
+
+        // close to the best one
+        let tried = vec![(vec![4, 1], trial(1.0)), (vec![0, 0], trial(-1.0))];
+        for point in optimizer.around(&mut rng, &tried, 3, 0.2) {
+            assert!(point[0] >= 3, "{:?}", point);
+            assert_ne!(point, vec![4, 1]);
+        }
+    }
+    #[test]
+    fn scores() {
+        let score = Score::new(100.0, &[10.0, -5.0, 20.0, -10.0]);
+        assert_eq!(score.trades, 4);
+        assert_eq!(score.net, 15.0);
+        assert_eq!(score.win_rate, 0.5);
+        assert_eq!(score.profit_factor, 2.0);
+        assert!((score.max_drawdown - 0.08).abs() < 1e-9);
+        assert!(score.trade_sharpe > 0.0 && score.trade_sharpe < 1.0);
+        assert_eq!(Score::new(100.0, &[1.0]).profit_factor, f64::INFINITY);
+        assert_eq!(Score::new(100.0, &[]).to_string(),
+                   "trades 0, net 0.00, trade sharpe 0.00, max drawdown 0.00%, win rate 0%, profit factor 0.00");
+
+        let execution = |direction, cents: i64, lots| Execution {
+            time: 0,
+            order_id: String::new(),
+            direction,
+            price: Quotation::from_nanos(cents as i128 * 10_000_000),
+            lots,
+            passive: true,
+            commission: Quotation { units: 0, nano: 10_000_000 },
+        };
+        let report = Report {
+            orders: 4,
+            executions: vec![
+                execution(OrderDirection::Buy, 590, 5),
+                execution(OrderDirection::Buy, 590, 5),
+                execution(OrderDirection::Sell, 592, 10),
+                execution(OrderDirection::Sell, 600, 1),
+            ],
+            commission: Quotation::default(),
+            money: (Quotation::default(), Quotation::default()),
+            position: -1,
+            currency: "rub".to_string(),
+        };
+        // the open short is not a round trip yet
+        let results = round_trips(&report);
+        assert_eq!(results.len(), 1);
+        assert!((results[0] - 0.17).abs() < 1e-9);
+    }
+    #[test]
+    fn walk_forward_windows() {
+        assert_eq!(folds(7, 3, 2), [(0..3, 3..5), (2..5, 5..7)]);
+        assert!(folds(4, 3, 2).is_empty());
+    }
+}
@@ -0,0 +1,362 @@
+//! Analytics over the full depth of `GetOrderBookResponse`.
+//! `Scalp` takes the best prices and `replay` the `Queue`, the rest waits for a strategy.
+
+use std::collections::BTreeMap;
+
+use crate::tcs::{GetOrderBookResponse, OrderDirection, Quotation};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Level {
+    pub price: Quotation,
+    /// Lots.
+    pub quantity: i64,
+}
+
+/// A snapshot of the order book, the best prices first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Book {
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+impl From<&GetOrderBookResponse> for Book {
+    fn from(orders: &GetOrderBookResponse) -> Book {
+        let levels = |orders: &[crate::tcs::Order]| orders.iter()
+            .filter_map(|o| Some(Level { price: o.price.clone()?, quantity: o.quantity }))
+            .collect();
+        Book { bids: levels(&orders.bids), asks: levels(&orders.asks) }
+    }
+}
+
+impl Book {
+    pub fn side(&self, side: Side) -> &[Level] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<&Level> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&Level> {
+        self.asks.first()
+    }
+
+    /// Lots at the price, zero if there is no such level.
+    pub fn quantity(&self, side: Side, price: &Quotation) -> i64 {
+        self.side(side).iter()
+            .find(|level| &level.price == price)
+            .map(|level| level.quantity)
+            .unwrap_or_default()
+    }
+
+    /// Lots on the first `levels` levels of the side.
+    #[allow(dead_code)]
+    pub fn depth(&self, side: Side, levels: usize) -> i64 {
+        self.side(side).iter().take(levels).map(|level| level.quantity).sum()
+    }
+
+    pub fn mid(&self) -> Option<f64> {
+        let (bid, ask) = (self.best_bid()?, self.best_ask()?);
+        Some((bid.price.to_f64() + ask.price.to_f64()) / 2.0)
+    }
+
+    /// `(bids - asks) / (bids + asks)` over the first `levels` levels:
+    /// 1 when there are only buyers, -1 when only sellers.
+    #[allow(dead_code)]
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        let (bids, asks) = (self.depth(Side::Bid, levels), self.depth(Side::Ask, levels));
+        if bids + asks == 0 {
+            return None;
+        }
+        Some((bids - asks) as f64 / (bids + asks) as f64)
+    }
+
+    /// Mid weighted by the opposite quantities at the best prices:
+    /// closer to the ask when the bid is thicker.
+    #[allow(dead_code)]
+    pub fn microprice(&self) -> Option<f64> {
+        self.weighted_mid(1)
+    }
+
+    /// The microprice over the first `levels` levels, each side at its average price.
+    #[allow(dead_code)]
+    pub fn weighted_mid(&self, levels: usize) -> Option<f64> {
+        let average = |side: Side| {
+            let depth = self.depth(side, levels);
+            let value: f64 = self.side(side).iter().take(levels)
+                .map(|level| level.price.to_f64() * level.quantity as f64)
+                .sum();
+            (depth > 0).then(|| (value / depth as f64, depth as f64))
+        };
+        let ((bid, bids), (ask, asks)) = (average(Side::Bid)?, average(Side::Ask)?);
+        Some((bid * asks + ask * bids) / (bids + asks))
+    }
+
+    /// The spread in price increments of the instrument.
+    #[allow(dead_code)]
+    pub fn spread_ticks(&self, tick: &Quotation) -> Option<i64> {
+        let (bid, ask) = (self.best_bid()?, self.best_ask()?);
+        let tick = tick.to_nanos();
+        if tick <= 0 {
+            return None;
+        }
+        Some(((ask.price.to_nanos() - bid.price.to_nanos()) / tick) as i64)
+    }
+
+    /// Like `imbalance`, but the level `i` from the top weighs `1 / (i + 1)`:
+    /// the orders near the spread push the price more.
+    #[allow(dead_code)]
+    pub fn pressure(&self, levels: usize) -> Option<f64> {
+        let weighted = |side: Side| -> f64 {
+            self.side(side).iter().take(levels).enumerate()
+                .map(|(i, level)| level.quantity as f64 / (i + 1) as f64)
+                .sum()
+        };
+        let (bids, asks) = (weighted(Side::Bid), weighted(Side::Ask));
+        if bids + asks == 0.0 {
+            return None;
+        }
+        Some((bids - asks) / (bids + asks))
+    }
+
+    /// What changed since `prev`, by price. Levels that left the depth look removed.
+    #[allow(dead_code)]
+    pub fn delta(&self, prev: &Book) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for side in [Side::Bid, Side::Ask] {
+            let mut levels: BTreeMap<i128, (Quotation, i64, i64)> = BTreeMap::new();
+            for level in prev.side(side) {
+                levels.insert(level.price.to_nanos(), (level.price.clone(), level.quantity, 0));
+            }
+            for level in self.side(side) {
+                levels.entry(level.price.to_nanos())
+                    .or_insert((level.price.clone(), 0, 0)).2 = level.quantity;
+            }
+            changes.extend(levels.into_values()
+                .filter(|(_, before, after)| before != after)
+                .map(|(price, before, after)| Change { side, price, before, after }));
+        }
+        changes
+    }
+
+    /// Levels with at least `factor` times the median quantity of their side.
+    #[allow(dead_code)]
+    pub fn walls(&self, factor: f64) -> Vec<Wall> {
+        let mut walls = Vec::new();
+        for side in [Side::Bid, Side::Ask] {
+            let levels = self.side(side);
+            let mut quantities: Vec<i64> = levels.iter().map(|level| level.quantity).collect();
+            if quantities.is_empty() {
+                continue;
+            }
+            quantities.sort_unstable();
+            let median = quantities[quantities.len() / 2] as f64;
+            walls.extend(levels.iter()
+                .filter(|level| level.quantity as f64 >= median * factor)
+                .map(|level| Wall { side, price: level.price.clone(), quantity: level.quantity }));
+        }
+        walls
+    }
+}
+
+/// A price level whose quantity changed between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub struct Change {
+    pub side: Side,
+    pub price: Quotation,
+    pub before: i64,
+    pub after: i64,
+}
+
+#[allow(dead_code)]
+impl Change {
+    pub fn diff(&self) -> i64 {
+        self.after - self.before
+    }
+}
+
+/// A level much bigger than the rest of its side.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub struct Wall {
+    pub side: Side,
+    pub price: Quotation,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum WallEvent {
+    Appeared(Wall),
+    /// The last seen size of the wall.
+    Disappeared(Wall),
+}
+
+/// Reports the walls that appear or disappear from snapshot to snapshot.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Walls {
+    factor: f64,
+    seen: BTreeMap<(Side, i128), Wall>,
+}
+
+#[allow(dead_code)]
+impl Walls {
+    pub fn new(factor: f64) -> Walls {
+        Walls { factor, seen: BTreeMap::new() }
+    }
+
+    pub fn update(&mut self, book: &Book) -> Vec<WallEvent> {
+        let now: BTreeMap<_, _> = book.walls(self.factor).into_iter()
+            .map(|wall| ((wall.side, wall.price.to_nanos()), wall))
+            .collect();
+        let gone = self.seen.iter()
+            .filter(|(key, _)| !now.contains_key(key))
+            .map(|(_, wall)| WallEvent::Disappeared(wall.clone()));
+        let new = now.iter()
+            .filter(|(key, _)| !self.seen.contains_key(key))
+            .map(|(_, wall)| WallEvent::Appeared(wall.clone()));
+        let events = gone.chain(new).collect();
+        self.seen = now;
+        events
+    }
+}
+
+/// Estimates how many lots stand ahead of our limit order at its price.
+/// Quantity leaving the level is assumed to leave from the front of the queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Queue {
+    side: Side,
+    price: Quotation,
+    lots: i64,
+    ahead: i64,
+}
+
+impl Queue {
+    /// `book` is the snapshot just before the order was placed.
+    pub fn new(book: &Book, direction: OrderDirection, price: Quotation, lots: i64) -> Queue {
+        let side = match direction {
+            OrderDirection::Sell => Side::Ask,
+            _ => Side::Bid,
+        };
+        let ahead = book.quantity(side, &price);
+        Queue { side, price, lots, ahead }
+    }
+
+    /// Lots ahead of the order.
+    #[allow(dead_code)]
+    pub fn ahead(&self) -> i64 {
+        self.ahead
+    }
+
+    /// Takes the next snapshot, our own lots are in it.
+    pub fn update(&mut self, book: &Book) -> i64 {
+        let best = book.side(self.side).first().map(|level| &level.price);
+        let passed = match (self.side, best) {
+            (_, None) => false,
+            (Side::Bid, Some(best)) => best < &self.price,
+            (Side::Ask, Some(best)) => best > &self.price,
+        };
+        let others = if passed { 0 } else { (book.quantity(self.side, &self.price) - self.lots).max(0) };
+        self.ahead = self.ahead.min(others);
+        self.ahead
+    }
+
+    /// A trade of `lots` at the price of the order: the queue ahead goes first.
+    /// Returns the lots that reach the order.
+    pub fn trade(&mut self, lots: i64) -> i64 {
+        let reach = (lots - self.ahead).max(0);
+        self.ahead = (self.ahead - lots).max(0);
+        reach
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcs::Order;
+
+    fn price(cents: i64) -> Quotation {
+        Quotation::from_nanos(cents as i128 * 10_000_000)
+    }
+    /// Levels by (price in cents, lots).
+    fn book(bids: &[(i64, i64)], asks: &[(i64, i64)]) -> Book {
+        let orders = |levels: &[(i64, i64)]| levels.iter()
+            .map(|&(p, quantity)| Order { price: Some(price(p)), quantity })
+            .collect();
+        Book::from(&GetOrderBookResponse {
+            bids: orders(bids),
+            asks: orders(asks),
+            ..GetOrderBookResponse::default()
+        })
+    }
+    fn close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn prices() {
+        let book = book(&[(589, 300), (588, 100), (587, 200)], &[(591, 100), (592, 400), (593, 100)]);
+        close(book.mid().unwrap(), 5.9);
+        assert_eq!(book.spread_ticks(&price(1)), Some(2));
+        // the thick bid pulls the price to the ask
+        close(book.microprice().unwrap(), (5.89 * 100.0 + 5.91 * 300.0) / 400.0);
+        let bid = (5.89 * 300.0 + 5.88 * 100.0) / 400.0;
+        let ask = (5.91 * 100.0 + 5.92 * 400.0) / 500.0;
+        close(book.weighted_mid(2).unwrap(), (bid * 500.0 + ask * 400.0) / 900.0);
+
+        close(book.imbalance(1).unwrap(), 0.5);
+        close(book.imbalance(3).unwrap(), 0.0);
+        // bids 300 + 100 / 2 + 200 / 3, asks 100 + 400 / 2 + 100 / 3
+        close(book.pressure(3).unwrap(), (1250.0 / 3.0 - 1000.0 / 3.0) / (2250.0 / 3.0));
+
+        let empty = Book::default();
+        assert_eq!(empty.mid(), None);
+        assert_eq!(empty.imbalance(5), None);
+        assert_eq!(empty.spread_ticks(&price(1)), None);
+    }
+    #[test]
+    fn deltas_and_walls() {
+        let before = book(&[(589, 300), (588, 100)], &[(591, 100), (592, 100), (593, 100)]);
+        let after = book(&[(590, 50), (589, 300), (588, 80)], &[(591, 100), (592, 900), (593, 100)]);
+        assert_eq!(after.delta(&before), vec![
+            Change { side: Side::Bid, price: price(588), before: 100, after: 80 },
+            Change { side: Side::Bid, price: price(590), before: 0, after: 50 },
+            Change { side: Side::Ask, price: price(592), before: 100, after: 900 },
+        ]);
+        assert_eq!(after.delta(&before)[0].diff(), -20);
+
+        let mut walls = Walls::new(5.0);
+        assert!(walls.update(&before).is_empty());
+        let wall = Wall { side: Side::Ask, price: price(592), quantity: 900 };
+        assert_eq!(walls.update(&after), vec![WallEvent::Appeared(wall.clone())]);
+        assert!(walls.update(&after).is_empty());
+        assert_eq!(walls.update(&before), vec![WallEvent::Disappeared(wall)]);
+    }
+    #[test]
+    fn queue_position() {
+        let start = book(&[(589, 300), (588, 100)], &[(591, 100)]);
+        let mut queue = Queue::new(&start, OrderDirection::Buy, price(589), 10);
+        assert_eq!(queue.ahead(), 300);
+        // someone joined behind us
+        assert_eq!(queue.update(&book(&[(589, 350)], &[(591, 100)])), 300);
+        // 120 lots traded or cancelled
+        assert_eq!(queue.update(&book(&[(589, 230)], &[(591, 100)])), 220);
+        // the level was eaten through
+        assert_eq!(queue.update(&book(&[(588, 100)], &[(589, 20)])), 0);
+
+        let queue = Queue::new(&start, OrderDirection::Sell, price(590), 10);
+        assert_eq!(queue.ahead(), 0);
+    }
+}
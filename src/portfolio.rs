@@ -0,0 +1,292 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    time::{Duration, Instant}
+};
+use serde::Serialize;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::balance::{Balance, Money};
+use crate::error::{Error, Recovery, Result};
+use crate::grpc::{Backoff, Broker};
+use crate::tcs::{
+    MoneyValue, PortfolioRequest, PortfolioResponse, PortfolioStreamRequest, PositionData,
+    PositionsRequest, PositionsResponse, PositionsStreamRequest, Quotation,
+    WithdrawLimitsResponse, portfolio_stream_response, positions_stream_response
+};
+
+
+/// An instrument on the account.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Holding {
+    pub figi: String,
+    /// `share`, `bond`, `etf`, `futures`, `option`, ...
+    pub instrument_type: String,
+    /// Pieces free to sell.
+    pub available: i64,
+    /// Pieces reserved by active orders.
+    pub blocked: i64,
+    pub average_price: Option<Money>,
+    pub current_price: Option<Money>,
+    pub expected_yield: Quotation,
+}
+
+impl Holding {
+    pub fn quantity(&self) -> i64 {
+        self.available + self.blocked
+    }
+}
+
+/// The account as the broker sees it: positions come from `PositionsStream`,
+/// prices and yields from `PortfolioStream`.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Portfolio {
+    /// By instrument uid.
+    pub holdings: BTreeMap<String, Holding>,
+    pub money: Balance,
+    pub total: Option<Money>,
+    pub expected_yield: Quotation,
+    /// No snapshot has come yet.
+    pub loading: bool,
+}
+
+impl Portfolio {
+    fn new() -> Portfolio {
+        Portfolio { loading: true, ..Portfolio::default() }
+    }
+
+    /// Pieces of the instrument, blocked ones included.
+    pub fn quantity(&self, uid: &str) -> i64 {
+        self.holdings.get(uid).map(Holding::quantity).unwrap_or_default()
+    }
+
+    /// Replaces the positions with the `GetPositions` answer.
+    pub fn set_positions(&mut self, positions: &PositionsResponse) {
+        for holding in self.holdings.values_mut() {
+            holding.available = 0;
+            holding.blocked = 0;
+        }
+        self.money = Balance::new(positions, &WithdrawLimitsResponse::default());
+        self.update_positions(&PositionData {
+            securities: positions.securities.clone(),
+            futures: positions.futures.clone(),
+            options: positions.options.clone(),
+            ..PositionData::default()
+        });
+    }
+
+    /// Applies a change from `PositionsStream`, it lists only the changed positions.
+    pub fn update_positions(&mut self, data: &PositionData) {
+        let securities = data.securities.iter()
+            .map(|s| (&s.instrument_uid, &s.figi, s.instrument_type.as_str(), s.balance, s.blocked));
+        let futures = data.futures.iter()
+            .map(|f| (&f.instrument_uid, &f.figi, "futures", f.balance, f.blocked));
+        let empty = String::new();
+        let options = data.options.iter()
+            .map(|o| (&o.instrument_uid, &empty, "option", o.balance, o.blocked));
+        for (uid, figi, kind, available, blocked) in securities.chain(futures).chain(options) {
+            let holding = self.holdings.entry(uid.clone()).or_default();
+            holding.figi = figi.clone();
+            holding.instrument_type = kind.to_string();
+            holding.available = available;
+            holding.blocked = blocked;
+        }
+        for money in &data.money {
+            let value = |m: &Option<MoneyValue>| m.as_ref().map(Money::from);
+            let (available, blocked) = (value(&money.available_value), value(&money.blocked_value));
+            let Some(currency) = available.as_ref().or(blocked.as_ref()).map(|m| m.currency.clone()) else {
+                continue;
+            };
+            let balance = self.money.currencies.entry(currency).or_default();
+            balance.available = available.map(|m| m.value).unwrap_or_default();
+            balance.blocked = blocked.map(|m| m.value).unwrap_or_default();
+        }
+        self.holdings.retain(|_, holding| holding.quantity() != 0);
+        self.loading = false;
+    }
+
+    /// Takes the prices and yields from `GetPortfolio` or `PortfolioStream`,
+    /// the quantities stay as the positions tell.
+    pub fn update_valuation(&mut self, portfolio: &PortfolioResponse) {
+        for position in &portfolio.positions {
+            if let Some(holding) = self.holdings.get_mut(&position.instrument_uid) {
+                holding.average_price = position.average_position_price.as_ref().map(Money::from);
+                holding.current_price = position.current_price.as_ref().map(Money::from);
+                holding.expected_yield = position.expected_yield.clone().unwrap_or_default();
+            }
+        }
+        self.total = portfolio.total_amount_portfolio.as_ref().map(Money::from);
+        self.expected_yield = portfolio.expected_yield.clone().unwrap_or_default();
+    }
+}
+
+impl Display for Portfolio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.loading {
+            return write!(f, "Портфель загружается");
+        }
+        write!(f, "Портфель")?;
+        if let Some(total) = &self.total {
+            write!(f, ": {}, доходность {}%", total, self.expected_yield)?;
+        }
+        write!(f, "\nДеньги: {}", self.money)?;
+        for holding in self.holdings.values() {
+            write!(f, "\n{} ({}): {} шт.", holding.figi, holding.instrument_type, holding.quantity())?;
+            if holding.blocked != 0 {
+                write!(f, ", заблокировано {}", holding.blocked)?;
+            }
+            if let Some(price) = &holding.average_price {
+                write!(f, ", средняя {}", price)?;
+            }
+            if let Some(price) = &holding.current_price {
+                write!(f, ", текущая {}", price)?;
+            }
+            write!(f, ", доход {}", holding.expected_yield)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the portfolio of the account up to date while anyone listens.
+/// The streams are reopened on errors, each time with a fresh snapshot.
+pub fn watch(broker: Broker, account_id: String) -> watch::Receiver<Portfolio> {
+    let (tx, rx) = watch::channel(Portfolio::new());
+    tokio::spawn(async move {
+        let backoff = Backoff { attempts: u32::MAX, ..Backoff::default() };
+        let mut attempt = 0;
+        loop {
+            let started = Instant::now();
+            let err = match follow(&broker, &account_id, &tx).await {
+                Ok(()) => return,
+                Err(err) => err,
+            };
+            if err.recovery() == Recovery::Fatal {
+                error!(account = %account_id, error = %err, "portfolio streams stopped");
+                return;
+            }
+            attempt = if started.elapsed() > STABLE { 1 } else { attempt + 1 };
+            let pause = backoff.pause(attempt);
+            warn!(account = %account_id, error = %err, ?pause, "portfolio streams broke");
+            tokio::time::sleep(pause).await;
+        }
+    });
+    rx
+}
+
+/// Streams that lived that long count as working, the backoff starts over.
+const STABLE: Duration = Duration::from_secs(60);
+
+/// Returns `Ok` when nobody listens anymore.
+async fn follow(broker: &Broker, account_id: &str, tx: &watch::Sender<Portfolio>) -> Result<()> {
+    let accounts = vec![account_id.to_string()];
+    let mut positions = broker.positions_stream(PositionsStreamRequest { accounts: accounts.clone() })
+        .await?.into_inner();
+    let mut valuations = broker.portfolio_stream(PortfolioStreamRequest { accounts })
+        .await?.into_inner();
+    // after subscribing, so no change falls in between
+    let snapshot = broker.get_positions(PositionsRequest { account_id: account_id.to_string() })
+        .await?.into_inner();
+    let valuation = broker.get_portfolio(PortfolioRequest { account_id: account_id.to_string(), currency: 0 })
+        .await?.into_inner();
+    tx.send_modify(|portfolio| {
+        portfolio.set_positions(&snapshot);
+        portfolio.update_valuation(&valuation);
+    });
+    info!(account = %account_id, "portfolio streams opened");
+
+    loop {
+        tokio::select! {
+            message = positions.message() => {
+                match message?.and_then(|m| m.payload) {
+                    Some(positions_stream_response::Payload::Position(data)) =>
+                        tx.send_modify(|portfolio| portfolio.update_positions(&data)),
+                    Some(_) => {},
+                    None => return Err(Error::Transport("positions stream ended".to_string())),
+                }
+            },
+            message = valuations.message() => {
+                match message?.and_then(|m| m.payload) {
+                    Some(portfolio_stream_response::Payload::Portfolio(valuation)) =>
+                        tx.send_modify(|portfolio| portfolio.update_valuation(&valuation)),
+                    Some(_) => {},
+                    None => return Err(Error::Transport("portfolio stream ended".to_string())),
+                }
+            },
+            _ = tx.closed() => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcs::{PortfolioPosition, PositionsMoney, PositionsSecurities};
+
+    fn rub(units: i64) -> MoneyValue {
+        MoneyValue { currency: "rub".to_string(), units, nano: 0 }
+    }
+    fn security(uid: &str, balance: i64, blocked: i64) -> PositionsSecurities {
+        PositionsSecurities {
+            figi: format!("figi-{}", uid),
+            instrument_uid: uid.to_string(),
+            instrument_type: "etf".to_string(),
+            balance,
+            blocked,
+            ..PositionsSecurities::default()
+        }
+    }
+
+    #[test]
+    fn snapshot_and_changes() {
+        let mut portfolio = Portfolio::new();
+        assert_eq!(portfolio.to_string(), "Портфель загружается");
+        portfolio.set_positions(&PositionsResponse {
+            money: vec![rub(1000)],
+            securities: vec![security("trur", 10, 2), security("tmos", 5, 0)],
+            ..PositionsResponse::default()
+        });
+        assert!(!portfolio.loading);
+        assert_eq!(portfolio.quantity("trur"), 12);
+
+        portfolio.update_valuation(&PortfolioResponse {
+            positions: vec![PortfolioPosition {
+                instrument_uid: "trur".to_string(),
+                average_position_price: Some(MoneyValue { currency: "rub".to_string(), units: 5, nano: 90_0000000 }),
+                expected_yield: Some(Quotation { units: 1, nano: 20_0000000 }),
+                ..PortfolioPosition::default()
+            }, PortfolioPosition {
+                // not among the positions yet
+                instrument_uid: "sber".to_string(),
+                ..PortfolioPosition::default()
+            }],
+            total_amount_portfolio: Some(rub(1100)),
+            ..PortfolioResponse::default()
+        });
+        assert_eq!(portfolio.holdings["trur"].average_price.as_ref().unwrap().to_string(), "5.9 RUB");
+        assert!(!portfolio.holdings.contains_key("sber"));
+
+        // only the changed positions come
+        portfolio.update_positions(&PositionData {
+            money: vec![PositionsMoney { available_value: Some(rub(970)), blocked_value: Some(rub(30)) }],
+            securities: vec![security("tmos", 0, 0), security("sber", 1, 0)],
+            ..PositionData::default()
+        });
+        assert_eq!(portfolio.quantity("trur"), 12);
+        assert!(!portfolio.holdings.contains_key("tmos"));
+        assert_eq!(portfolio.quantity("sber"), 1);
+        assert_eq!(portfolio.money.currencies["rub"].blocked, Quotation { units: 30, nano: 0 });
+        assert_eq!(portfolio.to_string(), "Портфель: 1100 RUB, доходность 0%\
+            \nДеньги: 970 RUB (заблокировано 30)\
+            \nfigi-sber (etf): 1 шт., доход 0\
+            \nfigi-trur (etf): 12 шт., заблокировано 2, средняя 5.9 RUB, доход 1.2");
+
+        // a new snapshot drops what is gone
+        portfolio.set_positions(&PositionsResponse {
+            securities: vec![security("trur", 12, 0)],
+            ..PositionsResponse::default()
+        });
+        assert_eq!(portfolio.holdings.keys().collect::<Vec<_>>(), vec!["trur"]);
+        assert!(portfolio.money.currencies.is_empty());
+    }
+}
@@ -0,0 +1,1192 @@
+//! Replays recorded order books and trades (see `history::record`) to the real `Bot`:
+//! a simulated exchange serves the broker API on a local port, so the bot runs
+//! the same code as with the broker, partial fills and the shutdown included.
+//!
+//! The fill model: a limit order waits `Params::latency` before it reaches the book,
+//! takes the liquidity it crosses and joins the queue at its price with the lots
+//! of the snapshot ahead. Trades at the price eat the queue first, trades through
+//! the price fill the order. The clock moves one snapshot with every `GetOrderBook`.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    net::SocketAddr,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::Europe::Moscow;
+use futures::Stream;
+use prost_types::Timestamp;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tonic::{Request, Response, Status, transport::Server};
+
+use crate::accounts::Account;
+use crate::balance::Wallet;
+use crate::bot::Bot;
+use crate::connection::{Connection, ConnectionSettings, Endpoint};
+use crate::credentials::{Credentials, Secret};
+use crate::dashboard::Dashboard;
+use crate::error::{Error, Result};
+use crate::grpc::Broker;
+use crate::history::{self, BookRecord};
+use crate::notify::{Notifier, NotifySettings};
+use crate::orderbook::{Book, Level, Queue};
+use crate::portfolio;
+use crate::shutdown::{ShutdownPolicy, StopReason};
+use crate::strategies::Strategy;
+use crate::tcs::{
+    AccountType, BrokerReportRequest, BrokerReportResponse, CancelOrderRequest,
+    CancelOrderResponse, GetCandlesRequest, GetCandlesResponse, GetClosePricesRequest,
+    GetClosePricesResponse, GetDividendsForeignIssuerRequest, GetDividendsForeignIssuerResponse,
+    GetLastPricesRequest, GetLastPricesResponse, GetLastTradesRequest, GetLastTradesResponse,
+    GetOperationsByCursorRequest, GetOperationsByCursorResponse, GetOrderBookRequest,
+    GetOrderBookResponse, GetOrderStateRequest, GetOrdersRequest, GetOrdersResponse,
+    GetTradingStatusRequest, GetTradingStatusResponse, LastPrice, MoneyValue, OperationsRequest,
+    OperationsResponse, Order as BookOrder, OrderDirection, OrderExecutionReportStatus, OrderStage,
+    OrderState, OrderType, PortfolioPosition, PortfolioRequest, PortfolioResponse,
+    PortfolioStreamRequest, PortfolioStreamResponse, PositionData, PositionsMoney, PositionsRequest,
+    PositionsResponse, PositionsSecurities, PositionsStreamRequest, PositionsStreamResponse,
+    PostOrderRequest, PostOrderResponse, Quotation, ReplaceOrderRequest, Trade,
+    WithdrawLimitsRequest, WithdrawLimitsResponse,
+    market_data_service_server::{MarketDataService, MarketDataServiceServer},
+    operations_service_server::{OperationsService, OperationsServiceServer},
+    operations_stream_service_server::{OperationsStreamService, OperationsStreamServiceServer},
+    orders_service_server::{OrdersService, OrdersServiceServer},
+    portfolio_stream_response, positions_stream_response
+};
+
+
+/// The id of the replayed account.
+pub const ACCOUNT: &str = "replay";
+/// The statistics of the replays by hand go to `replays/<strategy>`.
+pub const DIR: &str = "replays";
+
+const NANO: i128 = 1_000_000_000;
+
+/// Recorded market data of one instrument, by time.
+#[derive(Debug, Clone, Default)]
+pub struct Tape {
+    pub uid: String,
+    /// Unix milliseconds and the snapshot.
+    pub books: Vec<(i64, GetOrderBookResponse)>,
+    pub trades: Vec<Trade>,
+}
+
+impl Tape {
+    /// The days `from..=to` of the instrument recorded to `dir`, the missing days are skipped.
+    pub fn load(dir: &Path, uid: &str, from: NaiveDate, to: NaiveDate) -> Result<Tape> {
+        let mut tape = Tape { uid: uid.to_string(), ..Tape::default() };
+        for day in from.iter_days().take_while(|day| day <= &to) {
+            let books = read(&history::record_file(dir, uid, "orderbook", day, "jsonl"))?;
+            for line in books.lines().filter(|line| !line.is_empty()) {
+                let record: BookRecord = serde_json::from_str(line)
+                    .map_err(|err| Error::persistence("orderbook", err))?;
+                tape.books.push((record.time, record.to_response()));
+            }
+            let trades = read(&history::record_file(dir, uid, "trades", day, "csv"))?;
+            for line in trades.lines().filter(|line| !line.is_empty()) {
+                let trade = history::parse_trade(line)
+                    .ok_or_else(|| Error::persistence("trades", format!("bad line {}", line)))?;
+                tape.trades.push(trade);
+            }
+        }
+        if tape.books.is_empty() {
+            return Err(Error::Config(format!("no order books of {} recorded for {}..{}", uid, from, to)));
+        }
+        tape.books.sort_by_key(|(time, _)| *time);
+        tape.trades.sort_by_key(|trade| history::millis(&trade.time));
+        Ok(tape)
+    }
+}
+
+impl Tape {
+    /// Moscow days with order books, in order.
+    pub fn days(&self) -> Vec<NaiveDate> {
+        let mut days: Vec<_> = self.books.iter().map(|(time, _)| day(*time)).collect();
+        days.dedup();
+        days
+    }
+
+    /// The part of the tape of the days `from..=to`.
+    pub fn between(&self, from: NaiveDate, to: NaiveDate) -> Tape {
+        let within = |time: i64| (from..=to).contains(&day(time));
+        Tape {
+            uid: self.uid.clone(),
+            books: self.books.iter().filter(|(time, _)| within(*time)).cloned().collect(),
+            trades: self.trades.iter().filter(|trade| within(history::millis(&trade.time))).cloned().collect(),
+        }
+    }
+}
+
+fn day(millis: i64) -> NaiveDate {
+    Utc.timestamp_millis_opt(millis).single()
+        .map(|time| time.with_timezone(&Moscow).date_naive())
+        .unwrap_or_default()
+}
+
+/// The file, empty if there is none.
+fn read(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Params {
+    /// From placing an order to its arrival at the book.
+    pub latency: Duration,
+    /// Of the order value, taken with every execution.
+    pub fee_rate: Quotation,
+    /// Money of the account at the start.
+    pub money: Quotation,
+    pub currency: String,
+}
+
+/// An execution of a simulated order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    /// Unix milliseconds of the tape.
+    pub time: i64,
+    pub order_id: String,
+    pub direction: OrderDirection,
+    pub price: Quotation,
+    pub lots: i64,
+    /// Stood in the book, otherwise took the liquidity.
+    pub passive: bool,
+    pub commission: Quotation,
+}
+
+#[derive(Debug)]
+enum Stage {
+    /// On the way to the book.
+    Waiting,
+    /// In the book, market orders have no queue.
+    Working(Option<Queue>),
+    Filled,
+    Cancelled,
+}
+
+#[derive(Debug)]
+struct SimOrder {
+    id: String,
+    figi: String,
+    direction: OrderDirection,
+    order_type: OrderType,
+    /// None for market orders.
+    price: Option<Quotation>,
+    lots: i64,
+    /// Unix milliseconds.
+    placed: i64,
+    stages: Vec<(Quotation, i64)>,
+    commission: i128,
+    status: Stage,
+}
+
+impl SimOrder {
+    fn executed(&self) -> i64 {
+        self.stages.iter().map(|(_, lots)| lots).sum()
+    }
+
+    fn rest(&self) -> i64 {
+        self.lots - self.executed()
+    }
+
+    fn active(&self) -> bool {
+        matches!(self.status, Stage::Waiting | Stage::Working(_))
+    }
+
+    fn buy(&self) -> bool {
+        self.direction == OrderDirection::Buy
+    }
+
+    fn report_status(&self) -> OrderExecutionReportStatus {
+        match self.status {
+            Stage::Filled => OrderExecutionReportStatus::ExecutionReportStatusFill,
+            Stage::Cancelled => OrderExecutionReportStatus::ExecutionReportStatusCancelled,
+            _ if self.executed() > 0 => OrderExecutionReportStatus::ExecutionReportStatusPartiallyfill,
+            _ => OrderExecutionReportStatus::ExecutionReportStatusNew,
+        }
+    }
+}
+
+/// The simulated exchange and the account on it, driven by the tape.
+#[derive(Debug)]
+pub struct Exchange {
+    tape: Tape,
+    params: Params,
+    /// Index of the current snapshot.
+    current: usize,
+    /// The first `GetOrderBook` shows the first snapshot, the next ones move on.
+    shown: bool,
+    finished: bool,
+    /// The current snapshot less the liquidity taken by our orders.
+    book: Book,
+    /// Unix milliseconds of the current snapshot.
+    now: i64,
+    /// Index of the next trade of the tape.
+    trade: usize,
+    orders: Vec<SimOrder>,
+    cash: i128,
+    position: i64,
+    /// Average price of the position times its size, in nanos.
+    cost: i128,
+    executions: Vec<Execution>,
+    /// Grows with every change of the account.
+    version: u64,
+}
+
+impl Exchange {
+    pub fn new(tape: Tape, params: Params) -> Result<Exchange> {
+        let Some((now, first)) = tape.books.first().cloned() else {
+            return Err(Error::Config("the tape has no order books".to_string()));
+        };
+        let trade = tape.trades.iter().take_while(|t| history::millis(&t.time) <= now).count();
+        let cash = params.money.to_nanos();
+        Ok(Exchange {
+            tape,
+            params,
+            current: 0,
+            shown: false,
+            finished: false,
+            book: Book::from(&first),
+            now,
+            trade,
+            orders: Vec::new(),
+            cash,
+            position: 0,
+            cost: 0,
+            executions: Vec::new(),
+            version: 0,
+        })
+    }
+
+    /// The tape is over, the last snapshot stays.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The next snapshot with our liquidity taken out, the orders are matched on the way.
+    pub fn order_book(&mut self, depth: i32) -> GetOrderBookResponse {
+        if self.shown {
+            self.advance();
+        }
+        self.shown = true;
+        let orders = |levels: &[Level]| levels.iter()
+            .take(depth.max(1) as usize)
+            .map(|level| BookOrder { price: Some(level.price.clone()), quantity: level.quantity })
+            .collect();
+        GetOrderBookResponse {
+            figi: String::new(),
+            depth,
+            bids: orders(&self.book.bids),
+            asks: orders(&self.book.asks),
+            last_price: self.last_price(),
+            orderbook_ts: Some(timestamp(self.now)),
+            instrument_uid: self.tape.uid.clone(),
+            ..GetOrderBookResponse::default()
+        }
+    }
+
+    fn advance(&mut self) {
+        let Some((time, snapshot)) = self.tape.books.get(self.current + 1).cloned() else {
+            self.finished = true;
+            // nothing comes anymore, the orders on the way arrive right away
+            self.activate(self.now);
+            return;
+        };
+        self.current += 1;
+        while let Some(trade) = self.tape.trades.get(self.trade).cloned() {
+            let at = history::millis(&trade.time);
+            if at > time {
+                break;
+            }
+            self.trade += 1;
+            self.activate(at);
+            self.on_trade(&trade);
+        }
+        self.now = time;
+        self.book = Book::from(&snapshot);
+        for order in &mut self.orders {
+            if let Stage::Working(Some(queue)) = &mut order.status {
+                queue.update(&self.book);
+            }
+        }
+        self.activate(time);
+        self.cross();
+    }
+
+    /// Orders that reach the book by `time`.
+    fn activate(&mut self, time: i64) {
+        let latency = self.params.latency.as_millis() as i64;
+        for i in 0..self.orders.len() {
+            let order = &self.orders[i];
+            if matches!(order.status, Stage::Waiting) && (order.placed + latency <= time || self.finished) {
+                self.arrive(i);
+            }
+        }
+    }
+
+    fn arrive(&mut self, i: usize) {
+        let price = self.orders[i].price.clone();
+        self.take(i, price.as_ref());
+        if !self.orders[i].active() {
+            return;
+        }
+        match price {
+            Some(price) => {
+                // the recorded books don't have the order, so none of its lots are in them
+                let queue = Queue::new(&self.book, self.orders[i].direction, price, 0);
+                self.orders[i].status = Stage::Working(Some(queue));
+            },
+            None => {
+                // the depth is taken as enough for market orders, the rest goes at the last price
+                let order = &self.orders[i];
+                let price = order.stages.last().map(|(price, _)| price.clone()).or_else(|| self.last_price());
+                match price {
+                    Some(price) => self.fill(i, price, order.rest(), false),
+                    None => self.orders[i].status = Stage::Working(None),
+                }
+            },
+        }
+    }
+
+    /// Takes the liquidity of the other side up to `limit`.
+    fn take(&mut self, i: usize, limit: Option<&Quotation>) {
+        let buy = self.orders[i].buy();
+        let mut rest = self.orders[i].rest();
+        let side = if buy { &mut self.book.asks } else { &mut self.book.bids };
+        let mut fills = Vec::new();
+        for level in side.iter_mut() {
+            let within = match limit {
+                None => true,
+                Some(limit) if buy => &level.price <= limit,
+                Some(limit) => &level.price >= limit,
+            };
+            if rest == 0 || !within {
+                break;
+            }
+            let lots = rest.min(level.quantity);
+            level.quantity -= lots;
+            rest -= lots;
+            fills.push((level.price.clone(), lots));
+        }
+        side.retain(|level| level.quantity > 0);
+        for (price, lots) in fills.into_iter().filter(|(_, lots)| *lots > 0) {
+            self.fill(i, price, lots, false);
+        }
+    }
+
+    /// A trade of the tape: it eats the queues at its price and fills the orders it passes.
+    fn on_trade(&mut self, trade: &Trade) {
+        let Some(price) = trade.price.clone() else {
+            return;
+        };
+        let mut left = trade.quantity;
+        for i in 0..self.orders.len() {
+            let order = &mut self.orders[i];
+            let rest = order.rest();
+            let buy = order.buy();
+            let (Stage::Working(Some(queue)), Some(own)) = (&mut order.status, order.price.clone()) else {
+                continue;
+            };
+            let passed = if buy { price < own } else { price > own };
+            let reach = if passed {
+                left
+            } else if price == own {
+                queue.trade(left)
+            } else {
+                0
+            };
+            let lots = reach.min(rest);
+            if lots > 0 {
+                left -= lots;
+                self.fill(i, own, lots, true);
+            }
+        }
+    }
+
+    /// The book moved through the orders: they take what is there.
+    fn cross(&mut self) {
+        for i in 0..self.orders.len() {
+            if let (Stage::Working(_), Some(price)) = (&self.orders[i].status, self.orders[i].price.clone()) {
+                self.take(i, Some(&price));
+            }
+        }
+    }
+
+    fn fill(&mut self, i: usize, price: Quotation, lots: i64, passive: bool) {
+        let value = price.to_nanos() * lots as i128;
+        let commission = value * self.params.fee_rate.to_nanos() / NANO;
+        let order = &mut self.orders[i];
+        order.stages.push((price.clone(), lots));
+        order.commission += commission;
+        if order.rest() == 0 {
+            order.status = Stage::Filled;
+        }
+        let sign: i64 = if order.buy() { 1 } else { -1 };
+        self.cash -= sign as i128 * value + commission;
+
+        let before = self.position;
+        self.position += sign * lots;
+        if before == 0 || before.signum() == sign {
+            self.cost += value;
+        } else {
+            let closed = lots.min(before.abs());
+            self.cost -= self.cost * closed as i128 / before.abs() as i128;
+            if lots > closed {
+                // the position turned over
+                self.cost = price.to_nanos() * (lots - closed) as i128;
+            }
+        }
+        self.executions.push(Execution {
+            time: self.now,
+            order_id: order.id.clone(),
+            direction: order.direction,
+            price,
+            lots,
+            passive,
+            commission: Quotation::from_nanos(commission),
+        });
+        self.version += 1;
+    }
+
+    fn last_price(&self) -> Option<Quotation> {
+        self.tape.trades[..self.trade].last().and_then(|trade| trade.price.clone())
+    }
+
+    /// Money reserved by the buy orders.
+    fn blocked(&self) -> i128 {
+        self.orders.iter()
+            .filter(|order| order.active() && order.buy())
+            .filter_map(|order| Some(order.price.as_ref()?.to_nanos() * order.rest() as i128))
+            .sum()
+    }
+
+    /// Lots of the position reserved by the sell orders.
+    fn blocked_lots(&self) -> i64 {
+        let selling: i64 = self.orders.iter()
+            .filter(|order| order.active() && !order.buy())
+            .map(SimOrder::rest)
+            .sum();
+        selling.min(self.position.max(0))
+    }
+
+    #[allow(clippy::result_large_err)] // the errors go to the services as they are
+    pub fn post(&mut self, request: &PostOrderRequest) -> std::result::Result<OrderState, Status> {
+        if request.instrument_id != self.tape.uid {
+            return Err(Status::not_found(format!("no tape of {}", request.instrument_id)));
+        }
+        if request.quantity <= 0 {
+            return Err(Status::invalid_argument("quantity must be positive"));
+        }
+        let order_type = OrderType::from_i32(request.order_type).unwrap_or(OrderType::Unspecified);
+        let price = match order_type {
+            OrderType::Market => None,
+            _ => Some(request.price.clone().ok_or_else(|| Status::invalid_argument("no price"))?),
+        };
+        let direction = OrderDirection::from_i32(request.direction).unwrap_or(OrderDirection::Unspecified);
+        if direction == OrderDirection::Buy {
+            let value = price.clone().or_else(|| self.book.best_ask().map(|level| level.price.clone()))
+                .map_or(0, |price| price.to_nanos() * request.quantity as i128);
+            if value > self.cash - self.blocked() {
+                return Err(Status::failed_precondition("30042 not enough money"));
+            }
+        }
+        self.orders.push(SimOrder {
+            id: format!("R{}", self.orders.len() + 1),
+            figi: request.figi.clone(),
+            direction,
+            order_type,
+            price,
+            lots: request.quantity,
+            placed: self.now,
+            stages: Vec::new(),
+            commission: 0,
+            status: Stage::Waiting,
+        });
+        self.version += 1;
+        self.activate(self.now);
+        Ok(self.state(self.orders.last().unwrap()))
+    }
+
+    #[allow(clippy::result_large_err)] // the errors go to the services as they are
+    pub fn cancel(&mut self, order_id: &str) -> std::result::Result<(), Status> {
+        let order = self.orders.iter_mut()
+            .find(|order| order.id == order_id)
+            .ok_or_else(|| Status::not_found(format!("no order {}", order_id)))?;
+        if !order.active() {
+            return Err(Status::failed_precondition(format!("order {} is not active", order_id)));
+        }
+        order.status = Stage::Cancelled;
+        self.version += 1;
+        Ok(())
+    }
+
+    pub fn order(&self, order_id: &str) -> Option<OrderState> {
+        self.orders.iter().find(|order| order.id == order_id).map(|order| self.state(order))
+    }
+
+    pub fn active_orders(&self) -> Vec<OrderState> {
+        self.orders.iter().filter(|order| order.active()).map(|order| self.state(order)).collect()
+    }
+
+    fn money(&self, nanos: i128) -> Option<MoneyValue> {
+        let Quotation { units, nano } = Quotation::from_nanos(nanos);
+        Some(MoneyValue { currency: self.params.currency.clone(), units, nano })
+    }
+
+    fn state(&self, order: &SimOrder) -> OrderState {
+        let executed: i128 = order.stages.iter().map(|(price, lots)| price.to_nanos() * *lots as i128).sum();
+        let average = Quotation::average(&order.stages);
+        let price = order.price.clone().unwrap_or_else(|| average.clone()).to_nanos();
+        let fee = self.params.fee_rate.to_nanos();
+        let sign = if order.buy() { 1 } else { -1 };
+        OrderState {
+            order_id: order.id.clone(),
+            execution_report_status: order.report_status().into(),
+            lots_requested: order.lots,
+            lots_executed: order.executed(),
+            initial_order_price: self.money(price * order.lots as i128),
+            executed_order_price: self.money(executed),
+            total_order_amount: self.money(executed + sign * order.commission),
+            average_position_price: self.money(average.to_nanos()),
+            initial_commission: self.money(price * order.lots as i128 * fee / NANO),
+            executed_commission: self.money(order.commission),
+            figi: order.figi.clone(),
+            direction: order.direction.into(),
+            initial_security_price: self.money(price),
+            stages: order.stages.iter().enumerate()
+                .map(|(n, (price, lots))| OrderStage {
+                    price: self.money(price.to_nanos()),
+                    quantity: *lots,
+                    trade_id: format!("{}-{}", order.id, n + 1),
+                })
+                .collect(),
+            currency: self.params.currency.clone(),
+            order_type: order.order_type.into(),
+            order_date: Some(timestamp(order.placed)),
+            instrument_uid: self.tape.uid.clone(),
+            ..OrderState::default()
+        }
+    }
+
+    fn security(&self) -> PositionsSecurities {
+        let blocked = self.blocked_lots();
+        PositionsSecurities {
+            instrument_uid: self.tape.uid.clone(),
+            instrument_type: "share".to_string(),
+            balance: self.position - blocked,
+            blocked,
+            ..PositionsSecurities::default()
+        }
+    }
+
+    pub fn positions(&self) -> PositionsResponse {
+        let blocked = self.blocked();
+        PositionsResponse {
+            money: self.money(self.cash - blocked).into_iter().collect(),
+            blocked: self.money(blocked).into_iter().collect(),
+            securities: if self.position == 0 { Vec::new() } else { vec![self.security()] },
+            ..PositionsResponse::default()
+        }
+    }
+
+    /// A change for `PositionsStream`, the instrument is always there to drop a closed position.
+    pub fn position_data(&self) -> PositionData {
+        let blocked = self.blocked();
+        PositionData {
+            money: vec![PositionsMoney {
+                available_value: self.money(self.cash - blocked),
+                blocked_value: self.money(blocked),
+            }],
+            securities: vec![self.security()],
+            date: Some(timestamp(self.now)),
+            ..PositionData::default()
+        }
+    }
+
+    pub fn portfolio(&self) -> PortfolioResponse {
+        let mark = self.book.mid().map(|mid| (mid * 1e9) as i128)
+            .or_else(|| self.last_price().map(|price| price.to_nanos()))
+            .unwrap_or_default();
+        let value = mark * self.position as i128;
+        let positions = if self.position == 0 {
+            Vec::new()
+        } else {
+            vec![PortfolioPosition {
+                instrument_uid: self.tape.uid.clone(),
+                instrument_type: "share".to_string(),
+                quantity: Some(Quotation { units: self.position, nano: 0 }),
+                average_position_price: self.money(self.cost / self.position.abs() as i128),
+                current_price: self.money(mark),
+                expected_yield: Some(Quotation::from_nanos(value - self.cost * self.position.signum() as i128)),
+                ..PortfolioPosition::default()
+            }]
+        };
+        PortfolioResponse {
+            total_amount_portfolio: self.money(self.cash + value),
+            positions,
+            ..PortfolioResponse::default()
+        }
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            orders: self.orders.len(),
+            executions: self.executions.clone(),
+            commission: Quotation::from_nanos(self.orders.iter().map(|order| order.commission).sum()),
+            money: (self.params.money.clone(), Quotation::from_nanos(self.cash)),
+            position: self.position,
+            currency: self.params.currency.clone(),
+        }
+    }
+}
+
+fn timestamp(millis: i64) -> Timestamp {
+    Timestamp { seconds: millis.div_euclid(1000), nanos: (millis.rem_euclid(1000) * 1_000_000) as i32 }
+}
+
+/// What a replay came to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub orders: usize,
+    pub executions: Vec<Execution>,
+    pub commission: Quotation,
+    /// At the start and at the end.
+    pub money: (Quotation, Quotation),
+    /// Lots left at the end.
+    pub position: i64,
+    pub currency: String,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let passive = self.executions.iter().filter(|e| e.passive).count();
+        let currency = self.currency.to_uppercase();
+        write!(f, "{} orders, {} executions ({} passive), commission {} {}, money {} -> {} {}, position {}",
+               self.orders, self.executions.len(), passive, self.commission, currency,
+               self.money.0, self.money.1, currency, self.position)
+    }
+}
+
+/// Serves the broker API from the exchange.
+#[derive(Clone)]
+pub struct Simulator {
+    exchange: Arc<Mutex<Exchange>>,
+    /// Wakes the portfolio streams.
+    changes: broadcast::Sender<()>,
+    finished: Arc<watch::Sender<bool>>,
+}
+
+type Updates<T> = Pin<Box<dyn Stream<Item = std::result::Result<T, Status>> + Send>>;
+
+impl Simulator {
+    pub fn new(exchange: Exchange) -> Simulator {
+        Simulator {
+            exchange: Arc::new(Mutex::new(exchange)),
+            changes: broadcast::channel(16).0,
+            finished: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// Turns `true` when the tape is over.
+    pub fn finished(&self) -> watch::Receiver<bool> {
+        self.finished.subscribe()
+    }
+
+    pub fn report(&self) -> Report {
+        self.exchange.lock().unwrap().report()
+    }
+
+    /// Runs `f` on the exchange and tells the streams if the account changed.
+    fn with<T>(&self, f: impl FnOnce(&mut Exchange) -> T) -> T {
+        let mut exchange = self.exchange.lock().unwrap();
+        let version = exchange.version;
+        let result = f(&mut exchange);
+        if exchange.version != version {
+            let _ = self.changes.send(());
+        }
+        let finished = exchange.finished();
+        self.finished.send_if_modified(|value| std::mem::replace(value, finished) != finished);
+        result
+    }
+
+    /// Sends a snapshot of `make` on every change of the account.
+    fn stream<T: Send + 'static>(&self, make: fn(&Exchange) -> T) -> Updates<T> {
+        let exchange = self.exchange.clone();
+        Box::pin(futures::stream::unfold(self.changes.subscribe(), move |mut changes| {
+            let exchange = exchange.clone();
+            async move {
+                // a lag only means several changes, the snapshot covers them
+                if let Err(broadcast::error::RecvError::Closed) = changes.recv().await {
+                    return None;
+                }
+                let message = make(&exchange.lock().unwrap());
+                Some((Ok(message), changes))
+            }
+        }))
+    }
+
+    /// Serves on a free local port until the sender is used or dropped.
+    pub async fn serve(self) -> Result<(SocketAddr, oneshot::Sender<()>)> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let incoming = futures::stream::unfold(listener, |listener| async move {
+            let accepted = listener.accept().await.map(|(stream, _)| stream);
+            Some((accepted, listener))
+        });
+        let (stop, stopped) = oneshot::channel::<()>();
+        tokio::spawn(Server::builder()
+            .add_service(MarketDataServiceServer::new(self.clone()))
+            .add_service(OrdersServiceServer::new(self.clone()))
+            .add_service(OperationsServiceServer::new(self.clone()))
+            .add_service(OperationsStreamServiceServer::new(self))
+            .serve_with_incoming_shutdown(incoming, async {
+                let _ = stopped.await;
+            }));
+        Ok((addr, stop))
+    }
+}
+
+/// The quotas don't apply to the replay.
+#[allow(clippy::result_large_err)]
+fn answer<T>(message: T) -> std::result::Result<Response<T>, Status> {
+    let mut response = Response::new(message);
+    response.metadata_mut().insert("x-ratelimit-limit", "1000000".parse().unwrap());
+    Ok(response)
+}
+
+#[tonic::async_trait]
+#[allow(clippy::result_large_err)]
+impl MarketDataService for Simulator {
+    async fn get_candles(&self, _: Request<GetCandlesRequest>)
+        -> std::result::Result<Response<GetCandlesResponse>, Status> {
+        Err(Status::unimplemented("the replay has no candles"))
+    }
+    async fn get_last_prices(&self, _: Request<GetLastPricesRequest>)
+        -> std::result::Result<Response<GetLastPricesResponse>, Status> {
+        let last_prices = self.with(|exchange| LastPrice {
+            price: exchange.last_price(),
+            time: Some(timestamp(exchange.now)),
+            instrument_uid: exchange.tape.uid.clone(),
+            ..LastPrice::default()
+        });
+        answer(GetLastPricesResponse { last_prices: vec![last_prices] })
+    }
+    async fn get_order_book(&self, request: Request<GetOrderBookRequest>)
+        -> std::result::Result<Response<GetOrderBookResponse>, Status> {
+        let request = request.into_inner();
+        self.with(|exchange| {
+            if request.instrument_id != exchange.tape.uid {
+                return Err(Status::not_found(format!("no tape of {}", request.instrument_id)));
+            }
+            answer(exchange.order_book(request.depth))
+        })
+    }
+    async fn get_trading_status(&self, _: Request<GetTradingStatusRequest>)
+        -> std::result::Result<Response<GetTradingStatusResponse>, Status> {
+        Err(Status::unimplemented("the replay trades all the time"))
+    }
+    async fn get_last_trades(&self, request: Request<GetLastTradesRequest>)
+        -> std::result::Result<Response<GetLastTradesResponse>, Status> {
+        let request = request.into_inner();
+        let from = history::millis(&request.from);
+        let trades = self.with(|exchange| exchange.tape.trades[..exchange.trade].iter()
+            .filter(|trade| history::millis(&trade.time) >= from)
+            .cloned()
+            .collect());
+        answer(GetLastTradesResponse { trades })
+    }
+    async fn get_close_prices(&self, _: Request<GetClosePricesRequest>)
+        -> std::result::Result<Response<GetClosePricesResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+#[tonic::async_trait]
+#[allow(clippy::result_large_err)]
+impl OrdersService for Simulator {
+    async fn post_order(&self, request: Request<PostOrderRequest>)
+        -> std::result::Result<Response<PostOrderResponse>, Status> {
+        let state = self.with(|exchange| exchange.post(request.get_ref()))?;
+        answer(PostOrderResponse {
+            order_id: state.order_id,
+            execution_report_status: state.execution_report_status,
+            lots_requested: state.lots_requested,
+            lots_executed: state.lots_executed,
+            initial_order_price: state.initial_order_price,
+            executed_order_price: state.executed_order_price,
+            total_order_amount: state.total_order_amount,
+            initial_commission: state.initial_commission,
+            executed_commission: state.executed_commission,
+            figi: state.figi,
+            direction: state.direction,
+            initial_security_price: state.initial_security_price,
+            order_type: state.order_type,
+            instrument_uid: state.instrument_uid,
+            ..PostOrderResponse::default()
+        })
+    }
+    async fn cancel_order(&self, request: Request<CancelOrderRequest>)
+        -> std::result::Result<Response<CancelOrderResponse>, Status> {
+        self.with(|exchange| {
+            exchange.cancel(&request.get_ref().order_id)?;
+            answer(CancelOrderResponse { time: Some(timestamp(exchange.now)) })
+        })
+    }
+    async fn get_order_state(&self, request: Request<GetOrderStateRequest>)
+        -> std::result::Result<Response<OrderState>, Status> {
+        let order_id = &request.get_ref().order_id;
+        let state = self.with(|exchange| exchange.order(order_id));
+        answer(state.ok_or_else(|| Status::not_found(format!("no order {}", order_id)))?)
+    }
+    async fn get_orders(&self, _: Request<GetOrdersRequest>)
+        -> std::result::Result<Response<GetOrdersResponse>, Status> {
+        answer(GetOrdersResponse { orders: self.with(|exchange| exchange.active_orders()) })
+    }
+    async fn replace_order(&self, _: Request<ReplaceOrderRequest>)
+        -> std::result::Result<Response<PostOrderResponse>, Status> {
+        Err(Status::unimplemented("cancel and post instead"))
+    }
+}
+
+#[tonic::async_trait]
+impl OperationsService for Simulator {
+    async fn get_operations(&self, _: Request<OperationsRequest>)
+        -> std::result::Result<Response<OperationsResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+    async fn get_portfolio(&self, _: Request<PortfolioRequest>)
+        -> std::result::Result<Response<PortfolioResponse>, Status> {
+        answer(self.with(|exchange| exchange.portfolio()))
+    }
+    async fn get_positions(&self, _: Request<PositionsRequest>)
+        -> std::result::Result<Response<PositionsResponse>, Status> {
+        answer(self.with(|exchange| exchange.positions()))
+    }
+    async fn get_withdraw_limits(&self, _: Request<WithdrawLimitsRequest>)
+        -> std::result::Result<Response<WithdrawLimitsResponse>, Status> {
+        let positions = self.with(|exchange| exchange.positions());
+        answer(WithdrawLimitsResponse {
+            money: positions.money,
+            blocked: positions.blocked,
+            ..WithdrawLimitsResponse::default()
+        })
+    }
+    async fn get_broker_report(&self, _: Request<BrokerReportRequest>)
+        -> std::result::Result<Response<BrokerReportResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+    async fn get_dividends_foreign_issuer(&self, _: Request<GetDividendsForeignIssuerRequest>)
+        -> std::result::Result<Response<GetDividendsForeignIssuerResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+    async fn get_operations_by_cursor(&self, _: Request<GetOperationsByCursorRequest>)
+        -> std::result::Result<Response<GetOperationsByCursorResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+#[tonic::async_trait]
+impl OperationsStreamService for Simulator {
+    type PortfolioStreamStream = Updates<PortfolioStreamResponse>;
+    type PositionsStreamStream = Updates<PositionsStreamResponse>;
+
+    async fn portfolio_stream(&self, _: Request<PortfolioStreamRequest>)
+        -> std::result::Result<Response<Self::PortfolioStreamStream>, Status> {
+        answer(self.stream(|exchange| PortfolioStreamResponse {
+            payload: Some(portfolio_stream_response::Payload::Portfolio(exchange.portfolio())),
+        }))
+    }
+    async fn positions_stream(&self, _: Request<PositionsStreamRequest>)
+        -> std::result::Result<Response<Self::PositionsStreamStream>, Status> {
+        answer(self.stream(|exchange| PositionsStreamResponse {
+            payload: Some(positions_stream_response::Payload::Position(exchange.position_data())),
+        }))
+    }
+}
+
+/// Runs the strategy through the tape with the real bot, the position left at the end
+/// is handled by `Settings::shutdown_policy` against the last snapshot. The account files
+/// go to `dir` instead of `add_info`, the files of the bots there are not touched.
+pub async fn run(dir: &Path, tape: Tape, params: Params, strategy: Box<dyn Strategy>) -> Result<Report> {
+    // nothing comes after the tape to wait for
+    let shutdown_policy = match strategy.get_settings().shutdown_policy {
+        ShutdownPolicy::CloseLimit(_) => ShutdownPolicy::CloseLimit(Duration::ZERO),
+        policy => policy,
+    };
+    let simulator = Simulator::new(Exchange::new(tape, params)?);
+    let mut finished = simulator.finished();
+    let (addr, _server) = simulator.clone().serve().await?;
+
+    let settings = ConnectionSettings {
+        endpoint: Endpoint::Custom(format!("http://{}", addr)),
+        ..ConnectionSettings::default()
+    };
+    let credentials = Credentials {
+        token: Secret::new(ACCOUNT.to_string()),
+        readonly_token: None,
+        telegram_token: None,
+        account_id: None,
+        chat_id: None,
+        dashboard_token: None,
+    };
+    let broker = Broker::new(settings.channel()?, &credentials, Connection::new());
+
+    // every replay starts with empty statistics
+    match fs::remove_dir_all(dir) {
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+        _ => {},
+    }
+    // absolute, not to be taken relative to `add_info`
+    let dir = std::env::current_dir()?.join(dir).to_string_lossy().into_owned();
+    crate::create_account_files(std::slice::from_ref(&dir))?;
+    let dashboard = Dashboard::new().for_account(ACCOUNT, ACCOUNT);
+    let account = Account {
+        id: ACCOUNT.to_string(),
+        name: dir,
+        title: ACCOUNT.to_string(),
+        kind: AccountType::Tinkoff,
+        strategy: String::new(),
+        margin: None,
+    };
+    let portfolio = portfolio::watch(broker.clone(), account.id.clone());
+    let notifier = Notifier::start(&NotifySettings::default(), Vec::new());
+    let bot = Bot::new(account, broker.clone(), Wallet::new(broker), portfolio, strategy,
+                       notifier, dashboard)?
+        .with_pause(Duration::ZERO)
+        .with_shutdown_policy(shutdown_policy);
+
+    let (stop_tx, stop_rx) = watch::channel(None);
+    tokio::spawn(async move {
+        while !*finished.borrow_and_update() {
+            if finished.changed().await.is_err() {
+                return;
+            }
+        }
+        let _ = stop_tx.send(Some(StopReason::SessionEnd));
+    });
+    // nobody controls the replay
+    let (_, rx) = mpsc::channel(1);
+    bot.handler(rx, stop_rx).await?;
+    Ok(simulator.report())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{AnalysisType, PosState, Settings, State};
+    use crate::strategies::{Context, Fill};
+
+    /// 10:00 Moscow.
+    const MORNING: i64 = 1_675_926_000_000;
+    const UID: &str = "replay-uid";
+
+    fn price(cents: i64) -> Quotation {
+        Quotation::from_nanos(cents as i128 * 10_000_000)
+    }
+    /// A snapshot `ms` after the morning, levels by (price in cents, lots).
+    fn book(ms: i64, bids: &[(i64, i64)], asks: &[(i64, i64)]) -> (i64, GetOrderBookResponse) {
+        let orders = |levels: &[(i64, i64)]| levels.iter()
+            .map(|&(p, quantity)| BookOrder { price: Some(price(p)), quantity })
+            .collect();
+        (MORNING + ms, GetOrderBookResponse { bids: orders(bids), asks: orders(asks), ..GetOrderBookResponse::default() })
+    }
+    fn trade(ms: i64, cents: i64, quantity: i64) -> Trade {
+        Trade { price: Some(price(cents)), quantity, time: Some(timestamp(MORNING + ms)), ..Trade::default() }
+    }
+    fn params(latency: u64, fee_rate: Quotation) -> Params {
+        Params {
+            latency: Duration::from_millis(latency),
+            fee_rate,
+            money: Quotation { units: 1000, nano: 0 },
+            currency: "rub".to_string(),
+        }
+    }
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("replay-{}-{}", name, std::process::id()))
+    }
+    fn order(lots: i64, cents: Option<i64>, direction: OrderDirection) -> PostOrderRequest {
+        PostOrderRequest {
+            quantity: lots,
+            price: cents.map(price),
+            direction: direction.into(),
+            order_type: if cents.is_some() { OrderType::Limit } else { OrderType::Market }.into(),
+            instrument_id: UID.to_string(),
+            ..PostOrderRequest::default()
+        }
+    }
+
+    #[test]
+    fn queue_latency_and_fees() {
+        let tape = Tape {
+            uid: UID.to_string(),
+            books: vec![
+                book(0, &[(590, 20)], &[(591, 30)]),
+                book(1000, &[(590, 20)], &[(591, 30)]),
+                book(2000, &[(590, 5)], &[(591, 30)]),
+                book(3000, &[(590, 5)], &[(591, 30)]),
+                // the sellers came down to the order
+                book(4000, &[(589, 10)], &[(590, 4)]),
+                book(5000, &[(589, 6)], &[(590, 10)]),
+            ],
+            trades: vec![
+                // before the order reaches the book
+                trade(500, 590, 15),
+                // the queue ahead is 20, then 5 by the book
+                trade(1500, 590, 10),
+                trade(2500, 590, 8),
+                // through the price
+                trade(4500, 589, 5),
+            ],
+        };
+        let mut exchange = Exchange::new(tape, params(600, Quotation { units: 0, nano: 1_000_000 })).unwrap();
+        assert_eq!(exchange.order_book(10).orderbook_ts.unwrap().seconds, MORNING / 1000);
+        let id = exchange.post(&order(10, Some(590), OrderDirection::Buy)).unwrap().order_id;
+        assert_eq!(exchange.positions().blocked[0].units, 59);
+        assert_eq!(exchange.order_book(10).bids[0].quantity, 20);
+        assert_eq!(exchange.order(&id).unwrap().execution_report_status, 4);
+        exchange.order_book(10);
+        assert_eq!(exchange.order(&id).unwrap().lots_executed, 0);
+        exchange.order_book(10);
+        let state = exchange.order(&id).unwrap();
+        assert_eq!((state.execution_report_status, state.lots_executed), (5, 3));
+        // the taken liquidity is gone from the book
+        assert!(exchange.order_book(10).asks.is_empty());
+        assert_eq!(exchange.order(&id).unwrap().lots_executed, 7);
+        exchange.order_book(10);
+        let state = exchange.order(&id).unwrap();
+        assert_eq!((state.execution_report_status, state.lots_executed), (1, 10));
+        assert_eq!(state.average_position_price.unwrap().units, 5);
+        assert_eq!(state.executed_commission.unwrap().nano, 59_000_000);
+        let passive: Vec<_> = exchange.report().executions.iter().map(|e| (e.lots, e.passive)).collect();
+        assert_eq!(passive, [(3, true), (4, false), (3, true)]);
+        assert_eq!(exchange.positions().securities[0].balance, 10);
+
+        let id = exchange.post(&order(1, Some(500), OrderDirection::Buy)).unwrap().order_id;
+        assert!(exchange.post(&order(200, Some(500), OrderDirection::Buy)).is_err());
+        exchange.cancel(&id).unwrap();
+        assert!(exchange.cancel(&id).is_err());
+
+        // the tape is over: the order arrives at once, what the depth lacks goes at the last price
+        let id = exchange.post(&order(10, None, OrderDirection::Sell)).unwrap().order_id;
+        exchange.order_book(10);
+        assert!(exchange.finished());
+        let state = exchange.order(&id).unwrap();
+        assert_eq!((state.execution_report_status, state.lots_executed), (1, 10));
+        assert_eq!(state.average_position_price.unwrap().nano, 89_0000000);
+        let report = exchange.report();
+        assert_eq!(report.position, 0);
+        assert_eq!(report.money.1, Quotation::from_nanos(999_782_100_000));
+        assert!(exchange.positions().securities.is_empty());
+    }
+
+    /// Buys 10 at the best bid once, sells them a cent higher.
+    struct Probe {
+        opened: bool,
+        states: Arc<Mutex<Vec<PosState>>>,
+        fills: Arc<Mutex<Vec<Fill>>>,
+    }
+
+    impl Strategy for Probe {
+        fn get_settings(&self) -> Settings {
+            Settings {
+                ticker: "TEST".to_string(),
+                uid: UID.to_string(),
+                figi: String::new(),
+                class_code: String::new(),
+                currency: "rub".to_string(),
+                trading_time: vec![(10, 0, 18, 40)],
+                data_type: AnalysisType::OrderBook(10),
+                sources: Vec::new(),
+                fee_rate: Quotation { units: 0, nano: 500_000 },
+                tax_rate: Quotation::default(),
+                shutdown_policy: ShutdownPolicy::Flatten,
+                stop_on_session_end: false,
+            }
+        }
+        fn get_take_profit(&mut self, p: Quotation, l: i64) -> (Quotation, i64, OrderDirection) {
+            (p + price(1), l, OrderDirection::Sell)
+        }
+        fn on_order_book(&mut self, ctx: &mut Context, book: &GetOrderBookResponse) {
+            if let Some(position) = ctx.position() {
+                self.states.lock().unwrap().push(position.state().clone());
+            }
+            if !self.opened && matches!(ctx.state(), State::Seeking(..)) {
+                self.opened = true;
+                ctx.open(book.bids[0].price.clone().unwrap(), 10, OrderDirection::Buy);
+            }
+        }
+        fn on_fill(&mut self, _: &mut Context, fill: &Fill) {
+            self.fills.lock().unwrap().push(fill.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn bot_through_partial_fills() {
+        let tape = Tape {
+            uid: UID.to_string(),
+            books: vec![
+                book(0, &[(590, 20), (589, 50)], &[(591, 30), (592, 40)]),
+                book(1000, &[(590, 5), (589, 50)], &[(591, 30), (592, 40)]),
+                book(2000, &[(590, 5), (589, 50)], &[(591, 30), (592, 40)]),
+                book(3000, &[(590, 5), (589, 50)], &[(591, 10), (592, 40)]),
+                book(4000, &[(590, 5), (589, 50)], &[(592, 30)]),
+                book(5000, &[(590, 5), (589, 50)], &[(592, 30)]),
+            ],
+            trades: vec![
+                trade(500, 590, 25),
+                trade(1500, 590, 5),
+                trade(2500, 591, 32),
+                trade(3500, 592, 8),
+            ],
+        };
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let fills = Arc::new(Mutex::new(Vec::new()));
+        let probe = Probe { opened: false, states: states.clone(), fills: fills.clone() };
+        let dir = test_dir("partial");
+        let report = run(&dir, tape, params(0, probe.get_settings().fee_rate), Box::new(probe))
+            .await.unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        let states = states.lock().unwrap().clone();
+        for state in [PosState::WaitOpen, PosState::PartialOpen, PosState::WaitClose, PosState::PartialClose] {
+            assert!(states.contains(&state), "no {:?} in {:?}", state, states);
+        }
+        let fills = fills.lock().unwrap().clone();
+        assert_eq!(fills.iter().map(|f| (f.lots, f.opening)).collect::<Vec<_>>(), [(10, true), (10, false)]);
+        assert_eq!(fills[1].price, price(591));
+
+        let lots: Vec<_> = report.executions.iter().map(|e| e.lots).collect();
+        assert_eq!(lots, [5, 5, 2, 8]);
+        assert_eq!(report.orders, 2);
+        assert_eq!(report.position, 0);
+        assert_eq!(report.money.1, Quotation::from_nanos(1_000_040_950_000));
+    }
+
+    #[tokio::test]
+    async fn empty_book_keeps_position() {
+        // the bids are gone by the end, nothing to sell to
+        let tape = Tape {
+            uid: UID.to_string(),
+            books: vec![
+                book(0, &[(590, 1)], &[(592, 30)]),
+                book(1000, &[(590, 1)], &[(592, 30)]),
+                book(2000, &[], &[(592, 30)]),
+            ],
+            trades: vec![trade(500, 590, 20)],
+        };
+        let probe = Probe { opened: false, states: Arc::default(), fills: Arc::default() };
+        let dir = test_dir("kept");
+        let report = run(&dir, tape, params(0, probe.get_settings().fee_rate), Box::new(probe))
+            .await.unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        assert_eq!(report.position, 10);
+        assert_eq!(report.orders, 2);
+    }
+}
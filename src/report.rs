@@ -0,0 +1,458 @@
+//! Reports for a period from `stat.json` of the accounts: the trade journal, the daily results
+//! and the tax summary of the years the period touches in CSV, and all of them with the charts in one HTML file.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use chrono::{Datelike, NaiveDate};
+
+use crate::analytics::Summary;
+use crate::bot::{DayResult, ProfitStat, Statistics, TradeStat};
+use crate::chart::{self, ChartError};
+use crate::error::{Error, Result};
+use crate::tcs::Quotation;
+
+
+const NANO: i128 = 1_000_000_000;
+/// The personal income tax: 13%, 15% of the income above 5 mln a year.
+const TAX_RATES: (i128, i128) = (13, 15);
+const TAX_THRESHOLD: i128 = 5_000_000 * NANO;
+/// The account of the rows summed over the accounts.
+const TOTAL: &str = "total";
+
+/// (CSV column, HTML title)
+type Columns = [(&'static str, &'static str)];
+
+const TRADES: &Columns = &[
+    ("date", "Дата"), ("account", "Счёт"), ("strategy", "Стратегия"), ("ticker", "Инструмент"),
+    ("direction", "Направление"), ("lots", "Лотов"), ("time_in", "Вход"), ("price_in", "Цена входа"),
+    ("time_out", "Выход"), ("price_out", "Цена выхода"), ("turnover", "Оборот"), ("net", "Результат"),
+    ("fees", "Комиссия"), ("tax", "Налог"), ("after_tax", "После налога"),
+];
+const DAYS: &Columns = &[
+    ("date", "Дата"), ("account", "Счёт"), ("trades", "Сделок"), ("net", "Результат"),
+    ("fees", "Комиссия"), ("tax", "Налог"), ("after_tax", "После налога"),
+];
+const TAXES: &Columns = &[
+    ("year", "Год"), ("account", "Счёт"), ("trades", "Сделок"), ("proceeds", "Доходы"),
+    ("expenses", "Расходы с комиссией"), ("fees", "Комиссия"), ("base", "Налоговая база"),
+    ("tax", "НДФЛ"), ("bot_tax", "Налог по статистике бота"),
+];
+
+fn fees(profit: &ProfitStat) -> Quotation {
+    profit.net.clone() - profit.after_fees.clone()
+}
+fn tax(profit: &ProfitStat) -> Quotation {
+    profit.after_fees.clone() - profit.after_tax.clone()
+}
+fn price(&(units, nano): &(i64, i32)) -> Quotation {
+    Quotation { units, nano }
+}
+fn date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()
+}
+
+/// The tax on a year's income.
+fn income_tax(base: i128) -> i128 {
+    let (low, high) = TAX_RATES;
+    if base <= 0 {
+        0
+    } else if base <= TAX_THRESHOLD {
+        base * low / 100
+    } else {
+        TAX_THRESHOLD * low / 100 + (base - TAX_THRESHOLD) * high / 100
+    }
+}
+
+/// A year of one account or of all of them for the 3-NDFL check, in nanos.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TaxYear {
+    pub trades: usize,
+    /// The sales.
+    pub proceeds: i128,
+    /// The purchases and the fees.
+    pub expenses: i128,
+    pub fees: i128,
+    /// What `Settings::tax_rate` gave.
+    pub bot_tax: i128,
+}
+
+impl TaxYear {
+    fn add(&mut self, trade: &TradeStat) {
+        let lots = trade.lots as i128;
+        let (buy, sell) = if trade.direction {
+            (price(&trade.price_in), price(&trade.price_out))
+        } else {
+            (price(&trade.price_out), price(&trade.price_in))
+        };
+        let fees = fees(&trade.profit).to_nanos();
+        self.trades += 1;
+        self.proceeds += sell.to_nanos() * lots;
+        self.expenses += buy.to_nanos() * lots + fees;
+        self.fees += fees;
+        self.bot_tax += tax(&trade.profit).to_nanos();
+    }
+
+    pub fn base(&self) -> i128 {
+        self.proceeds - self.expenses
+    }
+
+    fn merge(&mut self, other: &TaxYear) {
+        self.trades += other.trades;
+        self.proceeds += other.proceeds;
+        self.expenses += other.expenses;
+        self.fees += other.fees;
+        self.bot_tax += other.bot_tax;
+    }
+}
+
+/// The threshold of the tax is for the whole income of the year,
+/// the accounts are only a breakdown of it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Taxes {
+    pub total: TaxYear,
+    pub accounts: BTreeMap<String, TaxYear>,
+}
+
+impl Taxes {
+    pub fn tax(&self) -> i128 {
+        income_tax(self.total.base())
+    }
+}
+
+/// The trades and the days of the period, by account.
+pub struct Report {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// (account, trade) in order of the days.
+    pub trades: Vec<(String, TradeStat)>,
+    /// (account, day) in order of the days.
+    pub days: Vec<(String, DayResult)>,
+    /// (account, trade) of the whole calendar years of the period: the tax is for a year.
+    pub year_trades: Vec<(String, TradeStat)>,
+    /// The older statistics have no dates of the trades.
+    pub undated: usize,
+    /// Of the instruments traded, for the charts; empty if not known.
+    pub currency: String,
+}
+
+impl Report {
+    /// `accounts` are `AccountConfig::name`.
+    pub fn load(accounts: &[String], from: NaiveDate, to: NaiveDate) -> Result<Report> {
+        let stats = accounts.iter()
+            .map(|account| Ok((account.clone(), Statistics::load(account)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Report::new(stats, from, to))
+    }
+
+    pub fn new(stats: Vec<(String, Statistics)>, from: NaiveDate, to: NaiveDate) -> Report {
+        let within = |text: &str| date(text).is_some_and(|day| from <= day && day <= to);
+        let in_years = |text: &str| date(text).is_some_and(|day| (from.year()..=to.year()).contains(&day.year()));
+        let mut report = Report {
+            from, to, trades: Vec::new(), days: Vec::new(), year_trades: Vec::new(), undated: 0,
+            currency: String::new(),
+        };
+        for (account, stat) in stats {
+            report.undated += stat.trades.iter().filter(|trade| date(&trade.date).is_none()).count();
+            report.trades.extend(stat.trades.iter()
+                .filter(|trade| within(&trade.date))
+                .map(|trade| (account.clone(), trade.clone())));
+            report.year_trades.extend(stat.trades.into_iter()
+                .filter(|trade| in_years(&trade.date))
+                .map(|trade| (account.clone(), trade)));
+            report.days.extend(stat.days.into_iter()
+                .filter(|day| within(&day.date))
+                .map(|day| (account.clone(), day)));
+        }
+        // stable, the trades of a day stay in order
+        report.trades.sort_by(|a, b| a.1.date.cmp(&b.1.date));
+        report.days.sort_by(|a, b| a.1.date.cmp(&b.1.date));
+        report
+    }
+
+    /// By year, over the whole years of the period.
+    pub fn taxes(&self) -> BTreeMap<i32, Taxes> {
+        let mut years: BTreeMap<i32, Taxes> = BTreeMap::new();
+        for (account, trade) in &self.year_trades {
+            if let Some(day) = date(&trade.date) {
+                years.entry(day.year()).or_default().accounts.entry(account.clone()).or_default().add(trade);
+            }
+        }
+        for taxes in years.values_mut() {
+            for account in taxes.accounts.values() {
+                taxes.total.merge(account);
+            }
+        }
+        years
+    }
+
+    fn trade_rows(&self) -> Vec<Vec<String>> {
+        self.trades.iter()
+            .map(|(account, trade)| vec![
+                trade.date.clone(), account.clone(), trade.strategy.clone(), trade.ticker.clone(),
+                if trade.direction { "buy" } else { "sell" }.to_string(), trade.lots.to_string(),
+                trade.time_in.clone(), price(&trade.price_in).to_string(),
+                trade.time_out.clone(), price(&trade.price_out).to_string(), trade.turnover.to_string(),
+                trade.profit.net.to_string(), fees(&trade.profit).to_string(), tax(&trade.profit).to_string(),
+                trade.profit.after_tax.to_string(),
+            ])
+            .collect()
+    }
+
+    fn day_rows(&self) -> Vec<Vec<String>> {
+        self.days.iter()
+            .map(|(account, day)| vec![
+                day.date.clone(), account.clone(), day.trades_count.to_string(), day.profit.net.to_string(),
+                fees(&day.profit).to_string(), tax(&day.profit).to_string(), day.profit.after_tax.to_string(),
+            ])
+            .collect()
+    }
+
+    /// The accounts of a year without the tax, then their total with it.
+    fn tax_rows(&self) -> Vec<Vec<String>> {
+        let money = |nanos: i128| Quotation::from_nanos(nanos).to_string();
+        let row = |year: i32, account: &str, part: &TaxYear, tax: String| vec![
+            year.to_string(), account.to_string(), part.trades.to_string(), money(part.proceeds),
+            money(part.expenses), money(part.fees), money(part.base()), tax, money(part.bot_tax),
+        ];
+        let mut rows = Vec::new();
+        for (year, taxes) in self.taxes() {
+            for (account, part) in &taxes.accounts {
+                rows.push(row(year, account, part, String::new()));
+            }
+            rows.push(row(year, TOTAL, &taxes.total, money(taxes.tax())));
+        }
+        rows
+    }
+
+    /// The results of the days summed over the accounts, for the chart.
+    fn daily(&self) -> Vec<DayResult> {
+        let mut days: Vec<DayResult> = Vec::new();
+        for (_, day) in &self.days {
+            match days.last_mut() {
+                Some(last) if last.date == day.date => {
+                    last.profit = last.profit.clone() + day.profit.clone();
+                    last.trades_count += day.trades_count;
+                },
+                _ => days.push(day.clone()),
+            }
+        }
+        days
+    }
+
+    /// Tables, charts and the analytics of the period in one file without external links.
+    pub fn html(&self) -> String {
+        let trades: Vec<TradeStat> = self.trades.iter().map(|(_, trade)| trade.clone()).collect();
+        let summary = Summary::of(&trades.iter().collect::<Vec<_>>(), 0);
+        let title = format!("Отчёт за {} — {}", self.from, self.to);
+        let mut html = format!("<!DOCTYPE html>\n<html lang=\"ru\">\n<head>\n<meta charset=\"utf-8\">\n\
+            <title>{}</title>\n<style>\nbody {{ font-family: sans-serif; margin: 2em; }}\n\
+            table {{ border-collapse: collapse; margin-bottom: 2em; }}\n\
+            th, td {{ border: 1px solid #ccc; padding: 2px 8px; text-align: right; }}\n\
+            th {{ background: #eee; }}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n", title, title);
+        html.push_str(&format!("<h2>Итоги</h2>\n<pre>{}</pre>\n", escape(&summary.to_string())));
+        if self.undated > 0 {
+            html.push_str(&format!("<p>Сделок старой статистики без дат, не вошли в отчёт: {}.</p>\n",
+                                   self.undated));
+        }
+        html.push_str("<h2>Графики</h2>\n");
+        for (name, png) in [("Доходность", chart::equity_chart(&trades, &self.currency)),
+                            ("Результаты по дням", chart::daily_pnl_chart(&self.daily(), &self.currency))] {
+            html.push_str(&image(name, png));
+        }
+        html.push_str(&format!("<h2>По дням</h2>\n{}", table(DAYS, &self.day_rows())));
+        html.push_str(&format!("<h2>Налоги</h2>\n<p>За полные календарные годы периода, \
+                               с {} по {}. НДФЛ {}%, {}% с дохода свыше 5 млн в год \
+                               по всем счетам вместе, он в строке total; убыток года на налог не влияет.</p>\n{}",
+                               self.from.year(), self.to.year(), TAX_RATES.0, TAX_RATES.1, table(TAXES, &self.tax_rows())));
+        html.push_str(&format!("<h2>Сделки</h2>\n{}", table(TRADES, &self.trade_rows())));
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Writes `trades.csv`, `days.csv`, `taxes.csv` and `report.html` to `dir`.
+    pub fn write(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let files = [
+            ("trades.csv", csv(TRADES, &self.trade_rows())),
+            ("days.csv", csv(DAYS, &self.day_rows())),
+            ("taxes.csv", csv(TAXES, &self.tax_rows())),
+            ("report.html", self.html()),
+        ];
+        files.into_iter()
+            .map(|(name, text)| {
+                let path = dir.join(name);
+                fs::write(&path, text)?;
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+fn csv(columns: &Columns, rows: &[Vec<String>]) -> String {
+    let field = |text: &str| if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    };
+    let mut text = columns.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(",");
+    text.push('\n');
+    for row in rows {
+        text.push_str(&row.iter().map(|value| field(value)).collect::<Vec<_>>().join(","));
+        text.push('\n');
+    }
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn table(columns: &Columns, rows: &[Vec<String>]) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    for (_, title) in columns {
+        html.push_str(&format!("<th>{}</th>", title));
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for value in row {
+            html.push_str(&format!("<td>{}</td>", escape(value)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// The chart inline, or why there is none.
+fn image(name: &str, png: Result<Vec<u8>, ChartError>) -> String {
+    match png {
+        Ok(png) => format!("<h3>{}</h3>\n<img alt=\"{}\" src=\"data:image/png;base64,{}\">\n",
+                           name, name, base64::encode(png)),
+        Err(err) => format!("<h3>{}</h3>\n<p>Не получилось: {}</p>\n", name, escape(&err.to_string())),
+    }
+}
+
+/// `from..=to` from YYYY-MM-DD.
+pub fn parse_period(from: &str, to: &str) -> Result<(NaiveDate, NaiveDate)> {
+    let parse = |text: &str| date(text).ok_or_else(|| Error::Config(format!("bad date {}", text)));
+    let (from, to) = (parse(from)?, parse(to)?);
+    if from > to {
+        return Err(Error::Config(format!("{} is after {}", from, to)));
+    }
+    Ok((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(units: i64) -> Quotation {
+        Quotation { units, nano: 0 }
+    }
+    fn trade(date: &str, direction: bool, price_in: i64, price_out: i64, net: i64) -> TradeStat {
+        TradeStat {
+            date: date.to_string(),
+            direction,
+            lots: 10,
+            price_in: (price_in, 0),
+            price_out: (price_out, 0),
+            profit: ProfitStat { net: money(net), after_fees: money(net - 1), after_tax: money(net - 2) },
+            ticker: "TRUR".to_string(),
+            strategy: "scalp, v2".to_string(),
+            ..TradeStat::default()
+        }
+    }
+    fn stat(trades: Vec<TradeStat>) -> Statistics {
+        let mut stat = Statistics::new("2022-12-01".to_string());
+        stat.days = vec![DayResult {
+            date: "2023-02-09".to_string(),
+            profit: ProfitStat { net: money(50), after_fees: money(48), after_tax: money(44) },
+            trades_count: 2,
+        }];
+        stat.trades = trades;
+        stat
+    }
+
+    #[test]
+    fn period_and_taxes() {
+        let report = Report::new(vec![
+            ("iis".to_string(), stat(vec![
+                trade("2022-12-30", true, 10, 12, 20),
+                trade("2023-02-09", true, 10, 13, 30),
+                TradeStat::default(),
+            ])),
+            (String::new(), stat(vec![trade("2023-02-09", false, 15, 13, 20), trade("2023-03-01", true, 1, 2, 10)])),
+        ], date("2023-01-01").unwrap(), date("2023-02-28").unwrap());
+        assert_eq!(report.trades.len(), 2);
+        assert_eq!(report.days.len(), 2);
+        assert_eq!(report.undated, 1);
+        assert_eq!(report.daily().len(), 1);
+        assert_eq!(report.daily()[0].profit.net, money(100));
+
+        // the whole 2023: with the March trade, without the December one
+        let taxes = report.taxes();
+        assert_eq!(taxes.keys().collect::<Vec<_>>(), vec![&2023]);
+        let iis = &taxes[&2023].accounts["iis"];
+        assert_eq!((iis.proceeds, iis.expenses), (130 * NANO, 101 * NANO));
+        assert_eq!(iis.base(), 29 * NANO);
+        // the short sells first, then the March trade: 20 for 10 and a fee
+        let short = &taxes[&2023].accounts[""];
+        assert_eq!(short.trades, 2);
+        assert_eq!((short.proceeds, short.expenses), (170 * NANO, 142 * NANO));
+        assert_eq!((taxes[&2023].total.base(), taxes[&2023].tax()), (57 * NANO, 741 * NANO / 100));
+        assert_eq!(report.tax_rows().iter().map(|row| (row[1].as_str(), row[7].as_str())).collect::<Vec<_>>(),
+                   vec![("", ""), ("iis", ""), (TOTAL, "7.41")]);
+
+        // February alone crosses no threshold, the year does
+        let february = Report::new(vec![
+            ("iis".to_string(), stat(vec![
+                trade("2023-01-10", true, 0, 400_000, 0),
+                trade("2023-02-09", true, 10, 13, 30),
+                trade("2023-11-20", true, 0, 200_000, 0),
+                trade("2024-01-10", true, 0, 400_000, 0),
+            ])),
+        ], date("2023-02-01").unwrap(), date("2023-02-28").unwrap());
+        assert_eq!(february.trades.len(), 1);
+        let year = &february.taxes()[&2023];
+        assert_eq!(year.total.trades, 3);
+        assert_eq!(year.total.base(), 6_000_027 * NANO);
+        assert_eq!(year.tax(), TAX_THRESHOLD * 13 / 100 + 1_000_027 * NANO * 15 / 100);
+        assert_eq!(february.taxes().len(), 1);
+
+        // 3 mln on each account less a fee, 6 mln together
+        let rich = Report::new(vec![
+            ("iis".to_string(), stat(vec![trade("2023-02-09", true, 0, 300_000, 0)])),
+            (String::new(), stat(vec![trade("2023-02-09", true, 0, 300_000, 0)])),
+        ], date("2023-01-01").unwrap(), date("2023-12-31").unwrap());
+        let year = &rich.taxes()[&2023];
+        assert_eq!(year.accounts["iis"].base(), 2_999_999 * NANO);
+        assert_eq!(year.tax(), TAX_THRESHOLD * 13 / 100 + 999_998 * NANO * 15 / 100);
+        assert_eq!(income_tax(100 * NANO), 13 * NANO);
+        assert_eq!(income_tax(TAX_THRESHOLD + 100 * NANO), TAX_THRESHOLD * 13 / 100 + 15 * NANO);
+        assert_eq!(income_tax(-NANO), 0);
+    }
+
+    #[test]
+    fn files() {
+        let report = Report::new(vec![("iis".to_string(), stat(vec![trade("2023-02-09", true, 10, 13, 30)]))],
+                                 date("2023-02-09").unwrap(), date("2023-02-09").unwrap());
+        let trades = csv(TRADES, &report.trade_rows());
+        assert_eq!(trades.lines().nth(1),
+                   Some("2023-02-09,iis,\"scalp, v2\",TRUR,buy,10,,10,,13,0,30,1,1,28"));
+        let html = report.html();
+        assert!(html.contains("<td>scalp, v2</td>"));
+        assert_eq!(html.matches("data:image/png;base64,").count(), 2);
+        assert!(!html.contains("http"));
+
+        let dir = std::env::temp_dir().join(format!("report-test-{}", std::process::id()));
+        let files = report.write(&dir).unwrap();
+        assert_eq!(files.len(), 4);
+        assert!(fs::read_to_string(dir.join("taxes.csv")).unwrap().starts_with("year,account,"));
+        fs::remove_dir_all(dir).unwrap();
+        assert!(parse_period("2023-02-10", "2023-02-09").is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration
+};
+use crate::balance::Money;
+use crate::bot::DayStat;
+use crate::error::{Error, Result};
+
+
+/// What to do with an open position when the bot is stopping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShutdownPolicy {
+    /// Leave the position (and its take-profit order) as it is.
+    Keep,
+    /// Close with a limit order at the best opposite price,
+    /// whatever is left after the deadline is closed at market.
+    CloseLimit(Duration),
+    /// Close at market right away.
+    Flatten,
+}
+
+impl ShutdownPolicy {
+    /// `keep`, `flatten` or `close_limit:<deadline secs>`.
+    pub fn parse(text: &str) -> Option<ShutdownPolicy> {
+        let (name, arg) = text.trim().split_once(':').unwrap_or((text.trim(), ""));
+        match (name, arg) {
+            ("keep", "") => Some(ShutdownPolicy::Keep),
+            ("flatten", "") => Some(ShutdownPolicy::Flatten),
+            ("close_limit", secs) => Some(ShutdownPolicy::CloseLimit(Duration::from_secs(secs.parse().ok()?))),
+            _ => None,
+        }
+    }
+
+    /// `SHUTDOWN_POLICY` overrides the one of the strategies, see `parse`.
+    pub fn from_env() -> Result<Option<ShutdownPolicy>> {
+        match std::env::var("SHUTDOWN_POLICY") {
+            Ok(text) => ShutdownPolicy::parse(&text).map(Some).ok_or_else(|| Error::Config(
+                format!("SHUTDOWN_POLICY {}: expected keep, flatten or close_limit:<secs>", text))),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Command,
+    Signal,
+    SessionEnd,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PositionOutcome {
+    Flat,
+    Kept(i64, Money), // lots, price_in
+    Closed(i64, Money), // lots, price_out
+}
+
+pub struct ShutdownSummary {
+    pub reason: StopReason,
+    pub cancelled_orders: u32,
+    pub position: PositionOutcome,
+    pub day: DayStat,
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Command => write!(f, "команда /stop"),
+            StopReason::Signal => write!(f, "сигнал завершения"),
+            StopReason::SessionEnd => write!(f, "конец торговой сессии"),
+        }
+    }
+}
+impl Display for PositionOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PositionOutcome::Flat => write!(f, "нет"),
+            PositionOutcome::Kept(l, p) => write!(f, "оставлена, {} лотов по {}", l, p),
+            PositionOutcome::Closed(l, p) => write!(f, "закрыта, {} лотов по {}", l, p),
+        }
+    }
+}
+impl Display for ShutdownSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Бот остановлен: {}\nОтменено заявок: {}\nПозиция: {}\n{}",
+               self.reason, self.cancelled_orders, self.position, self.day)
+    }
+}
+
+/// Resolves on SIGTERM (unix) or Ctrl+C.
+pub async fn wait_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut term = signal(SignalKind::terminate())
+            .expect("Can't listen for SIGTERM");
+        tokio::select! {
+            _ = term.recv() => {},
+            _ = tokio::signal::ctrl_c() => {},
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcs::Quotation;
+
+    #[test]
+    fn parse_policy() {
+        assert_eq!(ShutdownPolicy::parse("keep"), Some(ShutdownPolicy::Keep));
+        assert_eq!(ShutdownPolicy::parse(" flatten "), Some(ShutdownPolicy::Flatten));
+        assert_eq!(ShutdownPolicy::parse("close_limit:90"), Some(ShutdownPolicy::CloseLimit(Duration::from_secs(90))));
+        assert_eq!(ShutdownPolicy::parse("close_limit"), None);
+        assert_eq!(ShutdownPolicy::parse("keep:10"), None);
+        assert_eq!(ShutdownPolicy::parse("market"), None);
+    }
+    #[test]
+    fn summary_text() {
+        let summary = ShutdownSummary {
+            reason: StopReason::SessionEnd,
+            cancelled_orders: 1,
+            position: PositionOutcome::Closed(3, Money::new(Quotation { units: 5, nano: 91_0000000 }, "rub")),
+            day: DayStat::new("2023-02-09".to_string()),
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Бот остановлен: конец торговой сессии\nОтменено заявок: 1\n\
+            Позиция: закрыта, 3 лотов по 5.91 RUB\nСегодня: 2023-02-09\nСделок: 0, Прибыль: 0"
+        );
+    }
+}
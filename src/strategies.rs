@@ -1 +1,200 @@
-pub mod scalp;
\ No newline at end of file
+pub mod scalp;
+pub mod trend;
+
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::balance::Money;
+use crate::candles::Candles;
+use crate::bot::{Position, Settings, State};
+use crate::error::{Error, Result};
+use crate::portfolio::Portfolio;
+use crate::tcs::{
+    GetOrderBookResponse, LastPrice, OrderDirection, Quotation, Trade
+};
+
+
+/// What the strategy asks the bot to do.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Action {
+    Close(Quotation, i64, OrderDirection),
+    Open(Quotation, i64, OrderDirection),
+    Hold,
+}
+
+/// An order of the bot executed completely.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub order_id: String,
+    pub direction: OrderDirection,
+    pub lots: i64,
+    pub price: Quotation,
+    /// The order opened the position, otherwise it closed it.
+    pub opening: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub order_id: String,
+    /// Empty if the broker gave no reason.
+    pub message: String,
+}
+
+/// Market data the bot polls besides `Settings::data_type`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    /// `on_last_price`
+    LastPrice,
+    /// `on_trade`, only the trades since the previous poll
+    Trades,
+}
+
+/// What a strategy sees and how it places orders, built for every callback.
+pub struct Context {
+    state: State,
+    portfolio: Portfolio,
+    money: Money,
+    settings: Settings,
+    paused: bool,
+    actions: Vec<Action>,
+}
+
+impl Context {
+    pub fn new(state: State, portfolio: Portfolio, money: Money, settings: Settings, paused: bool)
+        -> Context {
+        Context { state, portfolio, money, settings, paused, actions: Vec::new() }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn position(&self) -> Option<&Position> {
+        match &self.state {
+            State::InPosition(pos) => Some(pos),
+            _ => None,
+        }
+    }
+
+    /// What the bot may spend in the currency of the instrument, see `Balance::available`.
+    pub fn money(&self) -> &Money {
+        &self.money
+    }
+
+    /// Holdings and the balance of every currency.
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.portfolio
+    }
+
+    /// The traded instrument: ticker, uid, figi, currency.
+    pub fn instrument(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// New positions are not opened until `/resume`.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The actions are carried out in order after the callback returns.
+    pub fn submit(&mut self, action: Action) {
+        if action != Action::Hold {
+            self.actions.push(action);
+        }
+    }
+
+    pub fn open(&mut self, price: Quotation, lots: i64, direction: OrderDirection) {
+        self.submit(Action::Open(price, lots, direction));
+    }
+
+    /// Replaces the take profit of the position.
+    pub fn close(&mut self, price: Quotation, lots: i64, direction: OrderDirection) {
+        self.submit(Action::Close(price, lots, direction));
+    }
+
+    pub fn into_actions(self) -> Vec<Action> {
+        self.actions
+    }
+}
+
+/// A trading strategy. Every callback may submit several actions through the `Context`.
+pub trait Strategy {
+    fn get_settings(&self) -> Settings;
+    /// The close order placed right after the position is opened.
+    fn get_take_profit(&mut self, p: Quotation, l: i64) -> (Quotation, i64, OrderDirection);
+    /// How often `on_timer` is called, none by default.
+    fn timer(&self) -> Option<Duration> {
+        None
+    }
+
+    fn on_start(&mut self, _: &mut Context) {}
+    /// Orders are not placed from here, the position is left to `Settings::shutdown_policy`.
+    fn on_stop(&mut self, _: &mut Context) {}
+
+    fn on_order_book(&mut self, _: &mut Context, _: &GetOrderBookResponse) {}
+    /// The candles changed, `Candles::closed` tells which intervals got a complete one.
+    fn on_candle(&mut self, _: &mut Context, _: &Candles) {}
+    fn on_trade(&mut self, _: &mut Context, _: &Trade) {}
+    fn on_last_price(&mut self, _: &mut Context, _: &LastPrice) {}
+
+    fn on_fill(&mut self, _: &mut Context, _: &Fill) {}
+    fn on_order_rejected(&mut self, _: &mut Context, _: &Rejection) {}
+    /// The positions or the money of the account changed.
+    fn on_portfolio(&mut self, _: &mut Context) {}
+
+    fn on_session_open(&mut self, _: &mut Context) {}
+    fn on_session_close(&mut self, _: &mut Context) {}
+    fn on_timer(&mut self, _: &mut Context) {}
+}
+
+/// Strategies that can be named in `accounts.json`.
+pub fn by_name(name: &str) -> Option<Box<dyn Strategy>> {
+    match name {
+        "scalp" => Some(Box::new(scalp::Scalp::new())),
+        "trend" => Some(Box::new(trend::Trend::new())),
+        _ => None,
+    }
+}
+
+/// Tunable constants of a strategy by name, see `optimize`.
+pub type Params = BTreeMap<String, f64>;
+
+/// The strategy with the named constants changed, the others keep their defaults.
+pub fn with_params(name: &str, params: &Params) -> Result<Box<dyn Strategy>> {
+    match name {
+        "scalp" => {
+            let mut values = scalp::Params::default();
+            values.set(params)?;
+            Ok(Box::new(scalp::Scalp::with(values)))
+        },
+        "trend" => {
+            let mut values = trend::Params::default();
+            values.set(params)?;
+            Ok(Box::new(trend::Trend::with(values)))
+        },
+        other => Err(Error::Config(format!("unknown strategy {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn several_actions() {
+        let money = Money::new(Quotation { units: 100, nano: 0 }, "rub");
+        let settings = scalp::Scalp::new().get_settings();
+        let mut ctx = Context::new(State::Seeking(money.value.clone()), Portfolio::default(),
+                                   money, settings, false);
+        assert!(ctx.position().is_none());
+        assert_eq!(ctx.instrument().ticker, "TRUR");
+        let price = Quotation { units: 5, nano: 90_0000000 };
+        ctx.open(price.clone(), 2, OrderDirection::Buy);
+        ctx.submit(Action::Hold);
+        ctx.close(price.clone(), 2, OrderDirection::Sell);
+        assert_eq!(ctx.into_actions(), vec![
+            Action::Open(price.clone(), 2, OrderDirection::Buy),
+            Action::Close(price, 2, OrderDirection::Sell),
+        ]);
+    }
+}
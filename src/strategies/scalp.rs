@@ -1,5 +1,10 @@
-use crate::bot::{Action, Strategy, State, Settings, AnalysisType};
+use std::time::Duration;
+use crate::bot::{State, Settings, AnalysisType};
+use crate::error::{Error, Result};
+use crate::orderbook::Book;
+use crate::strategies::{Action, Context, Params as Values, Strategy};
 use crate::tcs::{GetOrderBookResponse, Quotation, OrderDirection};
+use crate::shutdown::ShutdownPolicy;
 
 enum Signal {
     BuyAsk,
@@ -7,55 +12,92 @@ enum Signal {
     Hold,
 }
 
+/// The constants of the signals, picked by hand at first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    /// Asks over bids above it: buy the bid.
+    pub bid_ratio: f64,
+    /// Asks over bids below it: buy the ask.
+    pub ask_ratio: f64,
+    /// A side is thin when its average is that many times its quantity.
+    pub thinning: i64,
+    /// Above the entry price.
+    pub take_profit_ticks: i32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params { bid_ratio: 3.0, ask_ratio: 0.35, thinning: 3, take_profit_ticks: 1 }
+    }
+}
+
+impl Params {
+    /// Changes the named values, see `strategies::with_params`.
+    pub fn set(&mut self, values: &Values) -> Result<()> {
+        for (name, &value) in values {
+            match name.as_str() {
+                "bid_ratio" => self.bid_ratio = value,
+                "ask_ratio" => self.ask_ratio = value,
+                "thinning" => self.thinning = value.round() as i64,
+                "take_profit_ticks" => self.take_profit_ticks = value.round() as i32,
+                other => return Err(Error::Config(format!("scalp has no parameter {}", other))),
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct Scalp {
     c_bid_q_vec: Vec<i64>,
     c_bid_q_avg: i64,
     c_ask_q_vec: Vec<i64>,
     c_ask_q_avg: i64,
+    params: Params,
 }
 
 impl Scalp {
     pub fn new() -> Scalp {
+        Scalp::with(Params::default())
+    }
+
+    pub fn with(params: Params) -> Scalp {
         Scalp {
             c_bid_q_vec: Vec::with_capacity(40),
             c_bid_q_avg: 0,
             c_ask_q_vec: Vec::with_capacity(40),
             c_ask_q_avg: 0,
+            params,
         }
     }
 
-    fn compute_price(&Quotation { units: u, nano: n}: &Quotation, shift: i32) -> Quotation {
-        match n + 01_0000000 * shift {
-            100_0000000 => Quotation { units: u + 1, nano: 0 },
-            -01_0000000 => Quotation { units: u - 1, nano: 99_0000000 },
-            n => Quotation { units: u, nano: n },
-        }
+    /// `shift` ticks of 0.01 away.
+    fn compute_price(price: &Quotation, shift: i32) -> Quotation {
+        Quotation::from_nanos(price.to_nanos() + shift as i128 * 1_0000000)
     }
     fn compute_lots_quantity(
         &Quotation { units: p_m, nano: q_m }: &Quotation,
         &Quotation { units: p_p, nano: q_p }: &Quotation) -> i64 {
         let nanos_p = p_p * 100 + q_p as i64 / 1_0000000;
         let nanos_m = p_m * 100 + q_m as i64 / 1_0000000;
-        return nanos_m / nanos_p
+        nanos_m / nanos_p
     }
-}
 
-impl Strategy for Scalp {
-    fn analyze_ob(&mut self, orders: &GetOrderBookResponse, state: &State) -> Action {
+    fn signal(&mut self, orders: &GetOrderBookResponse, state: &State) -> Action {
         use Signal::*;
 
+        let Params { bid_ratio, ask_ratio, thinning, .. } = self.params;
         let ratio = |ask: i64, bid: i64| -> Signal {
             match ask as f64 / bid as f64 {
-                val if val > 3.0 => BuyBid,
-                val if val < 0.35 => BuyAsk,
+                val if val > bid_ratio => BuyBid,
+                val if val < ask_ratio => BuyAsk,
                 _ => Hold,
             }
         };
         let order_book = |s: &mut Scalp, ask: i64, bid: i64| -> Signal {
-            let ask_a = s.c_ask_q_avg / ask > 3;
+            let ask_a = s.c_ask_q_avg / ask > thinning;
             s.c_ask_q_vec.push(ask);
             s.c_ask_q_avg = s.c_ask_q_vec.iter().sum::<i64>() / s.c_ask_q_vec.len() as i64;
-            let bid_a = s.c_bid_q_avg / bid > 3;
+            let bid_a = s.c_bid_q_avg / bid > thinning;
             s.c_bid_q_vec.push(bid);
             s.c_bid_q_avg = s.c_bid_q_vec.iter().sum::<i64>() / s.c_bid_q_vec.len() as i64;
             match (ask_a, bid_a) {
@@ -65,12 +107,12 @@ impl Strategy for Scalp {
             }
         };
 
-        let bids = &orders.bids;
-        let asks = &orders.asks;
-
-        let ask_q = asks[0].quantity;
-        let bid_q = bids[0].quantity;
-        return match (order_book(self, ask_q, bid_q), ratio(ask_q, bid_q)) {
+        let book = Book::from(orders);
+        let (Some(ask), Some(bid)) = (book.best_ask(), book.best_bid()) else {
+            return Action::Hold;
+        };
+        let (ask_q, bid_q) = (ask.quantity, bid.quantity);
+        match (order_book(self, ask_q, bid_q), ratio(ask_q, bid_q)) {
             (BuyBid, BuyBid) => {
                 if let State::InPosition(pos) = state {
                     Action::Close(pos.price_in.clone(), pos.lots, {
@@ -83,8 +125,8 @@ impl Strategy for Scalp {
             },
             (BuyAsk, BuyAsk) => {
                 if let State::Seeking(mv) = state {
-                    let price = Scalp::compute_price(asks[0].price.as_ref().unwrap(), 0);
-                    let lots = Scalp::compute_lots_quantity(&price, &mv);
+                    let price = Scalp::compute_price(&ask.price, 0);
+                    let lots = Scalp::compute_lots_quantity(mv, &price);
                     Action::Open(price, lots, OrderDirection::Sell)
                 } else {
                     Action::Hold
@@ -93,27 +135,48 @@ impl Strategy for Scalp {
             _ => Action::Hold
         }
     }
+}
+
+impl Default for Scalp {
+    fn default() -> Self {
+        Scalp::new()
+    }
+}
+
+impl Strategy for Scalp {
+    fn on_order_book(&mut self, ctx: &mut Context, orders: &GetOrderBookResponse) {
+        let action = self.signal(orders, ctx.state());
+        // the take profit manages the open position
+        if ctx.position().is_none() {
+            ctx.submit(action);
+        }
+    }
+
     fn get_take_profit(&mut self, p: Quotation, l: i64) -> (Quotation, i64, OrderDirection) {
-        (Scalp::compute_price(&p, 1), l, OrderDirection::Sell)
+        (Scalp::compute_price(&p, self.params.take_profit_ticks), l, OrderDirection::Sell)
     }
     fn get_settings(&self) -> Settings {
         Settings {
-            account_id: String::from("2157068285"),
             ticker: String::from("TRUR"),
             uid: String::from("e2d0dbac-d354-4c36-a5ed-e5aae42ffc76"),
             figi: String::from("BBG000000001"),
             class_code: String::from("TQTF"),
+            currency: String::from("rub"),
             trading_time: vec![
                 (10, 0, 18, 40)
             ],
             data_type: AnalysisType::OrderBook(10),
+            sources: Vec::new(),
             fee_rate: Quotation { units: 0, nano: 0 },
             tax_rate: Quotation { units: 0, nano: 13_0000000 },
+            shutdown_policy: ShutdownPolicy::CloseLimit(Duration::new(120, 0)),
+            stop_on_session_end: false,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::zero_prefixed_literal)] // the nano as 2 + 7 digits
 mod tests {
     use super::*;
 
@@ -142,5 +205,17 @@ mod tests {
             Scalp::compute_price(&Quotation { units: 5, nano: 00_0000000 }, -1),
             Quotation { units: 4, nano: 99_0000000 }
         );
+        assert_eq!(
+            Scalp::compute_price(&Quotation { units: 5, nano: 98_0000000 }, 3),
+            Quotation { units: 6, nano: 01_0000000 }
+        );
+    }
+    #[test]
+    fn params() {
+        let mut params = Params::default();
+        let values = [("bid_ratio".to_string(), 2.5), ("take_profit_ticks".to_string(), 2.0)];
+        params.set(&values.into_iter().collect()).unwrap();
+        assert_eq!(params, Params { bid_ratio: 2.5, take_profit_ticks: 2, ..Params::default() });
+        assert!(params.set(&[("ratio".to_string(), 1.0)].into_iter().collect()).is_err());
     }
 }
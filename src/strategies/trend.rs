@@ -0,0 +1,225 @@
+use std::time::Duration;
+use crate::bot::{AnalysisType, PosState, Settings};
+use crate::candles::Candles;
+use crate::error::{Error, Result};
+use crate::indicators::{Adx, Atr, Bar, Ema, Indicator, Rsi};
+use crate::shutdown::ShutdownPolicy;
+use crate::strategies::{Context, Params as Values, Strategy};
+use crate::tcs::{CandleInterval, HistoricCandle, OrderDirection, Quotation};
+
+/// The candles the signals are taken on.
+const INTERVAL: CandleInterval = CandleInterval::CandleInterval5Min;
+
+/// The constants of the signals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    /// Periods of the EMA crossing.
+    pub fast: usize,
+    pub slow: usize,
+    /// No buying above it, the move is over.
+    pub rsi_high: f64,
+    /// No trend below it.
+    pub adx_min: f64,
+    /// The take profit in ATRs above the entry.
+    pub take_profit_atr: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params { fast: 9, slow: 21, rsi_high: 70.0, adx_min: 20.0, take_profit_atr: 2.0 }
+    }
+}
+
+impl Params {
+    /// Changes the named values, see `strategies::with_params`.
+    pub fn set(&mut self, values: &Values) -> Result<()> {
+        for (name, &value) in values {
+            match name.as_str() {
+                "fast" => self.fast = value.round() as usize,
+                "slow" => self.slow = value.round() as usize,
+                "rsi_high" => self.rsi_high = value,
+                "adx_min" => self.adx_min = value,
+                "take_profit_atr" => self.take_profit_atr = value,
+                other => return Err(Error::Config(format!("trend has no parameter {}", other))),
+            }
+        }
+        if self.fast == 0 || self.fast >= self.slow {
+            return Err(Error::Config(format!("trend needs 0 < fast < slow, got {} and {}", self.fast, self.slow)));
+        }
+        Ok(())
+    }
+}
+
+/// Long on the fast EMA crossing the slow one up in a trend, out on the crossing down.
+pub struct Trend {
+    params: Params,
+    fast: Ema,
+    slow: Ema,
+    rsi: Rsi,
+    adx: Adx,
+    atr: Atr,
+    /// Time of the last candle given to the indicators.
+    last: i64,
+    /// The fast EMA was above the slow one on the previous candle.
+    above: Option<bool>,
+    /// Of the last candle.
+    atr_value: Option<f64>,
+}
+
+impl Trend {
+    pub fn new() -> Trend {
+        Trend::with(Params::default())
+    }
+
+    pub fn with(params: Params) -> Trend {
+        Trend {
+            fast: Ema::new(params.fast),
+            slow: Ema::new(params.slow),
+            rsi: Rsi::new(14),
+            adx: Adx::new(14),
+            atr: Atr::new(14),
+            last: i64::MIN,
+            above: None,
+            atr_value: None,
+            params,
+        }
+    }
+
+    /// Takes the candle, tells whether the EMAs crossed on it: up or down.
+    fn update(&mut self, candle: &HistoricCandle) -> (Option<bool>, Option<f64>, Option<f64>) {
+        let bar = Bar::from(candle);
+        self.last = bar.time;
+        self.atr_value = self.atr.update(&bar);
+        let (rsi, adx) = (self.rsi.update(&bar), self.adx.update(&bar).map(|v| v.adx));
+        let cross = match (self.fast.update(&bar), self.slow.update(&bar)) {
+            (Some(fast), Some(slow)) => {
+                let above = fast > slow;
+                let cross = self.above.filter(|&was| was != above).map(|_| above);
+                self.above = Some(above);
+                cross
+            },
+            _ => None,
+        };
+        (cross, rsi, adx)
+    }
+}
+
+impl Default for Trend {
+    fn default() -> Self {
+        Trend::new()
+    }
+}
+
+impl Strategy for Trend {
+    fn on_candle(&mut self, ctx: &mut Context, candles: &Candles) {
+        let Some(series) = candles.series(INTERVAL) else { return };
+        if !candles.closed().contains(&INTERVAL) {
+            return;
+        }
+        // the history only warms the indicators up, the signal is of the last candle
+        let (mut signal, last) = (None, self.last);
+        for candle in series.complete().iter().filter(|c| c.time.as_ref().is_some_and(|t| t.seconds > last)) {
+            signal = Some((self.update(candle), candle.close.clone().unwrap_or_default()));
+        }
+        let Some(((Some(up), rsi, adx), price)) = signal else { return };
+        match ctx.position() {
+            None if up && rsi.is_some_and(|rsi| rsi < self.params.rsi_high)
+                && adx.is_some_and(|adx| adx > self.params.adx_min) => {
+                let lots = (ctx.money().value.to_nanos() / price.to_nanos().max(1)) as i64;
+                if lots > 0 {
+                    ctx.open(price, lots, OrderDirection::Buy);
+                }
+            },
+            Some(pos) if !up && matches!(pos.state(), PosState::WaitClose | PosState::Hold) => {
+                let lots = pos.lots;
+                ctx.close(price, lots, OrderDirection::Sell);
+            },
+            _ => {},
+        }
+    }
+
+    fn get_take_profit(&mut self, p: Quotation, l: i64) -> (Quotation, i64, OrderDirection) {
+        let distance = self.atr_value.unwrap_or_default() * self.params.take_profit_atr;
+        // whole ticks of 0.01, one at least
+        let ticks = ((distance * 100.0).round() as i128).max(1);
+        (Quotation::from_nanos(p.to_nanos() + ticks * 1_0000000), l, OrderDirection::Sell)
+    }
+    fn get_settings(&self) -> Settings {
+        Settings {
+            ticker: String::from("TRUR"),
+            uid: String::from("e2d0dbac-d354-4c36-a5ed-e5aae42ffc76"),
+            figi: String::from("BBG000000001"),
+            class_code: String::from("TQTF"),
+            currency: String::from("rub"),
+            trading_time: vec![
+                (10, 0, 18, 40)
+            ],
+            data_type: AnalysisType::Candle(vec![CandleInterval::CandleInterval1Min, INTERVAL]),
+            sources: Vec::new(),
+            fee_rate: Quotation { units: 0, nano: 0 },
+            tax_rate: Quotation { units: 0, nano: 13_0000000 },
+            shutdown_policy: ShutdownPolicy::CloseLimit(Duration::new(120, 0)),
+            stop_on_session_end: false,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::zero_prefixed_literal)] // the nano as 2 + 7 digits
+mod tests {
+    use super::*;
+    use prost_types::Timestamp;
+    use crate::balance::Money;
+    use crate::bot::State;
+    use crate::portfolio::Portfolio;
+    use crate::strategies::Action;
+
+    /// 10:00 Moscow.
+    const MORNING: i64 = 1_675_926_000;
+
+    fn candle(i: i64, close: f64) -> HistoricCandle {
+        let q = |price: f64| Some(Quotation::from_nanos((price * 1e9).round() as i128));
+        HistoricCandle {
+            open: q(close),
+            high: q(close + 0.02),
+            low: q(close - 0.02),
+            close: q(close),
+            volume: 1000,
+            time: Some(Timestamp { seconds: MORNING + i * 300, nanos: 0 }),
+            is_complete: true,
+        }
+    }
+
+    #[test]
+    fn crossing_up() {
+        let mut strategy = Trend::new();
+        let settings = strategy.get_settings();
+        // down, then slowly up: the EMAs cross on the way
+        let closes: Vec<f64> = (0..60).map(|i| 6.0 - i as f64 * 0.01)
+            .chain((0..40).map(|i| 5.41 + i as f64 * 0.003))
+            .collect();
+        let mut candles = Candles::new(&[INTERVAL]);
+        let mut opened = None;
+        for (i, close) in closes.iter().enumerate() {
+            candles.update([candle(i as i64, *close)]);
+            let money = Money::new(Quotation { units: 100, nano: 0 }, "rub");
+            let mut ctx = Context::new(State::Seeking(money.value.clone()), Portfolio::default(),
+                                       money, settings.clone(), false);
+            strategy.on_candle(&mut ctx, &candles);
+            if let Some(Action::Open(price, lots, direction)) = ctx.into_actions().pop() {
+                opened = Some((i, price, lots, direction));
+                break;
+            }
+        }
+        let (i, price, lots, direction) = opened.expect("no signal");
+        assert!(i > 60, "{}", i);
+        assert_eq!(direction, OrderDirection::Buy);
+        assert_eq!(price, candle(i as i64, closes[i]).close.unwrap());
+        assert_eq!(lots, (100.0 / closes[i]) as i64);
+        let (take, _, side) = strategy.get_take_profit(price.clone(), lots);
+        assert!(take > price);
+        assert_eq!(side, OrderDirection::Sell);
+
+        assert!(Params::default().set(&[("slow".to_string(), 5.0)].into()).is_err());
+    }
+}
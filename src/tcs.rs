@@ -3134,6 +3134,1255 @@ pub mod instruments_service_client {
         }
     }
 }
+/// Generated server implementations.
+pub mod instruments_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with InstrumentsServiceServer.
+    #[async_trait]
+    pub trait InstrumentsService: Send + Sync + 'static {
+        /// Метод получения расписания торгов торговых площадок.
+        async fn trading_schedules(
+            &self,
+            request: tonic::Request<super::TradingSchedulesRequest>,
+        ) -> Result<tonic::Response<super::TradingSchedulesResponse>, tonic::Status>;
+        /// Метод получения облигации по её идентификатору.
+        async fn bond_by(
+            &self,
+            request: tonic::Request<super::InstrumentRequest>,
+        ) -> Result<tonic::Response<super::BondResponse>, tonic::Status>;
+        /// Метод получения списка облигаций.
+        async fn bonds(
+            &self,
+            request: tonic::Request<super::InstrumentsRequest>,
+        ) -> Result<tonic::Response<super::BondsResponse>, tonic::Status>;
+        /// Метод получения графика выплат купонов по облигации.
+        async fn get_bond_coupons(
+            &self,
+            request: tonic::Request<super::GetBondCouponsRequest>,
+        ) -> Result<tonic::Response<super::GetBondCouponsResponse>, tonic::Status>;
+        /// Метод получения валюты по её идентификатору.
+        async fn currency_by(
+            &self,
+            request: tonic::Request<super::InstrumentRequest>,
+        ) -> Result<tonic::Response<super::CurrencyResponse>, tonic::Status>;
+        /// Метод получения списка валют.
+        async fn currencies(
+            &self,
+            request: tonic::Request<super::InstrumentsRequest>,
+        ) -> Result<tonic::Response<super::CurrenciesResponse>, tonic::Status>;
+        /// Метод получения инвестиционного фонда по его идентификатору.
+        async fn etf_by(
+            &self,
+            request: tonic::Request<super::InstrumentRequest>,
+        ) -> Result<tonic::Response<super::EtfResponse>, tonic::Status>;
+        /// Метод получения списка инвестиционных фондов.
+        async fn etfs(
+            &self,
+            request: tonic::Request<super::InstrumentsRequest>,
+        ) -> Result<tonic::Response<super::EtfsResponse>, tonic::Status>;
+        /// Метод получения фьючерса по его идентификатору.
+        async fn future_by(
+            &self,
+            request: tonic::Request<super::InstrumentRequest>,
+        ) -> Result<tonic::Response<super::FutureResponse>, tonic::Status>;
+        /// Метод получения списка фьючерсов.
+        async fn futures(
+            &self,
+            request: tonic::Request<super::InstrumentsRequest>,
+        ) -> Result<tonic::Response<super::FuturesResponse>, tonic::Status>;
+        /// Метод получения опциона по его идентификатору.
+        async fn option_by(
+            &self,
+            request: tonic::Request<super::InstrumentRequest>,
+        ) -> Result<tonic::Response<super::OptionResponse>, tonic::Status>;
+        /// Метод получения списка опционов.
+        async fn options(
+            &self,
+            request: tonic::Request<super::InstrumentsRequest>,
+        ) -> Result<tonic::Response<super::OptionsResponse>, tonic::Status>;
+        /// Метод получения акции по её идентификатору.
+        async fn share_by(
+            &self,
+            request: tonic::Request<super::InstrumentRequest>,
+        ) -> Result<tonic::Response<super::ShareResponse>, tonic::Status>;
+        /// Метод получения списка акций.
+        async fn shares(
+            &self,
+            request: tonic::Request<super::InstrumentsRequest>,
+        ) -> Result<tonic::Response<super::SharesResponse>, tonic::Status>;
+        /// Метод получения накопленного купонного дохода по облигации.
+        async fn get_accrued_interests(
+            &self,
+            request: tonic::Request<super::GetAccruedInterestsRequest>,
+        ) -> Result<tonic::Response<super::GetAccruedInterestsResponse>, tonic::Status>;
+        /// Метод получения размера гарантийного обеспечения по фьючерсам.
+        async fn get_futures_margin(
+            &self,
+            request: tonic::Request<super::GetFuturesMarginRequest>,
+        ) -> Result<tonic::Response<super::GetFuturesMarginResponse>, tonic::Status>;
+        /// Метод получения основной информации об инструменте.
+        async fn get_instrument_by(
+            &self,
+            request: tonic::Request<super::InstrumentRequest>,
+        ) -> Result<tonic::Response<super::InstrumentResponse>, tonic::Status>;
+        /// Метод для получения событий выплаты дивидендов по инструменту.
+        async fn get_dividends(
+            &self,
+            request: tonic::Request<super::GetDividendsRequest>,
+        ) -> Result<tonic::Response<super::GetDividendsResponse>, tonic::Status>;
+        /// Метод получения актива по его идентификатору.
+        async fn get_asset_by(
+            &self,
+            request: tonic::Request<super::AssetRequest>,
+        ) -> Result<tonic::Response<super::AssetResponse>, tonic::Status>;
+        /// Метод получения списка активов.
+        async fn get_assets(
+            &self,
+            request: tonic::Request<super::AssetsRequest>,
+        ) -> Result<tonic::Response<super::AssetsResponse>, tonic::Status>;
+        /// Метод получения списка избранных инструментов.
+        async fn get_favorites(
+            &self,
+            request: tonic::Request<super::GetFavoritesRequest>,
+        ) -> Result<tonic::Response<super::GetFavoritesResponse>, tonic::Status>;
+        /// Метод редактирования списка избранных инструментов.
+        async fn edit_favorites(
+            &self,
+            request: tonic::Request<super::EditFavoritesRequest>,
+        ) -> Result<tonic::Response<super::EditFavoritesResponse>, tonic::Status>;
+        /// Метод получения списка стран.
+        async fn get_countries(
+            &self,
+            request: tonic::Request<super::GetCountriesRequest>,
+        ) -> Result<tonic::Response<super::GetCountriesResponse>, tonic::Status>;
+        /// Метод поиска инструмента.
+        async fn find_instrument(
+            &self,
+            request: tonic::Request<super::FindInstrumentRequest>,
+        ) -> Result<tonic::Response<super::FindInstrumentResponse>, tonic::Status>;
+        /// Метод получения списка брендов.
+        async fn get_brands(
+            &self,
+            request: tonic::Request<super::GetBrandsRequest>,
+        ) -> Result<tonic::Response<super::GetBrandsResponse>, tonic::Status>;
+        /// Метод получения бренда по его идентификатору.
+        async fn get_brand_by(
+            &self,
+            request: tonic::Request<super::GetBrandRequest>,
+        ) -> Result<tonic::Response<super::Brand>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct InstrumentsServiceServer<T: InstrumentsService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: InstrumentsService> InstrumentsServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for InstrumentsServiceServer<T>
+    where
+        T: InstrumentsService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/TradingSchedules" => {
+                    #[allow(non_camel_case_types)]
+                    struct TradingSchedulesSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::TradingSchedulesRequest>
+                    for TradingSchedulesSvc<T> {
+                        type Response = super::TradingSchedulesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TradingSchedulesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).trading_schedules(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TradingSchedulesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/BondBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct BondBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentRequest>
+                    for BondBySvc<T> {
+                        type Response = super::BondResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).bond_by(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BondBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/Bonds" => {
+                    #[allow(non_camel_case_types)]
+                    struct BondsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentsRequest>
+                    for BondsSvc<T> {
+                        type Response = super::BondsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).bonds(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BondsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetBondCoupons" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBondCouponsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetBondCouponsRequest>
+                    for GetBondCouponsSvc<T> {
+                        type Response = super::GetBondCouponsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetBondCouponsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_bond_coupons(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBondCouponsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/CurrencyBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct CurrencyBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentRequest>
+                    for CurrencyBySvc<T> {
+                        type Response = super::CurrencyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).currency_by(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CurrencyBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/Currencies" => {
+                    #[allow(non_camel_case_types)]
+                    struct CurrenciesSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentsRequest>
+                    for CurrenciesSvc<T> {
+                        type Response = super::CurrenciesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).currencies(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CurrenciesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/EtfBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct EtfBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentRequest>
+                    for EtfBySvc<T> {
+                        type Response = super::EtfResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).etf_by(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EtfBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/Etfs" => {
+                    #[allow(non_camel_case_types)]
+                    struct EtfsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentsRequest>
+                    for EtfsSvc<T> {
+                        type Response = super::EtfsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).etfs(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EtfsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/FutureBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct FutureBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentRequest>
+                    for FutureBySvc<T> {
+                        type Response = super::FutureResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).future_by(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = FutureBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/Futures" => {
+                    #[allow(non_camel_case_types)]
+                    struct FuturesSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentsRequest>
+                    for FuturesSvc<T> {
+                        type Response = super::FuturesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).futures(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = FuturesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/OptionBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct OptionBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentRequest>
+                    for OptionBySvc<T> {
+                        type Response = super::OptionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).option_by(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = OptionBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/Options" => {
+                    #[allow(non_camel_case_types)]
+                    struct OptionsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentsRequest>
+                    for OptionsSvc<T> {
+                        type Response = super::OptionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).options(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = OptionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/ShareBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct ShareBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentRequest>
+                    for ShareBySvc<T> {
+                        type Response = super::ShareResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).share_by(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ShareBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/Shares" => {
+                    #[allow(non_camel_case_types)]
+                    struct SharesSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentsRequest>
+                    for SharesSvc<T> {
+                        type Response = super::SharesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).shares(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SharesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetAccruedInterests" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAccruedInterestsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetAccruedInterestsRequest>
+                    for GetAccruedInterestsSvc<T> {
+                        type Response = super::GetAccruedInterestsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetAccruedInterestsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_accrued_interests(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAccruedInterestsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetFuturesMargin" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetFuturesMarginSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetFuturesMarginRequest>
+                    for GetFuturesMarginSvc<T> {
+                        type Response = super::GetFuturesMarginResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetFuturesMarginRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_futures_margin(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetFuturesMarginSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetInstrumentBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetInstrumentBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::InstrumentRequest>
+                    for GetInstrumentBySvc<T> {
+                        type Response = super::InstrumentResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_instrument_by(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetInstrumentBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetDividends" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDividendsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetDividendsRequest>
+                    for GetDividendsSvc<T> {
+                        type Response = super::GetDividendsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetDividendsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_dividends(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDividendsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetAssetBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAssetBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::AssetRequest>
+                    for GetAssetBySvc<T> {
+                        type Response = super::AssetResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AssetRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_asset_by(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAssetBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetAssets" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAssetsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::AssetsRequest>
+                    for GetAssetsSvc<T> {
+                        type Response = super::AssetsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AssetsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_assets(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAssetsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetFavorites" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetFavoritesSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetFavoritesRequest>
+                    for GetFavoritesSvc<T> {
+                        type Response = super::GetFavoritesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetFavoritesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_favorites(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetFavoritesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/EditFavorites" => {
+                    #[allow(non_camel_case_types)]
+                    struct EditFavoritesSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::EditFavoritesRequest>
+                    for EditFavoritesSvc<T> {
+                        type Response = super::EditFavoritesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EditFavoritesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).edit_favorites(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EditFavoritesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetCountries" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetCountriesSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetCountriesRequest>
+                    for GetCountriesSvc<T> {
+                        type Response = super::GetCountriesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetCountriesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_countries(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetCountriesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/FindInstrument" => {
+                    #[allow(non_camel_case_types)]
+                    struct FindInstrumentSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::FindInstrumentRequest>
+                    for FindInstrumentSvc<T> {
+                        type Response = super::FindInstrumentResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FindInstrumentRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).find_instrument(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = FindInstrumentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetBrands" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBrandsSvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetBrandsRequest>
+                    for GetBrandsSvc<T> {
+                        type Response = super::GetBrandsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetBrandsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_brands(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBrandsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.InstrumentsService/GetBrandBy" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBrandBySvc<T: InstrumentsService>(pub Arc<T>);
+                    impl<
+                        T: InstrumentsService,
+                    > tonic::server::UnaryService<super::GetBrandRequest>
+                    for GetBrandBySvc<T> {
+                        type Response = super::Brand;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetBrandRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_brand_by(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBrandBySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: InstrumentsService> Clone for InstrumentsServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: InstrumentsService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: InstrumentsService> tonic::server::NamedService
+    for InstrumentsServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.InstrumentsService";
+    }
+}
 /// Запрос подписки или отписки на определённые биржевые данные.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -4381,6 +5630,603 @@ pub mod market_data_stream_service_client {
         }
     }
 }
+/// Generated server implementations.
+pub mod market_data_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with MarketDataServiceServer.
+    #[async_trait]
+    pub trait MarketDataService: Send + Sync + 'static {
+        /// Метод запроса исторических свечей по инструменту.
+        async fn get_candles(
+            &self,
+            request: tonic::Request<super::GetCandlesRequest>,
+        ) -> Result<tonic::Response<super::GetCandlesResponse>, tonic::Status>;
+        /// Метод запроса цен последних сделок по инструментам.
+        async fn get_last_prices(
+            &self,
+            request: tonic::Request<super::GetLastPricesRequest>,
+        ) -> Result<tonic::Response<super::GetLastPricesResponse>, tonic::Status>;
+        /// Метод получения стакана по инструменту.
+        async fn get_order_book(
+            &self,
+            request: tonic::Request<super::GetOrderBookRequest>,
+        ) -> Result<tonic::Response<super::GetOrderBookResponse>, tonic::Status>;
+        /// Метод запроса статуса торгов по инструментам.
+        async fn get_trading_status(
+            &self,
+            request: tonic::Request<super::GetTradingStatusRequest>,
+        ) -> Result<tonic::Response<super::GetTradingStatusResponse>, tonic::Status>;
+        /// Метод запроса обезличенных сделок за последний час.
+        async fn get_last_trades(
+            &self,
+            request: tonic::Request<super::GetLastTradesRequest>,
+        ) -> Result<tonic::Response<super::GetLastTradesResponse>, tonic::Status>;
+        /// Метод запроса цен закрытия торговой сессии по инструментам.
+        async fn get_close_prices(
+            &self,
+            request: tonic::Request<super::GetClosePricesRequest>,
+        ) -> Result<tonic::Response<super::GetClosePricesResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct MarketDataServiceServer<T: MarketDataService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: MarketDataService> MarketDataServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for MarketDataServiceServer<T>
+    where
+        T: MarketDataService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.MarketDataService/GetCandles" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetCandlesSvc<T: MarketDataService>(pub Arc<T>);
+                    impl<
+                        T: MarketDataService,
+                    > tonic::server::UnaryService<super::GetCandlesRequest>
+                    for GetCandlesSvc<T> {
+                        type Response = super::GetCandlesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetCandlesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_candles(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetCandlesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.MarketDataService/GetLastPrices" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLastPricesSvc<T: MarketDataService>(pub Arc<T>);
+                    impl<
+                        T: MarketDataService,
+                    > tonic::server::UnaryService<super::GetLastPricesRequest>
+                    for GetLastPricesSvc<T> {
+                        type Response = super::GetLastPricesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetLastPricesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_last_prices(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetLastPricesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.MarketDataService/GetOrderBook" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetOrderBookSvc<T: MarketDataService>(pub Arc<T>);
+                    impl<
+                        T: MarketDataService,
+                    > tonic::server::UnaryService<super::GetOrderBookRequest>
+                    for GetOrderBookSvc<T> {
+                        type Response = super::GetOrderBookResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetOrderBookRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_order_book(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetOrderBookSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.MarketDataService/GetTradingStatus" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTradingStatusSvc<T: MarketDataService>(pub Arc<T>);
+                    impl<
+                        T: MarketDataService,
+                    > tonic::server::UnaryService<super::GetTradingStatusRequest>
+                    for GetTradingStatusSvc<T> {
+                        type Response = super::GetTradingStatusResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetTradingStatusRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_trading_status(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTradingStatusSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.MarketDataService/GetLastTrades" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLastTradesSvc<T: MarketDataService>(pub Arc<T>);
+                    impl<
+                        T: MarketDataService,
+                    > tonic::server::UnaryService<super::GetLastTradesRequest>
+                    for GetLastTradesSvc<T> {
+                        type Response = super::GetLastTradesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetLastTradesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_last_trades(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetLastTradesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.MarketDataService/GetClosePrices" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetClosePricesSvc<T: MarketDataService>(pub Arc<T>);
+                    impl<
+                        T: MarketDataService,
+                    > tonic::server::UnaryService<super::GetClosePricesRequest>
+                    for GetClosePricesSvc<T> {
+                        type Response = super::GetClosePricesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetClosePricesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_close_prices(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetClosePricesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: MarketDataService> Clone for MarketDataServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: MarketDataService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: MarketDataService> tonic::server::NamedService
+    for MarketDataServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.MarketDataService";
+    }
+}
+/// Generated server implementations.
+pub mod market_data_stream_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with MarketDataStreamServiceServer.
+    #[async_trait]
+    pub trait MarketDataStreamService: Send + Sync + 'static {
+        /// Server streaming response type for the MarketDataStream method.
+        type MarketDataStreamStream: futures_core::Stream<
+                Item = Result<super::MarketDataResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Bi-directional стрим предоставления биржевой информации.
+        async fn market_data_stream(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::MarketDataRequest>>,
+        ) -> Result<tonic::Response<Self::MarketDataStreamStream>, tonic::Status>;
+        /// Server streaming response type for the MarketDataServerSideStream method.
+        type MarketDataServerSideStreamStream: futures_core::Stream<
+                Item = Result<super::MarketDataResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Server-side стрим предоставления биржевой информации.
+        async fn market_data_server_side_stream(
+            &self,
+            request: tonic::Request<super::MarketDataServerSideStreamRequest>,
+        ) -> Result<
+            tonic::Response<Self::MarketDataServerSideStreamStream>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct MarketDataStreamServiceServer<T: MarketDataStreamService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: MarketDataStreamService> MarketDataStreamServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>>
+    for MarketDataStreamServiceServer<T>
+    where
+        T: MarketDataStreamService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.MarketDataStreamService/MarketDataStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct MarketDataStreamSvc<T: MarketDataStreamService>(pub Arc<T>);
+                    impl<
+                        T: MarketDataStreamService,
+                    > tonic::server::StreamingService<super::MarketDataRequest>
+                    for MarketDataStreamSvc<T> {
+                        type Response = super::MarketDataResponse;
+                        type ResponseStream = T::MarketDataStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::MarketDataRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).market_data_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MarketDataStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.MarketDataStreamService/MarketDataServerSideStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct MarketDataServerSideStreamSvc<T: MarketDataStreamService>(
+                        pub Arc<T>,
+                    );
+                    impl<
+                        T: MarketDataStreamService,
+                    > tonic::server::ServerStreamingService<
+                        super::MarketDataServerSideStreamRequest,
+                    > for MarketDataServerSideStreamSvc<T> {
+                        type Response = super::MarketDataResponse;
+                        type ResponseStream = T::MarketDataServerSideStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::MarketDataServerSideStreamRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).market_data_server_side_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MarketDataServerSideStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: MarketDataStreamService> Clone for MarketDataStreamServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: MarketDataStreamService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: MarketDataStreamService> tonic::server::NamedService
+    for MarketDataStreamServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.MarketDataStreamService";
+    }
+}
 /// Запрос получения списка операций по счёту.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -6186,6 +8032,655 @@ pub mod operations_stream_service_client {
         }
     }
 }
+/// Generated server implementations.
+pub mod operations_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with OperationsServiceServer.
+    #[async_trait]
+    pub trait OperationsService: Send + Sync + 'static {
+        /// Метод получения списка операций по счёту.При работе с данным методом необходимо учитывать
+        /// [особенности взаимодействия](/investAPI/operations_problems) с данным методом.
+        async fn get_operations(
+            &self,
+            request: tonic::Request<super::OperationsRequest>,
+        ) -> Result<tonic::Response<super::OperationsResponse>, tonic::Status>;
+        /// Метод получения портфеля по счёту.
+        async fn get_portfolio(
+            &self,
+            request: tonic::Request<super::PortfolioRequest>,
+        ) -> Result<tonic::Response<super::PortfolioResponse>, tonic::Status>;
+        /// Метод получения списка позиций по счёту.
+        async fn get_positions(
+            &self,
+            request: tonic::Request<super::PositionsRequest>,
+        ) -> Result<tonic::Response<super::PositionsResponse>, tonic::Status>;
+        /// Метод получения доступного остатка для вывода средств.
+        async fn get_withdraw_limits(
+            &self,
+            request: tonic::Request<super::WithdrawLimitsRequest>,
+        ) -> Result<tonic::Response<super::WithdrawLimitsResponse>, tonic::Status>;
+        /// Метод получения брокерского отчёта.
+        async fn get_broker_report(
+            &self,
+            request: tonic::Request<super::BrokerReportRequest>,
+        ) -> Result<tonic::Response<super::BrokerReportResponse>, tonic::Status>;
+        /// Метод получения отчёта "Справка о доходах за пределами РФ".
+        async fn get_dividends_foreign_issuer(
+            &self,
+            request: tonic::Request<super::GetDividendsForeignIssuerRequest>,
+        ) -> Result<
+            tonic::Response<super::GetDividendsForeignIssuerResponse>,
+            tonic::Status,
+        >;
+        /// Метод получения списка операций по счёту с пагинацией. При работе с данным методом необходимо учитывать
+        /// [особенности взаимодействия](/investAPI/operations_problems) с данным методом.
+        async fn get_operations_by_cursor(
+            &self,
+            request: tonic::Request<super::GetOperationsByCursorRequest>,
+        ) -> Result<
+            tonic::Response<super::GetOperationsByCursorResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct OperationsServiceServer<T: OperationsService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: OperationsService> OperationsServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for OperationsServiceServer<T>
+    where
+        T: OperationsService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.OperationsService/GetOperations" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetOperationsSvc<T: OperationsService>(pub Arc<T>);
+                    impl<
+                        T: OperationsService,
+                    > tonic::server::UnaryService<super::OperationsRequest>
+                    for GetOperationsSvc<T> {
+                        type Response = super::OperationsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::OperationsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_operations(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetOperationsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OperationsService/GetPortfolio" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPortfolioSvc<T: OperationsService>(pub Arc<T>);
+                    impl<
+                        T: OperationsService,
+                    > tonic::server::UnaryService<super::PortfolioRequest>
+                    for GetPortfolioSvc<T> {
+                        type Response = super::PortfolioResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PortfolioRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_portfolio(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetPortfolioSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OperationsService/GetPositions" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPositionsSvc<T: OperationsService>(pub Arc<T>);
+                    impl<
+                        T: OperationsService,
+                    > tonic::server::UnaryService<super::PositionsRequest>
+                    for GetPositionsSvc<T> {
+                        type Response = super::PositionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PositionsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_positions(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetPositionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OperationsService/GetWithdrawLimits" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetWithdrawLimitsSvc<T: OperationsService>(pub Arc<T>);
+                    impl<
+                        T: OperationsService,
+                    > tonic::server::UnaryService<super::WithdrawLimitsRequest>
+                    for GetWithdrawLimitsSvc<T> {
+                        type Response = super::WithdrawLimitsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WithdrawLimitsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_withdraw_limits(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetWithdrawLimitsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OperationsService/GetBrokerReport" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBrokerReportSvc<T: OperationsService>(pub Arc<T>);
+                    impl<
+                        T: OperationsService,
+                    > tonic::server::UnaryService<super::BrokerReportRequest>
+                    for GetBrokerReportSvc<T> {
+                        type Response = super::BrokerReportResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BrokerReportRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_broker_report(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBrokerReportSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OperationsService/GetDividendsForeignIssuer" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDividendsForeignIssuerSvc<T: OperationsService>(
+                        pub Arc<T>,
+                    );
+                    impl<
+                        T: OperationsService,
+                    > tonic::server::UnaryService<
+                        super::GetDividendsForeignIssuerRequest,
+                    > for GetDividendsForeignIssuerSvc<T> {
+                        type Response = super::GetDividendsForeignIssuerResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::GetDividendsForeignIssuerRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_dividends_foreign_issuer(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDividendsForeignIssuerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OperationsService/GetOperationsByCursor" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetOperationsByCursorSvc<T: OperationsService>(pub Arc<T>);
+                    impl<
+                        T: OperationsService,
+                    > tonic::server::UnaryService<super::GetOperationsByCursorRequest>
+                    for GetOperationsByCursorSvc<T> {
+                        type Response = super::GetOperationsByCursorResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetOperationsByCursorRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_operations_by_cursor(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetOperationsByCursorSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: OperationsService> Clone for OperationsServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: OperationsService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: OperationsService> tonic::server::NamedService
+    for OperationsServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.OperationsService";
+    }
+}
+/// Generated server implementations.
+pub mod operations_stream_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with OperationsStreamServiceServer.
+    #[async_trait]
+    pub trait OperationsStreamService: Send + Sync + 'static {
+        /// Server streaming response type for the PortfolioStream method.
+        type PortfolioStreamStream: futures_core::Stream<
+                Item = Result<super::PortfolioStreamResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Server-side stream обновлений портфеля
+        async fn portfolio_stream(
+            &self,
+            request: tonic::Request<super::PortfolioStreamRequest>,
+        ) -> Result<tonic::Response<Self::PortfolioStreamStream>, tonic::Status>;
+        /// Server streaming response type for the PositionsStream method.
+        type PositionsStreamStream: futures_core::Stream<
+                Item = Result<super::PositionsStreamResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Server-side stream обновлений информации по изменению позиций портфеля
+        async fn positions_stream(
+            &self,
+            request: tonic::Request<super::PositionsStreamRequest>,
+        ) -> Result<tonic::Response<Self::PositionsStreamStream>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct OperationsStreamServiceServer<T: OperationsStreamService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: OperationsStreamService> OperationsStreamServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>>
+    for OperationsStreamServiceServer<T>
+    where
+        T: OperationsStreamService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.OperationsStreamService/PortfolioStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct PortfolioStreamSvc<T: OperationsStreamService>(pub Arc<T>);
+                    impl<
+                        T: OperationsStreamService,
+                    > tonic::server::ServerStreamingService<
+                        super::PortfolioStreamRequest,
+                    > for PortfolioStreamSvc<T> {
+                        type Response = super::PortfolioStreamResponse;
+                        type ResponseStream = T::PortfolioStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PortfolioStreamRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).portfolio_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PortfolioStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OperationsStreamService/PositionsStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct PositionsStreamSvc<T: OperationsStreamService>(pub Arc<T>);
+                    impl<
+                        T: OperationsStreamService,
+                    > tonic::server::ServerStreamingService<
+                        super::PositionsStreamRequest,
+                    > for PositionsStreamSvc<T> {
+                        type Response = super::PositionsStreamResponse;
+                        type ResponseStream = T::PositionsStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PositionsStreamRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).positions_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PositionsStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: OperationsStreamService> Clone for OperationsStreamServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: OperationsStreamService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: OperationsStreamService> tonic::server::NamedService
+    for OperationsStreamServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.OperationsStreamService";
+    }
+}
 /// Запрос установки соединения.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -6915,6 +9410,492 @@ pub mod orders_service_client {
         }
     }
 }
+/// Generated server implementations.
+pub mod orders_stream_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with OrdersStreamServiceServer.
+    #[async_trait]
+    pub trait OrdersStreamService: Send + Sync + 'static {
+        /// Server streaming response type for the TradesStream method.
+        type TradesStreamStream: futures_core::Stream<
+                Item = Result<super::TradesStreamResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Stream сделок пользователя
+        async fn trades_stream(
+            &self,
+            request: tonic::Request<super::TradesStreamRequest>,
+        ) -> Result<tonic::Response<Self::TradesStreamStream>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct OrdersStreamServiceServer<T: OrdersStreamService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: OrdersStreamService> OrdersStreamServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for OrdersStreamServiceServer<T>
+    where
+        T: OrdersStreamService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.OrdersStreamService/TradesStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct TradesStreamSvc<T: OrdersStreamService>(pub Arc<T>);
+                    impl<
+                        T: OrdersStreamService,
+                    > tonic::server::ServerStreamingService<super::TradesStreamRequest>
+                    for TradesStreamSvc<T> {
+                        type Response = super::TradesStreamResponse;
+                        type ResponseStream = T::TradesStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TradesStreamRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).trades_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TradesStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: OrdersStreamService> Clone for OrdersStreamServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: OrdersStreamService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: OrdersStreamService> tonic::server::NamedService
+    for OrdersStreamServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.OrdersStreamService";
+    }
+}
+/// Generated server implementations.
+pub mod orders_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with OrdersServiceServer.
+    #[async_trait]
+    pub trait OrdersService: Send + Sync + 'static {
+        /// Метод выставления заявки.
+        async fn post_order(
+            &self,
+            request: tonic::Request<super::PostOrderRequest>,
+        ) -> Result<tonic::Response<super::PostOrderResponse>, tonic::Status>;
+        /// Метод отмены биржевой заявки.
+        async fn cancel_order(
+            &self,
+            request: tonic::Request<super::CancelOrderRequest>,
+        ) -> Result<tonic::Response<super::CancelOrderResponse>, tonic::Status>;
+        /// Метод получения статуса торгового поручения.
+        async fn get_order_state(
+            &self,
+            request: tonic::Request<super::GetOrderStateRequest>,
+        ) -> Result<tonic::Response<super::OrderState>, tonic::Status>;
+        /// Метод получения списка активных заявок по счёту.
+        async fn get_orders(
+            &self,
+            request: tonic::Request<super::GetOrdersRequest>,
+        ) -> Result<tonic::Response<super::GetOrdersResponse>, tonic::Status>;
+        /// Метод изменения выставленной заявки.
+        async fn replace_order(
+            &self,
+            request: tonic::Request<super::ReplaceOrderRequest>,
+        ) -> Result<tonic::Response<super::PostOrderResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct OrdersServiceServer<T: OrdersService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: OrdersService> OrdersServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for OrdersServiceServer<T>
+    where
+        T: OrdersService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.OrdersService/PostOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct PostOrderSvc<T: OrdersService>(pub Arc<T>);
+                    impl<
+                        T: OrdersService,
+                    > tonic::server::UnaryService<super::PostOrderRequest>
+                    for PostOrderSvc<T> {
+                        type Response = super::PostOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PostOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).post_order(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PostOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OrdersService/CancelOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelOrderSvc<T: OrdersService>(pub Arc<T>);
+                    impl<
+                        T: OrdersService,
+                    > tonic::server::UnaryService<super::CancelOrderRequest>
+                    for CancelOrderSvc<T> {
+                        type Response = super::CancelOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).cancel_order(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OrdersService/GetOrderState" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetOrderStateSvc<T: OrdersService>(pub Arc<T>);
+                    impl<
+                        T: OrdersService,
+                    > tonic::server::UnaryService<super::GetOrderStateRequest>
+                    for GetOrderStateSvc<T> {
+                        type Response = super::OrderState;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetOrderStateRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_order_state(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetOrderStateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OrdersService/GetOrders" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetOrdersSvc<T: OrdersService>(pub Arc<T>);
+                    impl<
+                        T: OrdersService,
+                    > tonic::server::UnaryService<super::GetOrdersRequest>
+                    for GetOrdersSvc<T> {
+                        type Response = super::GetOrdersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetOrdersRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_orders(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetOrdersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.OrdersService/ReplaceOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReplaceOrderSvc<T: OrdersService>(pub Arc<T>);
+                    impl<
+                        T: OrdersService,
+                    > tonic::server::UnaryService<super::ReplaceOrderRequest>
+                    for ReplaceOrderSvc<T> {
+                        type Response = super::PostOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReplaceOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).replace_order(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReplaceOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: OrdersService> Clone for OrdersServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: OrdersService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: OrdersService> tonic::server::NamedService for OrdersServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.OrdersService";
+    }
+}
 /// Запрос получения счетов пользователя.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -7314,6 +10295,290 @@ pub mod users_service_client {
         }
     }
 }
+/// Generated server implementations.
+pub mod users_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with UsersServiceServer.
+    #[async_trait]
+    pub trait UsersService: Send + Sync + 'static {
+        /// Метод получения счетов пользователя.
+        async fn get_accounts(
+            &self,
+            request: tonic::Request<super::GetAccountsRequest>,
+        ) -> Result<tonic::Response<super::GetAccountsResponse>, tonic::Status>;
+        /// Расчёт маржинальных показателей по счёту.
+        async fn get_margin_attributes(
+            &self,
+            request: tonic::Request<super::GetMarginAttributesRequest>,
+        ) -> Result<tonic::Response<super::GetMarginAttributesResponse>, tonic::Status>;
+        /// Запрос тарифа пользователя.
+        async fn get_user_tariff(
+            &self,
+            request: tonic::Request<super::GetUserTariffRequest>,
+        ) -> Result<tonic::Response<super::GetUserTariffResponse>, tonic::Status>;
+        /// Метод получения информации о пользователе.
+        async fn get_info(
+            &self,
+            request: tonic::Request<super::GetInfoRequest>,
+        ) -> Result<tonic::Response<super::GetInfoResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct UsersServiceServer<T: UsersService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: UsersService> UsersServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for UsersServiceServer<T>
+    where
+        T: UsersService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.UsersService/GetAccounts" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAccountsSvc<T: UsersService>(pub Arc<T>);
+                    impl<
+                        T: UsersService,
+                    > tonic::server::UnaryService<super::GetAccountsRequest>
+                    for GetAccountsSvc<T> {
+                        type Response = super::GetAccountsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetAccountsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_accounts(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAccountsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.UsersService/GetMarginAttributes" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetMarginAttributesSvc<T: UsersService>(pub Arc<T>);
+                    impl<
+                        T: UsersService,
+                    > tonic::server::UnaryService<super::GetMarginAttributesRequest>
+                    for GetMarginAttributesSvc<T> {
+                        type Response = super::GetMarginAttributesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetMarginAttributesRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_margin_attributes(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetMarginAttributesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.UsersService/GetUserTariff" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetUserTariffSvc<T: UsersService>(pub Arc<T>);
+                    impl<
+                        T: UsersService,
+                    > tonic::server::UnaryService<super::GetUserTariffRequest>
+                    for GetUserTariffSvc<T> {
+                        type Response = super::GetUserTariffResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetUserTariffRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_user_tariff(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetUserTariffSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.UsersService/GetInfo" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetInfoSvc<T: UsersService>(pub Arc<T>);
+                    impl<
+                        T: UsersService,
+                    > tonic::server::UnaryService<super::GetInfoRequest>
+                    for GetInfoSvc<T> {
+                        type Response = super::GetInfoResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetInfoRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_info(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetInfoSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: UsersService> Clone for UsersServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: UsersService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: UsersService> tonic::server::NamedService for UsersServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.UsersService";
+    }
+}
 /// Запрос открытия счёта в песочнице.
 ///
 /// пустой запрос
@@ -7715,6 +10980,747 @@ pub mod sandbox_service_client {
         }
     }
 }
+/// Generated server implementations.
+pub mod sandbox_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with SandboxServiceServer.
+    #[async_trait]
+    pub trait SandboxService: Send + Sync + 'static {
+        /// Метод регистрации счёта в песочнице.
+        async fn open_sandbox_account(
+            &self,
+            request: tonic::Request<super::OpenSandboxAccountRequest>,
+        ) -> Result<tonic::Response<super::OpenSandboxAccountResponse>, tonic::Status>;
+        /// Метод получения счетов в песочнице.
+        async fn get_sandbox_accounts(
+            &self,
+            request: tonic::Request<super::GetAccountsRequest>,
+        ) -> Result<tonic::Response<super::GetAccountsResponse>, tonic::Status>;
+        /// Метод закрытия счёта в песочнице.
+        async fn close_sandbox_account(
+            &self,
+            request: tonic::Request<super::CloseSandboxAccountRequest>,
+        ) -> Result<tonic::Response<super::CloseSandboxAccountResponse>, tonic::Status>;
+        /// Метод выставления торгового поручения в песочнице.
+        async fn post_sandbox_order(
+            &self,
+            request: tonic::Request<super::PostOrderRequest>,
+        ) -> Result<tonic::Response<super::PostOrderResponse>, tonic::Status>;
+        /// Метод изменения выставленной заявки.
+        async fn replace_sandbox_order(
+            &self,
+            request: tonic::Request<super::ReplaceOrderRequest>,
+        ) -> Result<tonic::Response<super::PostOrderResponse>, tonic::Status>;
+        /// Метод получения списка активных заявок по счёту в песочнице.
+        async fn get_sandbox_orders(
+            &self,
+            request: tonic::Request<super::GetOrdersRequest>,
+        ) -> Result<tonic::Response<super::GetOrdersResponse>, tonic::Status>;
+        /// Метод отмены торгового поручения в песочнице.
+        async fn cancel_sandbox_order(
+            &self,
+            request: tonic::Request<super::CancelOrderRequest>,
+        ) -> Result<tonic::Response<super::CancelOrderResponse>, tonic::Status>;
+        /// Метод получения статуса заявки в песочнице.
+        async fn get_sandbox_order_state(
+            &self,
+            request: tonic::Request<super::GetOrderStateRequest>,
+        ) -> Result<tonic::Response<super::OrderState>, tonic::Status>;
+        /// Метод получения позиций по виртуальному счёту песочницы.
+        async fn get_sandbox_positions(
+            &self,
+            request: tonic::Request<super::PositionsRequest>,
+        ) -> Result<tonic::Response<super::PositionsResponse>, tonic::Status>;
+        /// Метод получения операций в песочнице по номеру счёта.
+        async fn get_sandbox_operations(
+            &self,
+            request: tonic::Request<super::OperationsRequest>,
+        ) -> Result<tonic::Response<super::OperationsResponse>, tonic::Status>;
+        /// Метод получения операций в песочнице по номеру счета с пагинацией.
+        async fn get_sandbox_operations_by_cursor(
+            &self,
+            request: tonic::Request<super::GetOperationsByCursorRequest>,
+        ) -> Result<
+            tonic::Response<super::GetOperationsByCursorResponse>,
+            tonic::Status,
+        >;
+        /// Метод получения портфолио в песочнице.
+        async fn get_sandbox_portfolio(
+            &self,
+            request: tonic::Request<super::PortfolioRequest>,
+        ) -> Result<tonic::Response<super::PortfolioResponse>, tonic::Status>;
+        /// Метод пополнения счёта в песочнице.
+        async fn sandbox_pay_in(
+            &self,
+            request: tonic::Request<super::SandboxPayInRequest>,
+        ) -> Result<tonic::Response<super::SandboxPayInResponse>, tonic::Status>;
+        /// Метод получения доступного остатка для вывода средств в песочнице.
+        async fn get_sandbox_withdraw_limits(
+            &self,
+            request: tonic::Request<super::WithdrawLimitsRequest>,
+        ) -> Result<tonic::Response<super::WithdrawLimitsResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct SandboxServiceServer<T: SandboxService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: SandboxService> SandboxServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for SandboxServiceServer<T>
+    where
+        T: SandboxService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/OpenSandboxAccount" => {
+                    #[allow(non_camel_case_types)]
+                    struct OpenSandboxAccountSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::OpenSandboxAccountRequest>
+                    for OpenSandboxAccountSvc<T> {
+                        type Response = super::OpenSandboxAccountResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::OpenSandboxAccountRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).open_sandbox_account(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = OpenSandboxAccountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxAccounts" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxAccountsSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::GetAccountsRequest>
+                    for GetSandboxAccountsSvc<T> {
+                        type Response = super::GetAccountsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetAccountsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_accounts(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxAccountsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/CloseSandboxAccount" => {
+                    #[allow(non_camel_case_types)]
+                    struct CloseSandboxAccountSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::CloseSandboxAccountRequest>
+                    for CloseSandboxAccountSvc<T> {
+                        type Response = super::CloseSandboxAccountResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CloseSandboxAccountRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).close_sandbox_account(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CloseSandboxAccountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/PostSandboxOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct PostSandboxOrderSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::PostOrderRequest>
+                    for PostSandboxOrderSvc<T> {
+                        type Response = super::PostOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PostOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).post_sandbox_order(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PostSandboxOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/ReplaceSandboxOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReplaceSandboxOrderSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::ReplaceOrderRequest>
+                    for ReplaceSandboxOrderSvc<T> {
+                        type Response = super::PostOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReplaceOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).replace_sandbox_order(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReplaceSandboxOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxOrders" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxOrdersSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::GetOrdersRequest>
+                    for GetSandboxOrdersSvc<T> {
+                        type Response = super::GetOrdersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetOrdersRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_orders(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxOrdersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/CancelSandboxOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelSandboxOrderSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::CancelOrderRequest>
+                    for CancelSandboxOrderSvc<T> {
+                        type Response = super::CancelOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).cancel_sandbox_order(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelSandboxOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxOrderState" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxOrderStateSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::GetOrderStateRequest>
+                    for GetSandboxOrderStateSvc<T> {
+                        type Response = super::OrderState;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetOrderStateRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_order_state(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxOrderStateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxPositions" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxPositionsSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::PositionsRequest>
+                    for GetSandboxPositionsSvc<T> {
+                        type Response = super::PositionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PositionsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_positions(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxPositionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxOperations" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxOperationsSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::OperationsRequest>
+                    for GetSandboxOperationsSvc<T> {
+                        type Response = super::OperationsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::OperationsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_operations(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxOperationsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxOperationsByCursor" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxOperationsByCursorSvc<T: SandboxService>(
+                        pub Arc<T>,
+                    );
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::GetOperationsByCursorRequest>
+                    for GetSandboxOperationsByCursorSvc<T> {
+                        type Response = super::GetOperationsByCursorResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetOperationsByCursorRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_operations_by_cursor(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxOperationsByCursorSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxPortfolio" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxPortfolioSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::PortfolioRequest>
+                    for GetSandboxPortfolioSvc<T> {
+                        type Response = super::PortfolioResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PortfolioRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_portfolio(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxPortfolioSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/SandboxPayIn" => {
+                    #[allow(non_camel_case_types)]
+                    struct SandboxPayInSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::SandboxPayInRequest>
+                    for SandboxPayInSvc<T> {
+                        type Response = super::SandboxPayInResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SandboxPayInRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).sandbox_pay_in(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SandboxPayInSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.SandboxService/GetSandboxWithdrawLimits" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSandboxWithdrawLimitsSvc<T: SandboxService>(pub Arc<T>);
+                    impl<
+                        T: SandboxService,
+                    > tonic::server::UnaryService<super::WithdrawLimitsRequest>
+                    for GetSandboxWithdrawLimitsSvc<T> {
+                        type Response = super::WithdrawLimitsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WithdrawLimitsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_sandbox_withdraw_limits(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSandboxWithdrawLimitsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: SandboxService> Clone for SandboxServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: SandboxService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: SandboxService> tonic::server::NamedService for SandboxServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.SandboxService";
+    }
+}
 /// Запрос выставления стоп-заявки.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -8074,3 +12080,245 @@ pub mod stop_orders_service_client {
         }
     }
 }
+/// Generated server implementations.
+pub mod stop_orders_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with StopOrdersServiceServer.
+    #[async_trait]
+    pub trait StopOrdersService: Send + Sync + 'static {
+        /// Метод выставления стоп-заявки.
+        async fn post_stop_order(
+            &self,
+            request: tonic::Request<super::PostStopOrderRequest>,
+        ) -> Result<tonic::Response<super::PostStopOrderResponse>, tonic::Status>;
+        /// Метод получения списка активных стоп заявок по счёту.
+        async fn get_stop_orders(
+            &self,
+            request: tonic::Request<super::GetStopOrdersRequest>,
+        ) -> Result<tonic::Response<super::GetStopOrdersResponse>, tonic::Status>;
+        /// Метод отмены стоп-заявки.
+        async fn cancel_stop_order(
+            &self,
+            request: tonic::Request<super::CancelStopOrderRequest>,
+        ) -> Result<tonic::Response<super::CancelStopOrderResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct StopOrdersServiceServer<T: StopOrdersService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: StopOrdersService> StopOrdersServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for StopOrdersServiceServer<T>
+    where
+        T: StopOrdersService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/tinkoff.public.invest.api.contract.v1.StopOrdersService/PostStopOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct PostStopOrderSvc<T: StopOrdersService>(pub Arc<T>);
+                    impl<
+                        T: StopOrdersService,
+                    > tonic::server::UnaryService<super::PostStopOrderRequest>
+                    for PostStopOrderSvc<T> {
+                        type Response = super::PostStopOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PostStopOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).post_stop_order(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PostStopOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.StopOrdersService/GetStopOrders" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetStopOrdersSvc<T: StopOrdersService>(pub Arc<T>);
+                    impl<
+                        T: StopOrdersService,
+                    > tonic::server::UnaryService<super::GetStopOrdersRequest>
+                    for GetStopOrdersSvc<T> {
+                        type Response = super::GetStopOrdersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetStopOrdersRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_stop_orders(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetStopOrdersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/tinkoff.public.invest.api.contract.v1.StopOrdersService/CancelStopOrder" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelStopOrderSvc<T: StopOrdersService>(pub Arc<T>);
+                    impl<
+                        T: StopOrdersService,
+                    > tonic::server::UnaryService<super::CancelStopOrderRequest>
+                    for CancelStopOrderSvc<T> {
+                        type Response = super::CancelStopOrderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelStopOrderRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).cancel_stop_order(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelStopOrderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: StopOrdersService> Clone for StopOrdersServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: StopOrdersService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: StopOrdersService> tonic::server::NamedService
+    for StopOrdersServiceServer<T> {
+        const NAME: &'static str = "tinkoff.public.invest.api.contract.v1.StopOrdersService";
+    }
+}
@@ -1,63 +1,97 @@
 use std::sync::Arc;
-use tokio::sync::{
-    Mutex,
-    mpsc::{Sender, Receiver, channel}
-};
+use futures::future::BoxFuture;
+use tokio::sync::{Mutex, mpsc::Sender};
 use teloxide::{
-    prelude::*, RequestError,
+    prelude::*,
     utils::command::BotCommands,
-    dispatching::DefaultKey,
-    dptree::deps
+    dptree::deps,
+    types::InputFile
 };
+use tracing::warn;
 
-static mut MY_CHAT_ID: ChatId = ChatId(0);
+use crate::access::{audit, Access, Role};
+use crate::credentials::Secret;
+use crate::notify::{Sink, SinkError};
+use crate::chart::ChartKind;
+use crate::control::{Control, Request, RequestType};
 
-#[derive(Debug)]
-pub enum RequestType {
-    Something = 3,
-    StateRequest = 2,
-    StatRequest = 1,
-    StopRequest = 0,
+/// Telegram commands and notifications, see `Access` for who may use them.
+pub struct TelegramControl {
+    bot: Bot,
+    access: Arc<Access>,
 }
 
-
-pub async fn start() -> (Dispatcher<Bot, RequestError, DefaultKey>, Receiver<RequestType>, Bot) {
-    use std::str::FromStr;
-
-    unsafe {
-        MY_CHAT_ID = ChatId(i64::from_str(std::env::var("TG_CHAT_ID").unwrap().as_str()).unwrap())
+impl TelegramControl {
+    pub fn new(token: &Secret, access: Access) -> TelegramControl {
+        TelegramControl { bot: Bot::new(token.expose()), access: Arc::new(access) }
     }
-    pretty_env_logger::init();
-
-    let (tx, rx): (Sender<RequestType>, Receiver<RequestType>) = channel(10);
+}
 
-    let dmap = deps![Arc::new(Mutex::new(tx))];
+impl Control for TelegramControl {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+    fn sinks(&self) -> Vec<Box<dyn Sink>> {
+        self.access.notify_chats.iter()
+            .map(|&id| Box::new(TelegramSink { bot: self.bot.clone(), chat: ChatId(id) }) as Box<dyn Sink>)
+            .collect()
+    }
+    fn run(&self, tx: Sender<Request>) -> BoxFuture<'static, ()> {
+        let dmap = deps![Arc::new(Mutex::new(tx)), self.access.clone()];
 
-    let bot = Bot::from_env();
-    let command_handler = teloxide::filter_command::<Command, _>().endpoint(answer);
-    let message_handler = Update::filter_message()
-        .branch(command_handler)
-        .branch(dptree::endpoint(get_message));
-    (
-        Dispatcher::builder(bot.clone(), message_handler)
+        let command_handler = teloxide::filter_command::<Command, _>().endpoint(answer);
+        let message_handler = Update::filter_message()
+            .branch(command_handler)
+            .branch(dptree::endpoint(get_message));
+        let mut dispatcher = Dispatcher::builder(self.bot.clone(), message_handler)
             .dependencies(dmap)
-            .build(),
-        rx,
-        bot
-    )
+            .build();
+        Box::pin(async move { dispatcher.dispatch().await })
+    }
 }
 
-pub async fn send_message(bot: Arc<Bot>, message: String) {
-    let _ = bot.send_message(unsafe { MY_CHAT_ID }, message).await;
+/// Sends notifications to one of the chats from `Access::notify_chats`,
+/// or the answers to the chat a command came from.
+pub struct TelegramSink {
+    bot: Bot,
+    chat: ChatId,
 }
 
-async fn get_message(_: Bot, _: Message, tx: Arc<Mutex<Sender<RequestType>>>)
-    -> ResponseResult<()> {
-    tx.lock().await.send(RequestType::Something).await.unwrap();
+impl Sink for TelegramSink {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+    fn send<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<(), SinkError>> {
+        Box::pin(async move {
+            self.bot.send_message(self.chat, text).await?;
+            Ok(())
+        })
+    }
+    fn send_image<'a>(&'a self, caption: &'a str, png: &'a [u8])
+        -> BoxFuture<'a, Result<(), SinkError>> {
+        Box::pin(async move {
+            self.bot.send_photo(self.chat, InputFile::memory(png.to_vec()).file_name("chart.png"))
+                .caption(caption)
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+async fn get_message(bot: Bot, msg: Message, access: Arc<Access>) -> ResponseResult<()> {
+    // chatter in groups is not for us
+    if !msg.chat.is_private() {
+        return Ok(());
+    }
+    if let Some(user) = msg.from() {
+        if access.role(user.id.0).is_some() {
+            bot.send_message(msg.chat.id, Command::descriptions().to_string()).await?;
+        }
+    }
     Ok(())
 }
 
-#[derive(BotCommands, Clone)]
+#[derive(BotCommands, Clone, Debug)]
 #[command(rename_rule = "lowercase", description = "Поддерживаемые команды:")]
 pub enum Command {
     #[command(description = "описание команд")]
@@ -66,31 +100,73 @@ pub enum Command {
     State,
     #[command(description = "статистика торговли")]
     Stat,
+    #[command(description = "не открывать новые позиции")]
+    Pause,
+    #[command(description = "продолжить торговлю")]
+    Resume,
+    #[command(description = "закрыть позицию по рынку и поставить на паузу")]
+    Flatten,
+    #[command(description = "графики: price (по умолчанию), equity или pnl")]
+    Chart(String),
+    #[command(description = "текущие настройки")]
+    Config,
     #[command(description = "остановить работу бота")]
     Stop,
 }
 
-pub async fn answer(bot: Bot, msg: Message, cmd: Command, tx: Arc<Mutex<Sender<RequestType>>>)
+impl Command {
+    pub fn required_role(&self) -> Role {
+        match self {
+            Command::Help | Command::State | Command::Stat | Command::Chart(_) => Role::Viewer,
+            Command::Pause | Command::Resume | Command::Flatten => Role::Trader,
+            Command::Config | Command::Stop => Role::Admin,
+        }
+    }
+}
+
+pub async fn answer(bot: Bot, msg: Message, cmd: Command,
+                    tx: Arc<Mutex<Sender<Request>>>, access: Arc<Access>)
     -> ResponseResult<()> {
-    if msg.chat.id != unsafe { MY_CHAT_ID } {
+    let Some(user) = msg.from() else {
+        return Ok(())
+    };
+    let allowed = access.is_allowed(user.id.0, cmd.required_role());
+    audit(user.id.0, user.username.as_deref().unwrap_or(""), msg.chat.id.0,
+          msg.text().unwrap_or(""), allowed);
+    if !allowed {
+        // strangers get no answer at all
+        if access.role(user.id.0).is_some() {
+            bot.send_message(msg.chat.id, "Недостаточно прав").await?;
+        }
         return Ok(())
     }
-    match cmd {
+    let (request, reply) = match cmd {
         Command::Help => {
-            bot.send_message(msg.chat.id, Command::descriptions().to_string()).await?
+            bot.send_message(msg.chat.id, Command::descriptions().to_string()).await?;
+            return Ok(())
         },
-        Command::State => {
-            tx.lock().await.send(RequestType::StateRequest).await.unwrap();
-            bot.send_message(msg.chat.id, format!("Запрашиваю состояние портфеля")).await?
+        Command::State => (RequestType::StateRequest, "Запрашиваю состояние портфеля"),
+        Command::Stat => (RequestType::StatRequest, "Запрашиваю статистику"),
+        Command::Pause => (RequestType::PauseRequest, "Ставлю на паузу"),
+        Command::Resume => (RequestType::ResumeRequest, "Продолжаю торговлю"),
+        Command::Flatten => (RequestType::FlattenRequest, "Закрываю позицию"),
+        Command::Chart(arg) => match ChartKind::parse(&arg) {
+            Some(kind) => (RequestType::ChartRequest(kind), "Рисую график"),
+            None => {
+                bot.send_message(msg.chat.id, "Графики: /chart price, /chart equity, /chart pnl").await?;
+                return Ok(())
+            }
         },
-        Command::Stat => {
-            tx.lock().await.send(RequestType::StatRequest).await.unwrap();
-            bot.send_message(msg.chat.id, format!("Запрашиваю статистику")).await?
-        }
-        Command::Stop => {
-            tx.lock().await.send(RequestType::StopRequest).await.unwrap();
-            bot.send_message(msg.chat.id, format!("Запрашиваю остановку")).await?
-        }
+        Command::Config => (RequestType::ConfigRequest, "Запрашиваю настройки"),
+        Command::Stop => (RequestType::StopRequest, "Запрашиваю остановку"),
     };
+    let chat = Arc::new(TelegramSink { bot: bot.clone(), chat: msg.chat.id });
+    // the bot is gone on shutdown or after a fatal error
+    if tx.lock().await.send(Request::new(request, chat)).await.is_err() {
+        warn!(command = msg.text().unwrap_or(""), "the bot is stopped, the request is dropped");
+        bot.send_message(msg.chat.id, "Бот остановлен").await?;
+        return Ok(())
+    }
+    bot.send_message(msg.chat.id, reply).await?;
     Ok(())
-}
\ No newline at end of file
+}